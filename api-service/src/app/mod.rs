@@ -1,9 +1,11 @@
 pub mod callback;
 pub mod config;
 pub mod middleware;
+pub mod operator;
 pub mod response;
 pub mod router;
 pub mod server;
+pub mod session;
 pub mod state;
 pub mod swagger;
 
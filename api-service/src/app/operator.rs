@@ -0,0 +1,54 @@
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use std::convert::Infallible;
+
+// 前端通过该请求头传递操作人，值需要经过URL编码
+pub const OPERATOR_HEADER: &str = "x-operator";
+const UNKNOWN_OPERATOR: &str = "unknown";
+
+//当前请求的操作人，未传递时为unknown
+#[derive(Debug, Clone)]
+pub struct Operator(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Operator
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let operator = parts
+            .headers
+            .get(OPERATOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(percent_decode)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| UNKNOWN_OPERATOR.to_string());
+        Ok(Operator(operator))
+    }
+}
+
+//请求头只能包含ASCII，中文姓名由前端URL编码后传递
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            if let Some(byte) = value
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                result.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        result.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
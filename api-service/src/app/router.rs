@@ -7,6 +7,13 @@ pub fn get_all_routers(app_state: AppState) -> Router {
         .merge(crate::knowledge::get_routers(app_state.clone()))
         .merge(crate::file::get_routers(app_state.clone()))
         .merge(crate::pe::get_routers(app_state.clone()))
+        .merge(crate::ioc::get_routers(app_state.clone()))
+        .merge(crate::rule::get_routers(app_state.clone()))
+        .merge(crate::capability::get_routers(app_state.clone()))
+        .merge(crate::attack::get_routers(app_state.clone()))
+        .merge(crate::risk::get_routers(app_state.clone()))
+        .merge(crate::hunt::get_routers(app_state.clone()))
+        .merge(crate::hash_list::get_routers(app_state.clone()))
         .merge(crate::tools::routers(app_state.clone()));
     log::info!("Successfully obtained all routing information");
     router
@@ -0,0 +1,36 @@
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+
+use crate::app::response::DefaultResponse;
+
+// 前端为每个浏览器页面生成一个会话ID，通过该请求头传递
+pub const SESSION_HEADER: &str = "x-session-id";
+const MAX_SESSION_ID_LEN: usize = 128;
+
+//当前请求的编辑会话ID，未传递时拒绝请求
+#[derive(Debug, Clone)]
+pub struct SessionId(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for SessionId
+where
+    S: Send + Sync,
+{
+    type Rejection = DefaultResponse;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty() && value.len() <= MAX_SESSION_ID_LEN)
+            .map(SessionId)
+            .ok_or_else(|| {
+                DefaultResponse::error()
+                    .code(400)
+                    .msg("缺少会话标识，请刷新页面后重试!".to_string())
+            })
+    }
+}
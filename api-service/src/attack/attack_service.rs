@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use entity::model::t_attack_technique;
+use migration::sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DataResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::tools::attack::{self, TechniqueInfo};
+
+//加载ATT&CK技术数据，key为技术编号
+pub async fn load_techniques(db: &DatabaseConnection) -> HashMap<String, TechniqueInfo> {
+    t_attack_technique::Entity::find()
+        .all(db)
+        .await
+        .unwrap_or_else(|err| {
+            log::error!("get attack technique list error: {}", err);
+            Vec::new()
+        })
+        .into_iter()
+        .map(|model| {
+            (
+                model.technique_id,
+                TechniqueInfo {
+                    name: model.technique_name,
+                    tactics: attack::split_tags(Some(&model.tactics)),
+                },
+            )
+        })
+        .collect()
+}
+
+pub async fn tactic_list() -> impl IntoResponse {
+    DataResponse::success(attack::TACTICS.to_vec())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TechniquePageListParam {
+    page: u64,
+    size: u64,
+    // 按编号或名称模糊查询
+    keyword: Option<String>,
+    tactic: Option<String>,
+}
+pub async fn technique_page_list(
+    app_state: State<AppState>,
+    Query(param): Query<TechniquePageListParam>,
+) -> impl IntoResponse {
+    let mut select = t_attack_technique::Entity::find();
+    if let Some(keyword) = param.keyword {
+        select = select.filter(
+            Condition::any()
+                .add(t_attack_technique::Column::TechniqueId.like(format!("%{}%", &keyword)))
+                .add(t_attack_technique::Column::TechniqueName.like(format!("%{}%", &keyword))),
+        );
+    }
+    if let Some(tactic) = param.tactic {
+        select = select.filter(t_attack_technique::Column::Tactics.like(format!("%{}%", &tactic)));
+    }
+    select = select.order_by_asc(t_attack_technique::Column::TechniqueId);
+    let paginate = select.paginate(app_state.db_conn.as_ref(), param.size);
+    let total = paginate.num_items().await.unwrap_or_else(|err| {
+        log::error!("get attack technique total num error: {}", err);
+        0
+    });
+    let pages = paginate.num_pages().await.unwrap_or(0);
+    if total == 0 {
+        return PaginateResponse::success(Vec::new(), PaginateInfo::default());
+    }
+    let data = paginate.fetch_page(param.page).await.unwrap_or_else(|err| {
+        log::error!("find attack technique page list error: {}", err);
+        vec![]
+    });
+    PaginateResponse::success(data, PaginateInfo { total, pages })
+}
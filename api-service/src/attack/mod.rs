@@ -0,0 +1,19 @@
+use axum::routing::get;
+use axum::Router;
+
+use crate::app::state::AppState;
+
+pub mod attack_service;
+
+pub fn get_routers(app_state: AppState) -> Router {
+    Router::new().nest(
+        "/attack",
+        Router::new()
+            .route("/tactic_list", get(attack_service::tactic_list))
+            .route(
+                "/technique_page_list",
+                get(attack_service::technique_page_list),
+            )
+            .with_state(app_state),
+    )
+}
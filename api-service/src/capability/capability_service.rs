@@ -0,0 +1,252 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use migration::sea_orm::ActiveValue::Set;
+use migration::sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder,
+};
+
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::capability::CAPABILITY_RULES;
+use crate::pe::pe_service::find_file_by_id;
+use crate::tools::attack;
+use crate::tools::capability::{self, CapabilityRule, CapabilityScope};
+use crate::tools::pe_image::PeImage;
+
+//获取已解析的全部能力规则，缓存为空时从数据库重新加载，无法解析的规则会被跳过
+pub async fn load_rules(db: &DatabaseConnection) -> Arc<Vec<CapabilityRule>> {
+    if let Some(rules) = CAPABILITY_RULES.read().await.as_ref() {
+        return rules.clone();
+    }
+    let mut cache = CAPABILITY_RULES.write().await;
+    if let Some(rules) = cache.as_ref() {
+        return rules.clone();
+    }
+    let models = match entity::model::t_capability::Entity::find()
+        .order_by_asc(entity::model::t_capability::Column::TechniqueId)
+        .all(db)
+        .await
+    {
+        Ok(data) => data,
+        Err(err) => {
+            // 查询失败时不缓存，下次检测重新加载
+            log::error!("get capability list error: {}", err);
+            return Arc::new(Vec::new());
+        }
+    };
+    let rules = models
+        .into_iter()
+        .filter_map(|model| {
+            let condition = serde_json::from_value(model.rule_content)
+                .map_err(|err| log::error!("parse capability rule error: {} [{}]", err, model.id))
+                .ok()?;
+            Some(CapabilityRule {
+                name: model.cap_name,
+                technique_id: model.technique_id,
+                description: model.cap_desc,
+                scope: CapabilityScope::parse(&model.scope).unwrap_or(CapabilityScope::File),
+                condition,
+            })
+        })
+        .collect::<Vec<_>>();
+    let rules = Arc::new(rules);
+    *cache = Some(rules.clone());
+    rules
+}
+
+//能力规则修改后清空缓存，下次检测时重新加载
+pub async fn invalidate_rules() {
+    *CAPABILITY_RULES.write().await = None;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveParam {
+    pub id: Option<String>,
+    pub name: String,
+    pub technique_id: String,
+    pub desc: Option<String>,
+    pub scope: String,
+    pub rule: serde_json::Value,
+}
+pub async fn save(app_state: State<AppState>, Json(param): Json<SaveParam>) -> impl IntoResponse {
+    if param.name.is_empty() {
+        return DefaultResponse::error().msg("能力名称不能为空!".to_string());
+    }
+    if !attack::is_valid_technique_id(&param.technique_id) {
+        return DefaultResponse::error()
+            .msg("ATT&CK技术编号格式错误, 例如 T1055 或 T1055.012!".to_string());
+    }
+    if CapabilityScope::parse(&param.scope).is_none() {
+        return DefaultResponse::error().msg("范围只能为 file 或 function!".to_string());
+    }
+    let condition = match serde_json::from_value::<capability::Condition>(param.rule.clone()) {
+        Ok(data) => data,
+        Err(err) => return DefaultResponse::error().msg(format!("规则条件格式错误: {}", err)),
+    };
+    if let Err(err) = capability::validate_condition(&condition) {
+        return DefaultResponse::error().msg(format!("规则条件格式错误: {}", err));
+    }
+    let active_model = match param.id {
+        None => {
+            let new_id = uuid::Uuid::new_v4().simple().to_string();
+            entity::model::t_capability::ActiveModel {
+                id: Set(new_id),
+                cap_name: Set(param.name),
+                technique_id: Set(param.technique_id),
+                cap_desc: Set(param.desc),
+                scope: Set(param.scope),
+                rule_content: Set(param.rule),
+                create_time: Set(chrono::Local::now().naive_local()),
+                modify_time: Set(chrono::Local::now().naive_local()),
+            }
+        }
+        Some(ref id) => {
+            match entity::model::t_capability::Entity::find_by_id(id)
+                .one(app_state.db_conn.as_ref())
+                .await
+            {
+                Ok(data) => match data {
+                    None => {
+                        return DefaultResponse::error()
+                            .msg("数据不存在, 请确认后再试!".to_string())
+                    }
+                    Some(data) => {
+                        let mut active_model = data.into_active_model();
+                        active_model.modify_time = Set(chrono::Local::now().naive_local());
+                        active_model.cap_name = Set(param.name);
+                        active_model.technique_id = Set(param.technique_id);
+                        active_model.cap_desc = Set(param.desc);
+                        active_model.scope = Set(param.scope);
+                        active_model.rule_content = Set(param.rule);
+                        active_model
+                    }
+                },
+                Err(err) => {
+                    log::error!("find capability by id error: {}", err);
+                    return DefaultResponse::error().msg("数据查询错误, 请稍后再试!".to_string());
+                }
+            }
+        }
+    };
+    let result = match param.id {
+        None => active_model.insert(app_state.db_conn.as_ref()).await,
+        Some(_) => active_model.update(app_state.db_conn.as_ref()).await,
+    };
+    match result {
+        Ok(_) => {
+            invalidate_rules().await;
+            DefaultResponse::success()
+        }
+        Err(err) => {
+            log::error!("保存能力规则失败, error: {}", err);
+            DefaultResponse::error().msg("保存数据失败, 请确认数据后重试!".to_string())
+        }
+    }
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteParam {
+    ids: Vec<String>,
+}
+pub async fn delete(
+    app_state: State<AppState>,
+    Json(param): Json<DeleteParam>,
+) -> impl IntoResponse {
+    if param.ids.is_empty() {
+        return DefaultResponse::success();
+    }
+    let result = entity::model::t_capability::Entity::delete_many()
+        .filter(entity::model::t_capability::Column::Id.is_in(param.ids))
+        .exec(app_state.db_conn.as_ref())
+        .await;
+    match result {
+        Ok(_) => {
+            invalidate_rules().await;
+            DefaultResponse::success()
+        }
+        Err(err) => {
+            log::error!("delete capability error: {}", err);
+            DefaultResponse::error().msg("删除失败，请重试!".to_string())
+        }
+    }
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageListParam {
+    page: u64,
+    size: u64,
+    // 按名称或技术编号模糊查询
+    name: Option<String>,
+}
+pub async fn page_list(
+    app_state: State<AppState>,
+    Query(param): Query<PageListParam>,
+) -> impl IntoResponse {
+    let mut select = entity::model::t_capability::Entity::find();
+    if let Some(name) = param.name {
+        select = select.filter(
+            Condition::any()
+                .add(entity::model::t_capability::Column::CapName.like(format!("%{}%", &name)))
+                .add(entity::model::t_capability::Column::TechniqueId.like(format!("%{}%", &name))),
+        );
+    }
+    select = select.order_by_desc(entity::model::t_capability::Column::ModifyTime);
+    let paginate = select.paginate(app_state.db_conn.as_ref(), param.size);
+    let total = paginate.num_items().await.unwrap_or_else(|err| {
+        log::error!("get capability total num error: {}", err);
+        0
+    });
+    let pages = paginate.num_pages().await.unwrap_or(0);
+    if total == 0 {
+        return PaginateResponse::success(Vec::new(), PaginateInfo::default());
+    }
+    let data = paginate.fetch_page(param.page).await.unwrap_or_else(|err| {
+        log::error!("find capability page list error: {}", err);
+        vec![]
+    });
+    PaginateResponse::success(data, PaginateInfo { total, pages })
+}
+
+pub async fn info(app_state: State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
+    match entity::model::t_capability::Entity::find_by_id(id)
+        .one(app_state.db_conn.as_ref())
+        .await
+    {
+        Ok(data) => match data {
+            None => DefaultResponse::error()
+                .msg("数据不存在, 请检查后重试!".to_string())
+                .into_response(),
+            Some(data) => DataResponse::success(data).into_response(),
+        },
+        Err(err) => {
+            log::error!("find capability by id error: {}", err);
+            DefaultResponse::error().into_response()
+        }
+    }
+}
+
+pub async fn detect(app_state: State<AppState>, Path(file_id): Path<String>) -> impl IntoResponse {
+    let file_model = match find_file_by_id(&app_state, &file_id).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    let rules = load_rules(app_state.db_conn.as_ref()).await;
+    // 反汇编与字符串提取耗时较长，放到阻塞线程中执行
+    let result = tokio::task::spawn_blocking(move || {
+        let image = PeImage::parse(&file_model.file_buf).ok();
+        capability::detect_capabilities(image.as_ref(), &file_model.file_buf, &rules)
+    })
+    .await;
+    match result {
+        Ok(hits) => DataResponse::success(hits).into_response(),
+        Err(err) => {
+            log::error!("detect capability task error: {} [{}]", err, file_id);
+            DefaultResponse::error()
+                .msg("能力检测失败，请重试!".to_string())
+                .into_response()
+        }
+    }
+}
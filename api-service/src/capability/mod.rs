@@ -0,0 +1,26 @@
+use axum::routing::{get, post};
+use axum::Router;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::app::state::AppState;
+use crate::tools::capability::CapabilityRule;
+
+pub mod capability_service;
+
+pub static CAPABILITY_RULES: Lazy<RwLock<Option<Arc<Vec<CapabilityRule>>>>> =
+    Lazy::new(|| RwLock::new(None));
+
+pub fn get_routers(app_state: AppState) -> Router {
+    Router::new().nest(
+        "/capability",
+        Router::new()
+            .route("/save", post(capability_service::save))
+            .route("/delete", post(capability_service::delete))
+            .route("/page_list", get(capability_service::page_list))
+            .route("/info/:id", get(capability_service::info))
+            .route("/detect/:file_id", get(capability_service::detect))
+            .with_state(app_state),
+    )
+}
@@ -1,10 +1,15 @@
-use crate::app::response::{DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
 use crate::app::state::AppState;
+use crate::ioc::ioc_service;
+use crate::pe::file_analysis;
+use std::collections::HashMap;
+
 use axum::extract::{Path, Query, State};
 use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
 use axum::http::{HeaderMap, HeaderValue};
 use axum::response::IntoResponse;
 use axum::Json;
+use entity::model::t_file;
 use migration::sea_orm;
 use migration::sea_orm::prelude::DateTime;
 use migration::sea_orm::{
@@ -29,6 +34,8 @@ pub async fn page_list(
         file_name: String,
         file_md5: String,
         has_report: bool,
+        parent_id: Option<String>,
+        version: i32,
         create_time: DateTime,
         modify_time: DateTime,
     }
@@ -41,6 +48,8 @@ pub async fn page_list(
             entity::model::t_file::Column::FileReport.is_not_null(),
             "has_report",
         )
+        .column(entity::model::t_file::Column::ParentId)
+        .column(entity::model::t_file::Column::Version)
         .column(entity::model::t_file::Column::CreateTime)
         .column(entity::model::t_file::Column::ModifyTime);
     match param.name {
@@ -85,11 +94,26 @@ pub async fn delete(
         return DefaultResponse::success();
     }
     let result = entity::model::t_file::Entity::delete_many()
-        .filter(entity::model::t_file::Column::Id.is_in(param.ids))
+        .filter(entity::model::t_file::Column::Id.is_in(param.ids.clone()))
         .exec(app_state.db_conn.as_ref())
         .await;
     match result {
-        Ok(_) => DefaultResponse::success(),
+        Ok(_) => {
+            if let Err(err) = file_analysis::delete_analysis_by_file_ids(
+                app_state.db_conn.as_ref(),
+                param.ids.clone(),
+            )
+            .await
+            {
+                log::error!("delete file analysis error: {}", err);
+            }
+            if let Err(err) =
+                ioc_service::delete_by_file_ids(app_state.db_conn.as_ref(), param.ids).await
+            {
+                log::error!("delete file ioc error: {}", err);
+            }
+            DefaultResponse::success()
+        }
         Err(err) => {
             log::error!("delete file error: {}", err.to_string());
             DefaultResponse::error().msg("删除失败，请重试!".to_string())
@@ -174,3 +198,123 @@ pub async fn download_report(
 
     (headers, file_buf).into_response()
 }
+
+// 查找版本关系时的最大层数，避免异常数据导致死循环
+const MAX_VERSION_DEPTH: usize = 1000;
+
+#[derive(Debug, Clone, FromQueryResult, Serialize, Deserialize)]
+struct VersionInfo {
+    id: String,
+    file_name: String,
+    file_md5: String,
+    parent_id: Option<String>,
+    version: i32,
+    create_time: DateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionNode {
+    #[serde(flatten)]
+    info: VersionInfo,
+    children: Vec<VersionNode>,
+}
+
+async fn find_versions(
+    app_state: &AppState,
+    condition: Condition,
+) -> Result<Vec<VersionInfo>, sea_orm::DbErr> {
+    t_file::Entity::find()
+        .select_only()
+        .column(t_file::Column::Id)
+        .column(t_file::Column::FileName)
+        .column(t_file::Column::FileMd5)
+        .column(t_file::Column::ParentId)
+        .column(t_file::Column::Version)
+        .column(t_file::Column::CreateTime)
+        .filter(condition)
+        .order_by_asc(t_file::Column::CreateTime)
+        .into_model::<VersionInfo>()
+        .all(app_state.db_conn.as_ref())
+        .await
+}
+
+fn build_node(info: VersionInfo, children: &mut HashMap<String, Vec<VersionInfo>>) -> VersionNode {
+    let child_infos = children.remove(&info.id).unwrap_or_default();
+    VersionNode {
+        children: child_infos
+            .into_iter()
+            .map(|child| build_node(child, children))
+            .collect(),
+        info,
+    }
+}
+
+//返回文件所在的完整版本树，父文件已删除时从最早的现存版本开始
+pub async fn version_tree(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+) -> impl IntoResponse {
+    let query_error = |err: sea_orm::DbErr| {
+        log::error!("find file version error: {} [{}]", err, file_id);
+        DefaultResponse::error()
+            .msg("数据查询错误, 请稍后再试!".to_string())
+            .into_response()
+    };
+    let mut root = match find_versions(
+        &app_state,
+        Condition::all().add(t_file::Column::Id.eq(&file_id)),
+    )
+    .await
+    {
+        Ok(data) => match data.into_iter().next() {
+            Some(data) => data,
+            None => {
+                return DefaultResponse::error()
+                    .msg("文件为空，请重试!".to_string())
+                    .into_response()
+            }
+        },
+        Err(err) => return query_error(err),
+    };
+    for _ in 0..MAX_VERSION_DEPTH {
+        let Some(parent_id) = root.parent_id.clone() else {
+            break;
+        };
+        match find_versions(
+            &app_state,
+            Condition::all().add(t_file::Column::Id.eq(parent_id)),
+        )
+        .await
+        {
+            Ok(data) => match data.into_iter().next() {
+                Some(parent) => root = parent,
+                None => break,
+            },
+            Err(err) => return query_error(err),
+        }
+    }
+    // 逐层查询子版本
+    let mut children: HashMap<String, Vec<VersionInfo>> = HashMap::new();
+    let mut level = vec![root.id.clone()];
+    for _ in 0..MAX_VERSION_DEPTH {
+        if level.is_empty() {
+            break;
+        }
+        let data = match find_versions(
+            &app_state,
+            Condition::all().add(t_file::Column::ParentId.is_in(level)),
+        )
+        .await
+        {
+            Ok(data) => data,
+            Err(err) => return query_error(err),
+        };
+        level = data.iter().map(|info| info.id.clone()).collect();
+        for info in data {
+            if let Some(parent_id) = info.parent_id.clone() {
+                children.entry(parent_id).or_default().push(info);
+            }
+        }
+    }
+    DataResponse::success(build_node(root, &mut children)).into_response()
+}
@@ -14,6 +14,7 @@ pub fn get_routers(app_state: AppState) -> Router {
                 "/download_report/:file_id",
                 get(file_service::download_report),
             )
+            .route("/version_tree/:file_id", get(file_service::version_tree))
             .with_state(app_state),
     )
 }
@@ -0,0 +1,394 @@
+use std::collections::HashSet;
+
+use axum::extract::{Multipart, Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use entity::model::t_hash_list;
+use migration::sea_orm::ActiveValue::Set;
+use migration::sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait,
+};
+use migration::OnConflict;
+
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::tools::file_hash::{self, FileHashes, HashType, ListType};
+
+const INSERT_CHUNK_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashListHit {
+    pub list_type: ListType,
+    pub hash_type: HashType,
+    pub hash_value: String,
+    pub reason: Option<String>,
+}
+
+impl HashListHit {
+    pub fn message(&self) -> String {
+        let mut message = format!(
+            "{}命中哈希{}",
+            self.hash_type.display_name(),
+            self.list_type.display_name()
+        );
+        if let Some(reason) = self.reason.as_ref().filter(|reason| !reason.is_empty()) {
+            message.push_str(&format!("，原因: {}", reason));
+        }
+        message
+    }
+}
+
+//按文件哈希查询黑白名单，同时命中时以黑名单为准，查询失败时返回错误，避免名单中的文件按普通文件评分
+pub async fn check_hashes(
+    db: &DatabaseConnection,
+    hashes: &FileHashes,
+) -> Result<Option<HashListHit>, DbErr> {
+    let mut condition = Condition::any();
+    for (hash_type, value) in hashes.values() {
+        condition = condition.add(
+            Condition::all()
+                .add(t_hash_list::Column::HashType.eq(hash_type.as_str()))
+                .add(t_hash_list::Column::HashValue.eq(value)),
+        );
+    }
+    let models = t_hash_list::Entity::find()
+        .filter(condition)
+        .all(db)
+        .await?;
+    let mut hits = models
+        .into_iter()
+        .filter_map(|model| {
+            Some(HashListHit {
+                list_type: ListType::parse(&model.list_type)?,
+                hash_type: HashType::parse(&model.hash_type)?,
+                hash_value: model.hash_value,
+                reason: model.reason,
+            })
+        })
+        .collect::<Vec<_>>();
+    hits.sort_by_key(|hit| hit.list_type != ListType::Block);
+    Ok(hits.into_iter().next())
+}
+
+fn parse_list_type(value: &str) -> Result<ListType, String> {
+    ListType::parse(value).ok_or_else(|| format!("名单类型不存在: {}", value))
+}
+
+//解析哈希类型，为空时根据长度识别
+fn parse_hash(hash_type: Option<&str>, value: &str) -> Result<(HashType, String), String> {
+    let value = value.trim();
+    let hash_type = match hash_type.filter(|hash_type| !hash_type.is_empty()) {
+        Some(hash_type) => {
+            HashType::parse(hash_type).ok_or_else(|| format!("哈希类型不存在: {}", hash_type))?
+        }
+        None => HashType::detect(value).ok_or_else(|| format!("无法识别哈希类型: {}", value))?,
+    };
+    Ok((hash_type, file_hash::normalize_hash(hash_type, value)?))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveParam {
+    pub id: Option<String>,
+    // md5/sha1/sha256/imphash，为空时根据长度识别
+    pub hash_type: Option<String>,
+    pub hash_value: String,
+    // allow/block
+    pub list_type: String,
+    pub reason: Option<String>,
+}
+pub async fn save(app_state: State<AppState>, Json(param): Json<SaveParam>) -> impl IntoResponse {
+    let db = app_state.db_conn.as_ref();
+    let list_type = match parse_list_type(&param.list_type) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg),
+    };
+    let (hash_type, hash_value) = match parse_hash(param.hash_type.as_deref(), &param.hash_value) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg),
+    };
+    match t_hash_list::Entity::find()
+        .filter(t_hash_list::Column::HashType.eq(hash_type.as_str()))
+        .filter(t_hash_list::Column::HashValue.eq(&hash_value))
+        .one(db)
+        .await
+    {
+        Ok(Some(data)) if Some(&data.id) != param.id.as_ref() => {
+            let list_type = ListType::parse(&data.list_type)
+                .map_or(data.list_type.as_str(), |list_type| {
+                    list_type.display_name()
+                });
+            return DefaultResponse::error().msg(format!("该哈希已存在于{}!", list_type));
+        }
+        Ok(_) => {}
+        Err(err) => {
+            log::error!("find hash list by value error: {}", err);
+            return DefaultResponse::error().msg("数据查询错误, 请稍后再试!".to_string());
+        }
+    }
+    let now = chrono::Local::now().naive_local();
+    let active_model = match param.id {
+        None => t_hash_list::ActiveModel {
+            id: Set(uuid::Uuid::new_v4().simple().to_string()),
+            hash_type: Set(hash_type.as_str().to_string()),
+            hash_value: Set(hash_value),
+            list_type: Set(list_type.as_str().to_string()),
+            reason: Set(param.reason),
+            create_time: Set(now),
+            modify_time: Set(now),
+        },
+        Some(ref id) => match t_hash_list::Entity::find_by_id(id).one(db).await {
+            Ok(None) => {
+                return DefaultResponse::error().msg("数据不存在, 请确认后再试!".to_string())
+            }
+            Ok(Some(data)) => {
+                let mut active_model = data.into_active_model();
+                active_model.hash_type = Set(hash_type.as_str().to_string());
+                active_model.hash_value = Set(hash_value);
+                active_model.list_type = Set(list_type.as_str().to_string());
+                active_model.reason = Set(param.reason);
+                active_model.modify_time = Set(now);
+                active_model
+            }
+            Err(err) => {
+                log::error!("find hash list by id error: {}", err);
+                return DefaultResponse::error().msg("数据查询错误, 请稍后再试!".to_string());
+            }
+        },
+    };
+    let result = match param.id {
+        None => active_model.insert(db).await,
+        Some(_) => active_model.update(db).await,
+    };
+    match result {
+        Ok(_) => DefaultResponse::success(),
+        Err(err) => {
+            log::error!("save hash list error: {}", err);
+            DefaultResponse::error().msg("保存数据失败, 请确认数据后重试!".to_string())
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteParam {
+    ids: Vec<String>,
+}
+pub async fn delete(
+    app_state: State<AppState>,
+    Json(param): Json<DeleteParam>,
+) -> impl IntoResponse {
+    if param.ids.is_empty() {
+        return DefaultResponse::success();
+    }
+    let result = t_hash_list::Entity::delete_many()
+        .filter(t_hash_list::Column::Id.is_in(param.ids))
+        .exec(app_state.db_conn.as_ref())
+        .await;
+    match result {
+        Ok(_) => DefaultResponse::success(),
+        Err(err) => {
+            log::error!("delete hash list error: {}", err);
+            DefaultResponse::error().msg("删除失败，请重试!".to_string())
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageListParam {
+    page: u64,
+    size: u64,
+    hash_value: Option<String>,
+    hash_type: Option<String>,
+    list_type: Option<String>,
+}
+pub async fn page_list(
+    app_state: State<AppState>,
+    Query(param): Query<PageListParam>,
+) -> impl IntoResponse {
+    let mut select = t_hash_list::Entity::find();
+    if let Some(hash_value) = param.hash_value.filter(|value| !value.is_empty()) {
+        select = select.filter(
+            t_hash_list::Column::HashValue
+                .like(format!("%{}%", hash_value.trim().to_ascii_lowercase())),
+        );
+    }
+    if let Some(hash_type) = param.hash_type.filter(|value| !value.is_empty()) {
+        select = select.filter(t_hash_list::Column::HashType.eq(hash_type));
+    }
+    if let Some(list_type) = param.list_type.filter(|value| !value.is_empty()) {
+        select = select.filter(t_hash_list::Column::ListType.eq(list_type));
+    }
+    select = select.order_by_desc(t_hash_list::Column::ModifyTime);
+    let paginate = select.paginate(app_state.db_conn.as_ref(), param.size);
+    let total = paginate.num_items().await.unwrap_or_else(|err| {
+        log::error!("get hash list total num error: {}", err);
+        0
+    });
+    let pages = paginate.num_pages().await.unwrap_or(0);
+    if total == 0 {
+        return PaginateResponse::success(Vec::new(), PaginateInfo::default());
+    }
+    let data = paginate.fetch_page(param.page).await.unwrap_or_else(|err| {
+        log::error!("find hash list page list error: {}", err);
+        vec![]
+    });
+    PaginateResponse::success(data, PaginateInfo { total, pages })
+}
+
+pub async fn info(app_state: State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
+    match t_hash_list::Entity::find_by_id(id)
+        .one(app_state.db_conn.as_ref())
+        .await
+    {
+        Ok(None) => DefaultResponse::error()
+            .msg("数据不存在, 请检查后重试!".to_string())
+            .into_response(),
+        Ok(Some(data)) => DataResponse::success(data).into_response(),
+        Err(err) => {
+            log::error!("find hash list by id error: {}", err);
+            DefaultResponse::error().into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportParam {
+    list_type: String,
+    // 为空时根据长度识别，32位的imphash需要指定
+    hash_type: Option<String>,
+    // 行内没有说明时使用的原因
+    reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportError {
+    // 从1开始的行号
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub imported: usize,
+    pub errors: Vec<ImportError>,
+}
+
+//从每行一个哈希的文本文件批量导入，已存在的哈希会被移到指定名单
+pub async fn import(
+    app_state: State<AppState>,
+    Query(param): Query<ImportParam>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let list_type = match parse_list_type(&param.list_type) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg).into_response(),
+    };
+    let field = match multipart.next_field().await {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            return DefaultResponse::error()
+                .msg("文件不能为空!".to_string())
+                .into_response()
+        }
+        Err(err) => {
+            return DefaultResponse::error()
+                .msg(err.to_string())
+                .into_response()
+        }
+    };
+    let file_bytes = match field.bytes().await {
+        Ok(data) => data,
+        Err(err) => {
+            return DefaultResponse::error()
+                .msg(err.to_string())
+                .into_response()
+        }
+    };
+    let content = String::from_utf8_lossy(&file_bytes);
+    let now = chrono::Local::now().naive_local();
+    let mut result = ImportResult::default();
+    let mut active_models: Vec<t_hash_list::ActiveModel> = Vec::new();
+    let mut keys: HashSet<(HashType, String)> = HashSet::new();
+    for (index, line) in content.lines().enumerate() {
+        // 忽略空行与#开头的注释，哈希后的内容作为原因，兼容sha256sum等工具的输出
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (value, comment) = match line.split_once(char::is_whitespace) {
+            Some((value, comment)) => (value, Some(comment.trim().to_string())),
+            None => (line, None),
+        };
+        let (hash_type, hash_value) = match parse_hash(param.hash_type.as_deref(), value) {
+            Ok(data) => data,
+            Err(message) => {
+                result.errors.push(ImportError {
+                    line: index + 1,
+                    message,
+                });
+                continue;
+            }
+        };
+        // 同一批次中重复的哈希会导致更新冲突
+        if !keys.insert((hash_type, hash_value.clone())) {
+            continue;
+        }
+        active_models.push(t_hash_list::ActiveModel {
+            id: Set(uuid::Uuid::new_v4().simple().to_string()),
+            hash_type: Set(hash_type.as_str().to_string()),
+            hash_value: Set(hash_value),
+            list_type: Set(list_type.as_str().to_string()),
+            reason: Set(comment
+                .filter(|comment| !comment.is_empty())
+                .or_else(|| param.reason.clone())),
+            create_time: Set(now),
+            modify_time: Set(now),
+        });
+    }
+    // 分批写入在同一事务中执行，失败时全部回滚
+    let txn = match app_state.db_conn.begin().await {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("begin import hash list transaction error: {}", err);
+            return DataResponse::success(result)
+                .code(500)
+                .msg("保存数据失败, 请确认数据后重试!".to_string())
+                .into_response();
+        }
+    };
+    for chunk in active_models.chunks(INSERT_CHUNK_SIZE) {
+        if let Err(err) = t_hash_list::Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                OnConflict::columns([
+                    t_hash_list::Column::HashType,
+                    t_hash_list::Column::HashValue,
+                ])
+                .update_columns([
+                    t_hash_list::Column::ListType,
+                    t_hash_list::Column::Reason,
+                    t_hash_list::Column::ModifyTime,
+                ])
+                .to_owned(),
+            )
+            .exec(&txn)
+            .await
+        {
+            log::error!("import hash list error: {}", err);
+            return DataResponse::success(result)
+                .code(500)
+                .msg("保存数据失败, 请确认数据后重试!".to_string())
+                .into_response();
+        }
+    }
+    if let Err(err) = txn.commit().await {
+        log::error!("commit import hash list transaction error: {}", err);
+        return DataResponse::success(result)
+            .code(500)
+            .msg("保存数据失败, 请确认数据后重试!".to_string())
+            .into_response();
+    }
+    result.imported = active_models.len();
+    DataResponse::success(result).into_response()
+}
@@ -0,0 +1,19 @@
+use axum::routing::{get, post};
+use axum::Router;
+
+use crate::app::state::AppState;
+
+pub mod hash_list_service;
+
+pub fn get_routers(app_state: AppState) -> Router {
+    Router::new().nest(
+        "/hash_list",
+        Router::new()
+            .route("/save", post(hash_list_service::save))
+            .route("/delete", post(hash_list_service::delete))
+            .route("/page_list", get(hash_list_service::page_list))
+            .route("/info/:id", get(hash_list_service::info))
+            .route("/import", post(hash_list_service::import))
+            .with_state(app_state),
+    )
+}
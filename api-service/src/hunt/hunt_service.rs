@@ -0,0 +1,226 @@
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use entity::model::t_file;
+use migration::sea_orm::prelude::DateTime;
+use migration::sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DataResponse, DefaultResponse};
+use crate::app::state::AppState;
+use crate::hunt::HUNT_JOBS;
+use crate::pe::file_analysis::{self, ANALYZER_RISK};
+use crate::pe::pe_service::{self, FileFilter};
+use crate::tools::risk::{RiskScore, Verdict};
+
+// 保留的已结束任务数量
+const MAX_FINISHED_JOBS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HuntStatus {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerdictChange {
+    pub file_id: String,
+    pub file_name: String,
+    // 文件从未分析过时为空
+    pub old_verdict: Option<Verdict>,
+    pub old_score: Option<u32>,
+    pub new_verdict: Verdict,
+    pub new_score: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HuntFailure {
+    pub file_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HuntJob {
+    pub job_id: String,
+    pub status: HuntStatus,
+    pub total: usize,
+    pub processed: usize,
+    pub changed: Vec<VerdictChange>,
+    pub failures: Vec<HuntFailure>,
+    #[serde(skip)]
+    cancel_requested: bool,
+    pub start_time: DateTime,
+    pub end_time: Option<DateTime>,
+}
+
+//启动回溯检测任务，用当前的知识库与规则重新分析已上传的文件
+pub async fn start(app_state: State<AppState>, Json(param): Json<FileFilter>) -> impl IntoResponse {
+    if has_running_job().await {
+        return DefaultResponse::error()
+            .msg("已有回溯任务正在运行，请稍后再试!".to_string())
+            .into_response();
+    }
+    // 查询文件时不持有任务锁，避免阻塞进度查询与取消
+    let file_ids = match pe_service::find_file_ids(&app_state, &param, None).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    if file_ids.is_empty() {
+        return DefaultResponse::error()
+            .msg("没有符合条件的文件!".to_string())
+            .into_response();
+    }
+    let mut jobs = HUNT_JOBS.lock().await;
+    // 查询期间可能已有其他任务启动
+    if jobs.values().any(|job| job.status == HuntStatus::Running) {
+        return DefaultResponse::error()
+            .msg("已有回溯任务正在运行，请稍后再试!".to_string())
+            .into_response();
+    }
+    // 清理最早结束的任务
+    let mut finished = jobs
+        .values()
+        .map(|job| (job.start_time, job.job_id.clone()))
+        .collect::<Vec<_>>();
+    finished.sort();
+    while finished.len() >= MAX_FINISHED_JOBS {
+        let (_, job_id) = finished.remove(0);
+        jobs.remove(&job_id);
+    }
+    let job_id = uuid::Uuid::new_v4().simple().to_string();
+    let job = HuntJob {
+        job_id: job_id.clone(),
+        status: HuntStatus::Running,
+        total: file_ids.len(),
+        processed: 0,
+        changed: Vec::new(),
+        failures: Vec::new(),
+        cancel_requested: false,
+        start_time: chrono::Local::now().naive_local(),
+        end_time: None,
+    };
+    jobs.insert(job_id.clone(), job.clone());
+    drop(jobs);
+    let state = app_state.0.clone();
+    tokio::spawn(async move { run_job(state, job_id, file_ids).await });
+    DataResponse::success(job).into_response()
+}
+
+async fn has_running_job() -> bool {
+    HUNT_JOBS
+        .lock()
+        .await
+        .values()
+        .any(|job| job.status == HuntStatus::Running)
+}
+
+enum HuntOutcome {
+    Changed(VerdictChange),
+    Unchanged,
+    Failed(String),
+}
+
+async fn hunt_file(app_state: &AppState, file_id: &str) -> HuntOutcome {
+    let db = app_state.db_conn.as_ref();
+    let old = file_analysis::find_analysis::<RiskScore>(db, file_id, ANALYZER_RISK)
+        .await
+        .unwrap_or_else(|err| {
+            log::error!("find file risk error: {} [{}]", err, file_id);
+            None
+        });
+    let file_model = match t_file::Entity::find_by_id(file_id).one(db).await {
+        Ok(Some(data)) => data,
+        Ok(None) => return HuntOutcome::Failed("文件不存在".to_string()),
+        Err(err) => {
+            log::error!("find file by id error: {} [{}]", err, file_id);
+            return HuntOutcome::Failed("文件查找失败".to_string());
+        }
+    };
+    match pe_service::analyze_file(app_state, file_model).await {
+        Ok(result) => {
+            if old.as_ref().map(|old| old.verdict) == Some(result.risk.verdict) {
+                HuntOutcome::Unchanged
+            } else {
+                HuntOutcome::Changed(VerdictChange {
+                    file_id: result.file_id,
+                    file_name: result.file_name,
+                    old_verdict: old.as_ref().map(|old| old.verdict),
+                    old_score: old.as_ref().map(|old| old.score),
+                    new_verdict: result.risk.verdict,
+                    new_score: result.risk.score,
+                })
+            }
+        }
+        Err(err) => HuntOutcome::Failed(err.to_string()),
+    }
+}
+
+async fn run_job(app_state: AppState, job_id: String, file_ids: Vec<String>) {
+    for file_id in file_ids {
+        let cancel_requested = HUNT_JOBS
+            .lock()
+            .await
+            .get(&job_id)
+            .is_none_or(|job| job.cancel_requested);
+        if cancel_requested {
+            finish_job(&job_id, HuntStatus::Cancelled).await;
+            return;
+        }
+        let outcome = hunt_file(&app_state, &file_id).await;
+        let mut jobs = HUNT_JOBS.lock().await;
+        let Some(job) = jobs.get_mut(&job_id) else {
+            return;
+        };
+        job.processed += 1;
+        match outcome {
+            HuntOutcome::Changed(change) => job.changed.push(change),
+            HuntOutcome::Unchanged => {}
+            HuntOutcome::Failed(message) => job.failures.push(HuntFailure { file_id, message }),
+        }
+    }
+    finish_job(&job_id, HuntStatus::Completed).await;
+}
+
+async fn finish_job(job_id: &str, status: HuntStatus) {
+    if let Some(job) = HUNT_JOBS.lock().await.get_mut(job_id) {
+        job.status = status;
+        job.end_time = Some(chrono::Local::now().naive_local());
+        log::info!(
+            "hunt job {} finished: {}/{} files, {} changed",
+            job_id,
+            job.processed,
+            job.total,
+            job.changed.len()
+        );
+    }
+}
+
+pub async fn cancel(Path(job_id): Path<String>) -> impl IntoResponse {
+    match HUNT_JOBS.lock().await.get_mut(&job_id) {
+        None => DefaultResponse::error().msg("任务不存在, 请检查后重试!".to_string()),
+        Some(job) if job.status != HuntStatus::Running => {
+            DefaultResponse::error().msg("任务已结束!".to_string())
+        }
+        Some(job) => {
+            job.cancel_requested = true;
+            DefaultResponse::success()
+        }
+    }
+}
+
+pub async fn job_list() -> impl IntoResponse {
+    let mut data = HUNT_JOBS.lock().await.values().cloned().collect::<Vec<_>>();
+    data.sort_by_key(|job| std::cmp::Reverse(job.start_time));
+    DataResponse::success(data).into_response()
+}
+
+pub async fn info(Path(job_id): Path<String>) -> impl IntoResponse {
+    match HUNT_JOBS.lock().await.get(&job_id) {
+        None => DefaultResponse::error()
+            .msg("任务不存在, 请检查后重试!".to_string())
+            .into_response(),
+        Some(job) => DataResponse::success(job.clone()).into_response(),
+    }
+}
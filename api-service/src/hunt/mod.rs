@@ -0,0 +1,25 @@
+use axum::routing::{get, post};
+use axum::Router;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+use crate::app::state::AppState;
+use crate::hunt::hunt_service::HuntJob;
+
+pub mod hunt_service;
+
+pub static HUNT_JOBS: Lazy<Mutex<HashMap<String, HuntJob>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn get_routers(app_state: AppState) -> Router {
+    Router::new().nest(
+        "/hunt",
+        Router::new()
+            .route("/start", post(hunt_service::start))
+            .route("/cancel/:job_id", post(hunt_service::cancel))
+            .route("/job_list", get(hunt_service::job_list))
+            .route("/info/:job_id", get(hunt_service::info))
+            .with_state(app_state),
+    )
+}
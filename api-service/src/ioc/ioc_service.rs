@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use entity::model::{t_file, t_ioc};
+use migration::sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::tools::ioc::Ioc;
+
+const INSERT_CHUNK_SIZE: usize = 1000;
+
+//覆盖保存文件的IOC，删除与插入在同一事务中执行，失败时保留原有数据
+pub async fn save_file_iocs(
+    db: &DatabaseConnection,
+    file_id: &str,
+    iocs: &[Ioc],
+) -> anyhow::Result<()> {
+    let txn = db.begin().await?;
+    t_ioc::Entity::delete_many()
+        .filter(t_ioc::Column::FileId.eq(file_id))
+        .exec(&txn)
+        .await?;
+    let now = chrono::Local::now().naive_local();
+    for chunk in iocs.chunks(INSERT_CHUNK_SIZE) {
+        let active_models = chunk.iter().map(|item| t_ioc::ActiveModel {
+            id: Set(uuid::Uuid::new_v4().simple().to_string()),
+            file_id: Set(file_id.to_string()),
+            ioc_type: Set(item.ioc_type.as_str().to_string()),
+            value: Set(item.value.clone()),
+            offset: Set(item.offset as i64),
+            encoding: Set(item.encoding.as_str().to_string()),
+            create_time: Set(now),
+        });
+        t_ioc::Entity::insert_many(active_models).exec(&txn).await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+pub async fn delete_by_file_ids(
+    db: &DatabaseConnection,
+    file_ids: Vec<String>,
+) -> anyhow::Result<()> {
+    t_ioc::Entity::delete_many()
+        .filter(t_ioc::Column::FileId.is_in(file_ids))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IocInfo {
+    pub id: String,
+    pub file_id: String,
+    pub file_name: Option<String>,
+    pub ioc_type: String,
+    pub value: String,
+    pub offset: i64,
+    pub encoding: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageListParam {
+    page: u64,
+    size: u64,
+    ioc_type: Option<String>,
+    value: Option<String>,
+    file_id: Option<String>,
+}
+//跨文件查询IOC
+pub async fn page_list(
+    app_state: State<AppState>,
+    Query(param): Query<PageListParam>,
+) -> impl IntoResponse {
+    let mut select = t_ioc::Entity::find();
+    if let Some(ioc_type) = param.ioc_type.filter(|value| !value.is_empty()) {
+        select = select.filter(t_ioc::Column::IocType.eq(ioc_type));
+    }
+    if let Some(value) = param.value.filter(|value| !value.is_empty()) {
+        select = select.filter(t_ioc::Column::Value.like(format!("%{}%", &value)));
+    }
+    if let Some(file_id) = param.file_id.filter(|value| !value.is_empty()) {
+        select = select.filter(t_ioc::Column::FileId.eq(file_id));
+    }
+    select = select
+        .order_by_asc(t_ioc::Column::IocType)
+        .order_by_asc(t_ioc::Column::Value);
+    let paginate = select.paginate(app_state.db_conn.as_ref(), param.size);
+    let total = paginate.num_items().await.unwrap_or_else(|err| {
+        log::error!("get ioc total num error: {}", err);
+        0
+    });
+    let pages = paginate.num_pages().await.unwrap_or(0);
+    if total == 0 {
+        return PaginateResponse::<IocInfo>::success(Vec::new(), PaginateInfo::default());
+    }
+    let data = paginate.fetch_page(param.page).await.unwrap_or_else(|err| {
+        log::error!("find ioc page list error: {}", err);
+        vec![]
+    });
+    let file_ids = data
+        .iter()
+        .map(|item| item.file_id.clone())
+        .collect::<Vec<_>>();
+    let file_names: HashMap<String, String> = t_file::Entity::find()
+        .select_only()
+        .column(t_file::Column::Id)
+        .column(t_file::Column::FileName)
+        .filter(t_file::Column::Id.is_in(file_ids))
+        .into_tuple::<(String, String)>()
+        .all(app_state.db_conn.as_ref())
+        .await
+        .unwrap_or_else(|err| {
+            log::error!("find ioc file name error: {}", err);
+            vec![]
+        })
+        .into_iter()
+        .collect();
+    let data = data
+        .into_iter()
+        .map(|item| IocInfo {
+            file_name: file_names.get(&item.file_id).cloned(),
+            id: item.id,
+            file_id: item.file_id,
+            ioc_type: item.ioc_type,
+            value: item.value,
+            offset: item.offset,
+            encoding: item.encoding,
+        })
+        .collect();
+    PaginateResponse::success(data, PaginateInfo { total, pages })
+}
+
+pub async fn file_list(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+) -> impl IntoResponse {
+    match t_ioc::Entity::find()
+        .filter(t_ioc::Column::FileId.eq(&file_id))
+        .order_by_asc(t_ioc::Column::Offset)
+        .all(app_state.db_conn.as_ref())
+        .await
+    {
+        Ok(data) => DataResponse::success(data).into_response(),
+        Err(err) => {
+            log::error!("find file ioc error: {} [{}]", err, file_id);
+            DefaultResponse::error().into_response()
+        }
+    }
+}
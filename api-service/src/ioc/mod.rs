@@ -0,0 +1,16 @@
+use axum::routing::get;
+use axum::Router;
+
+use crate::app::state::AppState;
+
+pub mod ioc_service;
+
+pub fn get_routers(app_state: AppState) -> Router {
+    Router::new().nest(
+        "/ioc",
+        Router::new()
+            .route("/page_list", get(ioc_service::page_list))
+            .route("/file_list/:file_id", get(ioc_service::file_list))
+            .with_state(app_state),
+    )
+}
@@ -0,0 +1,172 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use entity::model::t_file;
+use migration::sea_orm::{EntityTrait, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::app::response::{DataResponse, DefaultResponse};
+use crate::app::state::AppState;
+use crate::knowledge::knowledge_service;
+use crate::pe::pe_service::{self, FileFilter};
+use crate::tools::knowledge::{self, KnowledgeMatcher};
+use crate::tools::pe_image::PeImage;
+
+// 单次试运行最多扫描的文件数量
+const MAX_DRY_RUN_FILES: u64 = 1000;
+// 未指定样本数量时扫描的文件数量
+const DEFAULT_DRY_RUN_FILES: u64 = 200;
+const DEFAULT_EXAMPLE_COUNT: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DryRunParam {
+    pub name: String,
+    // 为空时为exact_variants
+    pub match_mode: Option<String>,
+    pub dll_name: Option<String>,
+    // 样本范围，为空时为最早上传的文件
+    #[serde(flatten)]
+    pub filter: FileFilter,
+    // 扫描的文件数量，默认200，最多1000
+    pub sample_size: Option<u64>,
+    // 返回的示例文件数量
+    pub example_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportCount {
+    pub import: String,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunExample {
+    pub file_id: String,
+    pub file_name: String,
+    pub file_md5: String,
+    pub imports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunResult {
+    pub scanned: usize,
+    // 不是PE文件或导入表无法解析
+    pub skipped: usize,
+    pub matched: usize,
+    // 命中的导入函数及命中文件数，按文件数倒序
+    pub imports: Vec<ImportCount>,
+    pub examples: Vec<DryRunExample>,
+}
+
+//在样本上试运行一条知识库条目，不保存数据
+pub async fn dry_run(
+    app_state: State<AppState>,
+    Json(param): Json<DryRunParam>,
+) -> impl IntoResponse {
+    if param.name.is_empty() {
+        return DefaultResponse::error()
+            .msg("方法名称不能为空!".to_string())
+            .into_response();
+    }
+    let rule = match knowledge_service::check_rule(
+        &param.name,
+        param.match_mode.as_deref(),
+        param.dll_name.as_deref(),
+    ) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg).into_response(),
+    };
+    let matcher = Arc::new(KnowledgeMatcher::build(&[rule]));
+    let sample_size = param
+        .sample_size
+        .unwrap_or(DEFAULT_DRY_RUN_FILES)
+        .clamp(1, MAX_DRY_RUN_FILES);
+    let file_ids =
+        match pe_service::find_file_ids(&app_state, &param.filter, Some(sample_size)).await {
+            Ok(data) => data,
+            Err(response) => return response.into_response(),
+        };
+    let example_count = param.example_count.unwrap_or(DEFAULT_EXAMPLE_COUNT);
+    let mut result = DryRunResult::default();
+    // 逐个读取文件，避免一次加载全部样本
+    for file_id in file_ids {
+        let file_model = match t_file::Entity::find_by_id(&file_id)
+            .select_only()
+            .columns([
+                t_file::Column::Id,
+                t_file::Column::FileName,
+                t_file::Column::FileMd5,
+                t_file::Column::FileBuf,
+            ])
+            .into_tuple::<(String, String, String, Vec<u8>)>()
+            .one(app_state.db_conn.as_ref())
+            .await
+        {
+            Ok(Some(data)) => data,
+            Ok(None) => continue,
+            Err(err) => {
+                log::error!("find file by id error: {} [{}]", err, file_id);
+                continue;
+            }
+        };
+        let (file_id, file_name, file_md5, file_buf) = file_model;
+        result.scanned += 1;
+        // 解析与匹配在阻塞线程中执行，返回None表示不是PE文件
+        let matcher = matcher.clone();
+        let matched = tokio::task::spawn_blocking(move || {
+            let image = PeImage::parse(&file_buf).ok()?;
+            let mut imports: Vec<String> = Vec::new();
+            for (dll, name) in knowledge::named_imports(&image) {
+                if !matcher.match_import(dll.as_deref(), &name).is_empty()
+                    && !imports.contains(&name)
+                {
+                    imports.push(name);
+                }
+            }
+            Some(imports)
+        })
+        .await;
+        let imports = match matched {
+            Ok(Some(data)) => data,
+            Ok(None) => {
+                result.skipped += 1;
+                continue;
+            }
+            Err(err) => {
+                log::error!("dry run task error: {} [{}]", err, file_id);
+                result.skipped += 1;
+                continue;
+            }
+        };
+        if imports.is_empty() {
+            continue;
+        }
+        result.matched += 1;
+        for import in &imports {
+            match result
+                .imports
+                .iter_mut()
+                .find(|item| &item.import == import)
+            {
+                Some(item) => item.files += 1,
+                None => result.imports.push(ImportCount {
+                    import: import.clone(),
+                    files: 1,
+                }),
+            }
+        }
+        if result.examples.len() < example_count {
+            result.examples.push(DryRunExample {
+                file_id,
+                file_name,
+                file_md5,
+                imports,
+            });
+        }
+    }
+    result
+        .imports
+        .sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.import.cmp(&b.import)));
+    DataResponse::success(result).into_response()
+}
@@ -0,0 +1,200 @@
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use entity::model::{t_knowledge, t_knowledge_history};
+use migration::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+
+use crate::app::operator::Operator;
+use crate::app::response::{DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::knowledge::knowledge_service;
+use crate::knowledge::transfer_service::{self, FieldChange};
+
+pub const OPERATION_CREATE: &str = "create";
+pub const OPERATION_UPDATE: &str = "update";
+pub const OPERATION_DELETE: &str = "delete";
+pub const OPERATION_RESTORE: &str = "restore";
+
+fn to_json(model: Option<&t_knowledge::Model>) -> Option<Value> {
+    model.and_then(|model| serde_json::to_value(model).ok())
+}
+
+//生成一条历史记录，新增时old为空，删除时new为空
+pub fn history_model(
+    knowledge_id: &str,
+    operation: &str,
+    operator: &str,
+    old: Option<&t_knowledge::Model>,
+    new: Option<&t_knowledge::Model>,
+) -> t_knowledge_history::ActiveModel {
+    t_knowledge_history::ActiveModel {
+        id: Set(uuid::Uuid::new_v4().simple().to_string()),
+        knowledge_id: Set(knowledge_id.to_string()),
+        operation: Set(operation.to_string()),
+        operator: Set(operator.to_string()),
+        old_value: Set(to_json(old)),
+        new_value: Set(to_json(new)),
+        create_time: Set(chrono::Local::now().naive_local()),
+    }
+}
+
+//保存历史记录，需要与数据修改在同一事务中执行，保证每次修改都有记录
+pub async fn save_history<C: ConnectionTrait>(
+    db: &C,
+    histories: Vec<t_knowledge_history::ActiveModel>,
+) -> Result<(), DbErr> {
+    if histories.is_empty() {
+        return Ok(());
+    }
+    t_knowledge_history::Entity::insert_many(histories)
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryItem {
+    #[serde(flatten)]
+    pub history: t_knowledge_history::Model,
+    pub changes: Vec<FieldChange>,
+}
+
+fn from_json(value: Option<&Value>) -> Option<t_knowledge::Model> {
+    value.and_then(|value| serde_json::from_value(value.clone()).ok())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryPageListParam {
+    page: u64,
+    size: u64,
+    knowledge_id: Option<String>,
+    operation: Option<String>,
+}
+pub async fn history_page_list(
+    app_state: State<AppState>,
+    Query(param): Query<HistoryPageListParam>,
+) -> impl IntoResponse {
+    let mut select = t_knowledge_history::Entity::find();
+    if let Some(knowledge_id) = param.knowledge_id {
+        select = select.filter(t_knowledge_history::Column::KnowledgeId.eq(knowledge_id));
+    }
+    if let Some(operation) = param.operation {
+        select = select.filter(t_knowledge_history::Column::Operation.eq(operation));
+    }
+    select = select.order_by_desc(t_knowledge_history::Column::CreateTime);
+    let paginate = select.paginate(app_state.db_conn.as_ref(), param.size);
+    let total = paginate.num_items().await.unwrap_or_else(|err| {
+        log::error!("get knowledge history total num error: {}", err);
+        0
+    });
+    let pages = paginate.num_pages().await.unwrap_or(0);
+    if total == 0 {
+        return PaginateResponse::success(Vec::new(), PaginateInfo::default());
+    }
+    let data = paginate.fetch_page(param.page).await.unwrap_or_else(|err| {
+        log::error!("find knowledge history page list error: {}", err);
+        vec![]
+    });
+    let data = data
+        .into_iter()
+        .map(|history| HistoryItem {
+            changes: transfer_service::model_changes(
+                from_json(history.old_value.as_ref()).as_ref(),
+                from_json(history.new_value.as_ref()).as_ref(),
+            ),
+            history,
+        })
+        .collect::<Vec<_>>();
+    PaginateResponse::success(data, PaginateInfo { total, pages })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestoreParam {
+    history_id: String,
+}
+
+//恢复到某条历史记录修改后的版本，删除记录则恢复被删除的数据
+pub async fn restore(
+    app_state: State<AppState>,
+    Operator(operator): Operator,
+    Json(param): Json<RestoreParam>,
+) -> impl IntoResponse {
+    let db = app_state.db_conn.as_ref();
+    let history = match t_knowledge_history::Entity::find_by_id(&param.history_id)
+        .one(db)
+        .await
+    {
+        Ok(Some(data)) => data,
+        Ok(None) => return DefaultResponse::error().msg("数据不存在, 请确认后再试!".to_string()),
+        Err(err) => {
+            log::error!("find knowledge history by id error: {}", err);
+            return DefaultResponse::error().msg("数据查询错误, 请稍后再试!".to_string());
+        }
+    };
+    let Some(Value::Object(version)) = history.new_value.or(history.old_value) else {
+        return DefaultResponse::error().msg("历史版本数据为空，无法恢复!".to_string());
+    };
+    let current = match t_knowledge::Entity::find_by_id(&history.knowledge_id)
+        .one(db)
+        .await
+    {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("find knowledge by id error: {}", err);
+            return DefaultResponse::error().msg("数据查询错误, 请稍后再试!".to_string());
+        }
+    };
+    // 历史版本中没有的新字段保留当前值
+    let mut value = match to_json(current.as_ref()) {
+        Some(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    value.extend(version);
+    let mut restored: t_knowledge::Model = match serde_json::from_value(Value::Object(value)) {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("parse knowledge history error: {}", err);
+            return DefaultResponse::error().msg("历史版本数据不完整，无法恢复!".to_string());
+        }
+    };
+    restored.id = history.knowledge_id.clone();
+    restored.modify_time = chrono::Local::now().naive_local();
+    let active_model = restored.into_active_model().reset_all();
+    // 恢复与历史记录在同一事务中保存
+    let result = async {
+        let txn = db.begin().await?;
+        let model = match current {
+            Some(_) => active_model.update(&txn).await?,
+            None => active_model.insert(&txn).await?,
+        };
+        save_history(
+            &txn,
+            vec![history_model(
+                &model.id,
+                OPERATION_RESTORE,
+                &operator,
+                current.as_ref(),
+                Some(&model),
+            )],
+        )
+        .await?;
+        txn.commit().await
+    }
+    .await;
+    match result {
+        Ok(_) => {
+            knowledge_service::invalidate_index().await;
+            DefaultResponse::success()
+        }
+        Err(err) => {
+            log::error!("restore knowledge error: {}", err);
+            DefaultResponse::error().msg("恢复失败，请重试!".to_string())
+        }
+    }
+}
@@ -2,15 +2,25 @@ use axum::extract::{Path, Query, State};
 use axum::response::IntoResponse;
 use axum::Json;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
+use entity::model::{t_attack_technique, t_knowledge};
 use migration::sea_orm::ActiveValue::Set;
 use migration::sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter,
-    QueryOrder,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait,
 };
+use migration::Expr;
 
+use crate::app::operator::Operator;
 use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
 use crate::app::state::AppState;
+use crate::knowledge::history_service::{
+    self, OPERATION_CREATE, OPERATION_DELETE, OPERATION_UPDATE,
+};
+use crate::knowledge::KNOWLEDGE_INDEX;
+use crate::tools::attack;
+use crate::tools::knowledge::{self, KnowledgeMatcher, KnowledgeRule, MatchMode, Severity};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SaveParam {
@@ -18,22 +28,262 @@ pub struct SaveParam {
     pub name: String,
     pub desc: Option<String>,
     pub is_sensitive: bool,
+    // ATT&CK技术编号，如 T1055.012
+    #[serde(default)]
+    pub technique_ids: Vec<String>,
+    // 战术简称，如 defense-evasion，为空时使用技术所属的战术
+    #[serde(default)]
+    pub tactics: Vec<String>,
+    // 严重级别 info/low/medium/high/critical，为空时为medium
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    // 为空时新增数据默认启用，修改数据保持原状态
+    pub enabled: Option<bool>,
+    // 匹配方式 exact/prefix/regex/exact_variants，为空时为exact_variants
+    pub match_mode: Option<String>,
+    // 限定导入的DLL，如 kernel32.dll
+    pub dll_name: Option<String>,
+}
+
+//已编译的知识库，只包含启用的条目，知识库变更后重新构建
+pub struct KnowledgeIndex {
+    pub entries: Vec<t_knowledge::Model>,
+    pub matcher: KnowledgeMatcher,
+}
+
+pub fn knowledge_rule(model: &t_knowledge::Model) -> KnowledgeRule {
+    KnowledgeRule {
+        pattern: model.func_name.clone(),
+        mode: MatchMode::parse(&model.match_mode).unwrap_or(MatchMode::ExactVariants),
+        dll: model.dll_name.clone(),
+    }
+}
+
+//校验并规范化匹配规则，match_mode为空时为exact_variants，DLL名称转为小写
+pub fn check_rule(
+    name: &str,
+    match_mode: Option<&str>,
+    dll_name: Option<&str>,
+) -> Result<KnowledgeRule, String> {
+    let mode = match match_mode {
+        None => MatchMode::ExactVariants,
+        Some(value) => {
+            MatchMode::parse(value).ok_or_else(|| format!("匹配方式不存在: {}", value))?
+        }
+    };
+    let rule = KnowledgeRule {
+        pattern: name.to_string(),
+        mode,
+        dll: dll_name
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty()),
+    };
+    knowledge::validate_rule(&rule)?;
+    Ok(rule)
+}
+
+//获取已编译的知识库，缓存为空时从数据库重新构建
+pub async fn load_index(db: &DatabaseConnection) -> Arc<KnowledgeIndex> {
+    if let Some(index) = KNOWLEDGE_INDEX.read().await.as_ref() {
+        return index.clone();
+    }
+    let mut cache = KNOWLEDGE_INDEX.write().await;
+    if let Some(index) = cache.as_ref() {
+        return index.clone();
+    }
+    match t_knowledge::Entity::find()
+        .filter(t_knowledge::Column::Enabled.eq(true))
+        .all(db)
+        .await
+    {
+        Ok(entries) => {
+            let rules = entries.iter().map(knowledge_rule).collect::<Vec<_>>();
+            let index = Arc::new(KnowledgeIndex {
+                matcher: KnowledgeMatcher::build(&rules),
+                entries,
+            });
+            *cache = Some(index.clone());
+            index
+        }
+        Err(err) => {
+            // 查询失败时不缓存，下次请求重新构建
+            log::error!("get knowledge error: {}", err);
+            Arc::new(KnowledgeIndex {
+                entries: Vec::new(),
+                matcher: KnowledgeMatcher::build(&[]),
+            })
+        }
+    }
 }
-pub async fn save(app_state: State<AppState>, Json(param): Json<SaveParam>) -> impl IntoResponse {
+
+//知识库变更后清除缓存
+pub async fn invalidate_index() {
+    *KNOWLEDGE_INDEX.write().await = None;
+}
+
+//校验分类名称，返回逗号拼接后的保存值
+fn check_categories(categories: &[String]) -> Result<Option<String>, String> {
+    let mut result: Vec<&str> = Vec::new();
+    for category in categories {
+        let category = category.trim();
+        if category.is_empty() {
+            continue;
+        }
+        if category.contains(',') || category.chars().count() > 32 {
+            return Err(format!("分类名称不合法: {}", category));
+        }
+        if !result.contains(&category) {
+            result.push(category);
+        }
+    }
+    if result.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(result.join(",")))
+    }
+}
+
+//校验ATT&CK标签，返回逗号拼接后的保存值
+async fn check_attack_tags(
+    db: &DatabaseConnection,
+    technique_ids: &[String],
+    tactics: &[String],
+) -> Result<(Option<String>, Option<String>), String> {
+    if let Some(id) = technique_ids
+        .iter()
+        .find(|id| !attack::is_valid_technique_id(id))
+    {
+        return Err(format!("ATT&CK技术编号格式错误: {}", id));
+    }
+    if let Some(tactic) = tactics
+        .iter()
+        .find(|tactic| attack::tactic_by_short_name(tactic).is_none())
+    {
+        return Err(format!("ATT&CK战术不存在: {}", tactic));
+    }
+    if !technique_ids.is_empty() {
+        // 离线数据只包含部分子技术，父技术存在即可
+        let parent_id = |id: &String| {
+            id.split_once('.')
+                .map_or(id.clone(), |(parent, _)| parent.to_string())
+        };
+        let exists = t_attack_technique::Entity::find()
+            .filter(
+                t_attack_technique::Column::TechniqueId.is_in(technique_ids.iter().map(parent_id)),
+            )
+            .all(db)
+            .await
+            .map_err(|err| {
+                log::error!("find attack technique error: {}", err);
+                "数据查询错误, 请稍后再试!".to_string()
+            })?;
+        if let Some(id) = technique_ids.iter().find(|id| {
+            !exists
+                .iter()
+                .any(|model| model.technique_id == parent_id(id))
+        }) {
+            return Err(format!("ATT&CK技术编号不存在: {}", id));
+        }
+    }
+    let join = |values: &[String]| {
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.join(","))
+        }
+    };
+    Ok((join(technique_ids), join(tactics)))
+}
+
+//校验通过后可直接保存的字段
+pub struct CheckedParam {
+    technique_ids: Option<String>,
+    tactics: Option<String>,
+    severity: Severity,
+    categories: Option<String>,
+    match_mode: MatchMode,
+    dll_name: Option<String>,
+}
+
+//校验保存参数，返回错误描述
+pub async fn check_save_param(
+    db: &DatabaseConnection,
+    param: &SaveParam,
+) -> Result<CheckedParam, String> {
     if param.name.is_empty() {
-        return DefaultResponse::error().msg("方法名称不能为空!".to_string());
+        return Err("方法名称不能为空!".to_string());
     }
+    let (technique_ids, tactics) =
+        check_attack_tags(db, &param.technique_ids, &param.tactics).await?;
+    let severity = match param.severity.as_deref() {
+        None => Severity::Medium,
+        Some(value) => {
+            Severity::parse(value).ok_or_else(|| format!("严重级别不存在: {}", value))?
+        }
+    };
+    let categories = check_categories(&param.categories)?;
+    let rule = check_rule(
+        &param.name,
+        param.match_mode.as_deref(),
+        param.dll_name.as_deref(),
+    )?;
+    Ok(CheckedParam {
+        technique_ids,
+        tactics,
+        severity,
+        categories,
+        match_mode: rule.mode,
+        dll_name: rule.dll,
+    })
+}
+
+//把校验后的参数写入数据，enabled为空时保持原状态
+pub fn apply_param(
+    active_model: &mut t_knowledge::ActiveModel,
+    param: &SaveParam,
+    checked: CheckedParam,
+) {
+    active_model.func_name = Set(param.name.clone());
+    active_model.func_desc = Set(param.desc.clone());
+    active_model.is_sensitive = Set(param.is_sensitive);
+    active_model.technique_ids = Set(checked.technique_ids);
+    active_model.tactics = Set(checked.tactics);
+    active_model.severity = Set(checked.severity.as_str().to_string());
+    active_model.categories = Set(checked.categories);
+    active_model.match_mode = Set(checked.match_mode.as_str().to_string());
+    active_model.dll_name = Set(checked.dll_name);
+    if let Some(enabled) = param.enabled {
+        active_model.enabled = Set(enabled);
+    }
+}
+
+//新增数据，默认启用
+pub fn new_active_model() -> t_knowledge::ActiveModel {
+    t_knowledge::ActiveModel {
+        id: Set(uuid::Uuid::new_v4().simple().to_string()),
+        enabled: Set(true),
+        create_time: Set(chrono::Local::now().naive_local()),
+        modify_time: Set(chrono::Local::now().naive_local()),
+        ..Default::default()
+    }
+}
+
+pub async fn save(
+    app_state: State<AppState>,
+    Operator(operator): Operator,
+    Json(param): Json<SaveParam>,
+) -> impl IntoResponse {
+    let checked = match check_save_param(app_state.db_conn.as_ref(), &param).await {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg),
+    };
+    let mut old_model = None;
     let active_model = match param.id {
         None => {
-            let new_id = uuid::Uuid::new_v4().simple().to_string();
-            entity::model::t_knowledge::ActiveModel {
-                id: Set(new_id),
-                func_name: Set(param.name),
-                func_desc: Set(param.desc),
-                is_sensitive: Set(param.is_sensitive),
-                create_time: Set(chrono::Local::now().naive_local()),
-                modify_time: Set(chrono::Local::now().naive_local()),
-            }
+            let mut active_model = new_active_model();
+            apply_param(&mut active_model, &param, checked);
+            active_model
         }
         Some(ref id) => {
             match entity::model::t_knowledge::Entity::find_by_id(id)
@@ -46,11 +296,10 @@ pub async fn save(app_state: State<AppState>, Json(param): Json<SaveParam>) -> i
                             .msg("数据不存在, 请确认后再试!".to_string())
                     }
                     Some(data) => {
+                        old_model = Some(data.clone());
                         let mut active_model = data.into_active_model();
                         active_model.modify_time = Set(chrono::Local::now().naive_local());
-                        active_model.func_name = Set(param.name);
-                        active_model.func_desc = Set(param.desc);
-                        active_model.is_sensitive = Set(param.is_sensitive);
+                        apply_param(&mut active_model, &param, checked);
                         active_model
                     }
                 },
@@ -61,35 +310,100 @@ pub async fn save(app_state: State<AppState>, Json(param): Json<SaveParam>) -> i
             }
         }
     };
-    let result = match param.id {
-        None => active_model.insert(app_state.db_conn.as_ref()).await,
-        Some(_) => active_model.update(app_state.db_conn.as_ref()).await,
+    let operation = match old_model {
+        None => OPERATION_CREATE,
+        Some(_) => OPERATION_UPDATE,
     };
+    // 数据与历史记录在同一事务中保存
+    let result = async {
+        let txn = app_state.db_conn.begin().await?;
+        let model = match param.id {
+            None => active_model.insert(&txn).await?,
+            Some(_) => active_model.update(&txn).await?,
+        };
+        history_service::save_history(
+            &txn,
+            vec![history_service::history_model(
+                &model.id,
+                operation,
+                &operator,
+                old_model.as_ref(),
+                Some(&model),
+            )],
+        )
+        .await?;
+        txn.commit().await
+    }
+    .await;
     match result {
-        Ok(_) => DefaultResponse::success(),
+        Ok(_) => {
+            invalidate_index().await;
+            DefaultResponse::success()
+        }
         Err(err) => {
             log::error!("保存数据失败, error: {}", err.to_string());
             DefaultResponse::error().msg("保存数据失败, 请确认数据后重试!".to_string())
         }
     }
 }
+async fn find_by_ids(
+    db: &DatabaseConnection,
+    ids: &[String],
+) -> Result<Vec<t_knowledge::Model>, String> {
+    t_knowledge::Entity::find()
+        .filter(t_knowledge::Column::Id.is_in(ids.iter().cloned()))
+        .all(db)
+        .await
+        .map_err(|err| {
+            log::error!("find knowledge by ids error: {}", err);
+            "数据查询错误, 请稍后再试!".to_string()
+        })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteParam {
     ids: Vec<String>,
 }
 pub async fn delete(
     app_state: State<AppState>,
+    Operator(operator): Operator,
     Json(param): Json<DeleteParam>,
 ) -> impl IntoResponse {
     if param.ids.is_empty() {
         return DefaultResponse::success();
     }
-    let result = entity::model::t_knowledge::Entity::delete_many()
-        .filter(entity::model::t_knowledge::Column::Id.is_in(param.ids))
-        .exec(app_state.db_conn.as_ref())
-        .await;
+    // 删除前保留原数据，用于记录历史
+    let old_models = match find_by_ids(app_state.db_conn.as_ref(), &param.ids).await {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg),
+    };
+    let histories = old_models
+        .iter()
+        .map(|model| {
+            history_service::history_model(
+                &model.id,
+                OPERATION_DELETE,
+                &operator,
+                Some(model),
+                None,
+            )
+        })
+        .collect();
+    let result = async {
+        let txn = app_state.db_conn.begin().await?;
+        entity::model::t_knowledge::Entity::delete_many()
+            .filter(entity::model::t_knowledge::Column::Id.is_in(param.ids))
+            .exec(&txn)
+            .await?;
+        history_service::save_history(&txn, histories).await?;
+        txn.commit().await
+    }
+    .await;
     match result {
-        Ok(_) => DefaultResponse::success(),
+        Ok(_) => {
+            invalidate_index().await;
+            DefaultResponse::success()
+        }
         Err(err) => {
             log::error!("delete knowledge error: {}", err.to_string());
             DefaultResponse::error().msg("删除失败，请重试!".to_string())
@@ -101,6 +415,9 @@ pub struct PageListParam {
     page: u64,
     size: u64,
     name: Option<String>,
+    severity: Option<String>,
+    category: Option<String>,
+    enabled: Option<bool>,
 }
 pub async fn page_list(
     app_state: State<AppState>,
@@ -114,6 +431,16 @@ pub async fn page_list(
                 .filter(entity::model::t_knowledge::Column::FuncName.like(format!("%{}%", &name)));
         }
     }
+    if let Some(severity) = param.severity {
+        select = select.filter(entity::model::t_knowledge::Column::Severity.eq(severity));
+    }
+    if let Some(category) = param.category {
+        select = select
+            .filter(entity::model::t_knowledge::Column::Categories.like(format!("%{}%", category)));
+    }
+    if let Some(enabled) = param.enabled {
+        select = select.filter(entity::model::t_knowledge::Column::Enabled.eq(enabled));
+    }
 
     select = select.order_by_desc(entity::model::t_knowledge::Column::ModifyTime);
     let paginate = select.paginate(app_state.db_conn.as_ref(), param.size);
@@ -149,3 +476,95 @@ pub async fn info(app_state: State<AppState>, Path(id): Path<String>) -> impl In
         }
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionItem {
+    pub value: String,
+    pub name: String,
+}
+
+pub async fn severity_list() -> impl IntoResponse {
+    let data = Severity::ALL
+        .iter()
+        .map(|severity| OptionItem {
+            value: severity.as_str().to_string(),
+            name: severity.display_name().to_string(),
+        })
+        .collect::<Vec<_>>();
+    DataResponse::success(data).into_response()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetEnabledParam {
+    ids: Vec<String>,
+    enabled: bool,
+}
+pub async fn set_enabled(
+    app_state: State<AppState>,
+    Operator(operator): Operator,
+    Json(param): Json<SetEnabledParam>,
+) -> impl IntoResponse {
+    if param.ids.is_empty() {
+        return DefaultResponse::success();
+    }
+    let old_models = match find_by_ids(app_state.db_conn.as_ref(), &param.ids).await {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg),
+    };
+    let modify_time = chrono::Local::now().naive_local();
+    let histories = old_models
+        .iter()
+        .filter(|model| model.enabled != param.enabled)
+        .map(|model| {
+            let mut new_model = model.clone();
+            new_model.enabled = param.enabled;
+            new_model.modify_time = modify_time;
+            history_service::history_model(
+                &model.id,
+                OPERATION_UPDATE,
+                &operator,
+                Some(model),
+                Some(&new_model),
+            )
+        })
+        .collect();
+    let result = async {
+        let txn = app_state.db_conn.begin().await?;
+        entity::model::t_knowledge::Entity::update_many()
+            .col_expr(
+                entity::model::t_knowledge::Column::Enabled,
+                Expr::value(param.enabled),
+            )
+            .col_expr(
+                entity::model::t_knowledge::Column::ModifyTime,
+                Expr::value(modify_time),
+            )
+            .filter(entity::model::t_knowledge::Column::Id.is_in(param.ids))
+            .exec(&txn)
+            .await?;
+        history_service::save_history(&txn, histories).await?;
+        txn.commit().await
+    }
+    .await;
+    match result {
+        Ok(_) => {
+            invalidate_index().await;
+            DefaultResponse::success()
+        }
+        Err(err) => {
+            log::error!("update knowledge enabled error: {}", err);
+            DefaultResponse::error().msg("保存数据失败, 请确认数据后重试!".to_string())
+        }
+    }
+}
+
+pub async fn match_mode_list() -> impl IntoResponse {
+    let data = MatchMode::ALL
+        .iter()
+        .map(|mode| OptionItem {
+            value: mode.as_str().to_string(),
+            name: mode.display_name().to_string(),
+        })
+        .collect::<Vec<_>>();
+    DataResponse::success(data).into_response()
+}
@@ -1,9 +1,19 @@
 use axum::routing::{get, post};
 use axum::Router;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 use crate::app::state::AppState;
+use crate::knowledge::knowledge_service::KnowledgeIndex;
 
+pub mod dry_run_service;
+pub mod history_service;
 pub mod knowledge_service;
+pub mod transfer_service;
+
+pub static KNOWLEDGE_INDEX: Lazy<RwLock<Option<Arc<KnowledgeIndex>>>> =
+    Lazy::new(|| RwLock::new(None));
 
 pub fn get_routers(app_state: AppState) -> Router {
     Router::new().nest(
@@ -13,6 +23,17 @@ pub fn get_routers(app_state: AppState) -> Router {
             .route("/delete", post(knowledge_service::delete))
             .route("/page_list", get(knowledge_service::page_list))
             .route("/info/:id", get(knowledge_service::info))
+            .route("/severity_list", get(knowledge_service::severity_list))
+            .route("/match_mode_list", get(knowledge_service::match_mode_list))
+            .route("/set_enabled", post(knowledge_service::set_enabled))
+            .route("/import", post(transfer_service::import))
+            .route("/export", get(transfer_service::export))
+            .route(
+                "/history_page_list",
+                get(history_service::history_page_list),
+            )
+            .route("/restore", post(history_service::restore))
+            .route("/dry_run", post(dry_run_service::dry_run))
             .with_state(app_state),
     )
 }
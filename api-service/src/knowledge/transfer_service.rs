@@ -0,0 +1,451 @@
+use axum::extract::{Multipart, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use entity::model::t_knowledge;
+use migration::sea_orm::{
+    ActiveModelTrait, EntityTrait, IntoActiveModel, QueryOrder, Set, TransactionTrait, TryIntoModel,
+};
+
+use crate::app::operator::Operator;
+use crate::app::response::{DataResponse, DefaultResponse};
+use crate::app::state::AppState;
+use crate::knowledge::history_service::{self, OPERATION_CREATE, OPERATION_UPDATE};
+use crate::knowledge::knowledge_service::{self, SaveParam};
+use crate::tools::attack;
+use crate::tools::knowledge_transfer::{self, KnowledgeRecord, TransferFormat};
+
+// 对比差异时忽略的字段
+const IGNORED_FIELDS: [&str; 3] = ["id", "create_time", "modify_time"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportChange {
+    pub name: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportError {
+    // 从1开始的记录序号
+    pub index: usize,
+    pub name: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportDiff {
+    pub created: Vec<String>,
+    pub updated: Vec<ImportChange>,
+    pub unchanged: Vec<String>,
+    pub errors: Vec<ImportError>,
+    // 是否已写入数据库
+    pub applied: bool,
+}
+
+//对比两个版本的知识库条目，返回有变化的字段
+pub fn model_changes(
+    old: Option<&t_knowledge::Model>,
+    new: Option<&t_knowledge::Model>,
+) -> Vec<FieldChange> {
+    let to_map = |model: Option<&t_knowledge::Model>| match model.map(serde_json::to_value) {
+        Some(Ok(Value::Object(map))) => map,
+        _ => serde_json::Map::new(),
+    };
+    let (old, new) = (to_map(old), to_map(new));
+    let mut fields = new.keys().chain(old.keys()).collect::<Vec<_>>();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter(|field| old.get(*field) != new.get(*field))
+        .map(|field| FieldChange {
+            field: field.clone(),
+            old: old.get(field).cloned(),
+            new: new.get(field).cloned(),
+        })
+        .collect()
+}
+
+pub fn model_to_record(model: &t_knowledge::Model) -> KnowledgeRecord {
+    KnowledgeRecord {
+        name: model.func_name.clone(),
+        desc: model.func_desc.clone(),
+        is_sensitive: Some(model.is_sensitive),
+        severity: Some(model.severity.clone()),
+        categories: Some(attack::split_tags(model.categories.as_deref())),
+        technique_ids: Some(attack::split_tags(model.technique_ids.as_deref())),
+        tactics: Some(attack::split_tags(model.tactics.as_deref())),
+        enabled: Some(model.enabled),
+        match_mode: Some(model.match_mode.clone()),
+        dll_name: model.dll_name.clone(),
+    }
+}
+
+//已存在的条目未填写的字段保留原值，避免部分字段的导入文件覆盖现有数据
+fn record_to_param(record: KnowledgeRecord, existing: Option<&t_knowledge::Model>) -> SaveParam {
+    let existing_tags = |tags: fn(&t_knowledge::Model) -> Option<&str>| {
+        existing
+            .map(|model| attack::split_tags(tags(model)))
+            .unwrap_or_default()
+    };
+    SaveParam {
+        id: None,
+        name: record.name.trim().to_string(),
+        desc: record
+            .desc
+            .or_else(|| existing.and_then(|model| model.func_desc.clone())),
+        // 新增条目默认为敏感函数
+        is_sensitive: record
+            .is_sensitive
+            .or_else(|| existing.map(|model| model.is_sensitive))
+            .unwrap_or(true),
+        technique_ids: record
+            .technique_ids
+            .unwrap_or_else(|| existing_tags(|model| model.technique_ids.as_deref())),
+        tactics: record
+            .tactics
+            .unwrap_or_else(|| existing_tags(|model| model.tactics.as_deref())),
+        severity: record
+            .severity
+            .or_else(|| existing.map(|model| model.severity.clone())),
+        categories: record
+            .categories
+            .unwrap_or_else(|| existing_tags(|model| model.categories.as_deref())),
+        enabled: record.enabled,
+        match_mode: record
+            .match_mode
+            .or_else(|| existing.map(|model| model.match_mode.clone())),
+        dll_name: record
+            .dll_name
+            .or_else(|| existing.and_then(|model| model.dll_name.clone())),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportParam {
+    // json/csv/yaml，为空时根据文件扩展名识别
+    format: Option<String>,
+    // 只返回差异，不写入数据
+    #[serde(default)]
+    dry_run: bool,
+}
+
+//批量导入知识库，按函数名称新增或更新
+pub async fn import(
+    app_state: State<AppState>,
+    Operator(operator): Operator,
+    Query(param): Query<ImportParam>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let field = match multipart.next_field().await {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            return DefaultResponse::error()
+                .msg("文件不能为空!".to_string())
+                .into_response()
+        }
+        Err(err) => {
+            return DefaultResponse::error()
+                .msg(err.to_string())
+                .into_response()
+        }
+    };
+    let file_name = field.file_name().unwrap_or_default().to_string();
+    let format = match param.format.as_deref() {
+        Some(format) => TransferFormat::parse(format),
+        None => TransferFormat::from_file_name(&file_name),
+    };
+    let Some(format) = format else {
+        return DefaultResponse::error()
+            .msg("无法识别文件格式，请指定json、csv或yaml!".to_string())
+            .into_response();
+    };
+    let file_bytes = match field.bytes().await {
+        Ok(data) => data,
+        Err(err) => {
+            return DefaultResponse::error()
+                .msg(err.to_string())
+                .into_response()
+        }
+    };
+    let records = match knowledge_transfer::parse_records(format, &file_bytes) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg).into_response(),
+    };
+    let db = app_state.db_conn.as_ref();
+    let existing = match t_knowledge::Entity::find().all(db).await {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("find knowledge error: {}", err);
+            return DefaultResponse::error()
+                .msg("数据查询错误, 请稍后再试!".to_string())
+                .into_response();
+        }
+    };
+    let mut existing_map: HashMap<String, t_knowledge::Model> = HashMap::new();
+    for model in existing {
+        existing_map.entry(model.func_name.clone()).or_insert(model);
+    }
+    let mut diff = ImportDiff::default();
+    let mut inserts = Vec::new();
+    let mut updates = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for (index, record) in records.into_iter().enumerate() {
+        let existing = existing_map.get(record.name.trim());
+        let param = record_to_param(record, existing);
+        let error = |message: String| ImportError {
+            index: index + 1,
+            name: param.name.clone(),
+            message,
+        };
+        if names.contains(&param.name) {
+            diff.errors
+                .push(error("文件中存在重复的函数名称".to_string()));
+            continue;
+        }
+        names.push(param.name.clone());
+        let checked = match knowledge_service::check_save_param(db, &param).await {
+            Ok(data) => data,
+            Err(msg) => {
+                diff.errors.push(error(msg));
+                continue;
+            }
+        };
+        match existing_map.get(&param.name) {
+            None => {
+                let mut active_model = knowledge_service::new_active_model();
+                knowledge_service::apply_param(&mut active_model, &param, checked);
+                diff.created.push(param.name.clone());
+                inserts.push(active_model);
+            }
+            Some(model) => {
+                let mut active_model = model.clone().into_active_model();
+                knowledge_service::apply_param(&mut active_model, &param, checked);
+                let changes = match active_model.clone().try_into_model() {
+                    Ok(new_model) => model_changes(Some(model), Some(&new_model)),
+                    Err(err) => {
+                        diff.errors.push(error(err.to_string()));
+                        continue;
+                    }
+                };
+                if changes.is_empty() {
+                    diff.unchanged.push(param.name.clone());
+                } else {
+                    diff.updated.push(ImportChange {
+                        name: param.name.clone(),
+                        changes,
+                    });
+                    active_model.modify_time = Set(chrono::Local::now().naive_local());
+                    updates.push((model.clone(), active_model));
+                }
+            }
+        }
+    }
+    if !diff.errors.is_empty() {
+        return DataResponse::success(diff)
+            .code(500)
+            .msg("导入数据存在错误，未保存任何数据!".to_string())
+            .into_response();
+    }
+    if param.dry_run {
+        return DataResponse::success(diff).into_response();
+    }
+    // 新增与修改在同一事务中执行，失败时全部回滚
+    let txn = match db.begin().await {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("begin import transaction error: {}", err);
+            return DefaultResponse::error()
+                .msg("保存数据失败, 请确认数据后重试!".to_string())
+                .into_response();
+        }
+    };
+    let mut histories = Vec::new();
+    if !inserts.is_empty() {
+        for model in inserts
+            .iter()
+            .filter_map(|active_model| active_model.clone().try_into_model().ok())
+        {
+            histories.push(history_service::history_model(
+                &model.id,
+                OPERATION_CREATE,
+                &operator,
+                None,
+                Some(&model),
+            ));
+        }
+        if let Err(err) = t_knowledge::Entity::insert_many(inserts).exec(&txn).await {
+            log::error!("import knowledge error: {}", err);
+            return DefaultResponse::error()
+                .msg("保存数据失败, 请确认数据后重试!".to_string())
+                .into_response();
+        }
+    }
+    for (old_model, active_model) in updates {
+        match active_model.update(&txn).await {
+            Ok(model) => histories.push(history_service::history_model(
+                &model.id,
+                OPERATION_UPDATE,
+                &operator,
+                Some(&old_model),
+                Some(&model),
+            )),
+            Err(err) => {
+                log::error!("import knowledge error: {}", err);
+                return DefaultResponse::error()
+                    .msg("保存数据失败, 请确认数据后重试!".to_string())
+                    .into_response();
+            }
+        }
+    }
+    if let Err(err) = history_service::save_history(&txn, histories).await {
+        log::error!("save knowledge history error: {}", err);
+        return DefaultResponse::error()
+            .msg("保存数据失败, 请确认数据后重试!".to_string())
+            .into_response();
+    }
+    if let Err(err) = txn.commit().await {
+        log::error!("commit import transaction error: {}", err);
+        return DefaultResponse::error()
+            .msg("保存数据失败, 请确认数据后重试!".to_string())
+            .into_response();
+    }
+    knowledge_service::invalidate_index().await;
+    diff.applied = true;
+    DataResponse::success(diff).into_response()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportParam {
+    format: String,
+}
+
+pub async fn export(
+    app_state: State<AppState>,
+    Query(param): Query<ExportParam>,
+) -> impl IntoResponse {
+    let Some(format) = TransferFormat::parse(&param.format) else {
+        return DefaultResponse::error()
+            .msg("不支持的导出格式，请选择json、csv或yaml!".to_string())
+            .into_response();
+    };
+    let models = match t_knowledge::Entity::find()
+        .order_by_asc(t_knowledge::Column::FuncName)
+        .all(app_state.db_conn.as_ref())
+        .await
+    {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("find knowledge error: {}", err);
+            return DefaultResponse::error()
+                .msg("数据查询错误, 请稍后再试!".to_string())
+                .into_response();
+        }
+    };
+    let records = models.iter().map(model_to_record).collect::<Vec<_>>();
+    let file_buf = match knowledge_transfer::write_records(format, &records) {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("export knowledge error: {}", err);
+            return DefaultResponse::error()
+                .msg("导出失败，请重试!".to_string())
+                .into_response();
+        }
+    };
+    let attachment = format!("attachment; filename=knowledge.{}", format.extension());
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_str(&attachment).unwrap(),
+    );
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    (headers, file_buf).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn existing() -> t_knowledge::Model {
+        t_knowledge::Model {
+            id: "1".to_string(),
+            func_name: "VirtualAllocEx".to_string(),
+            func_desc: Some("跨进程分配内存".to_string()),
+            is_sensitive: false,
+            technique_ids: Some("T1055".to_string()),
+            tactics: Some("defense-evasion,privilege-escalation".to_string()),
+            severity: "high".to_string(),
+            categories: Some("进程注入".to_string()),
+            enabled: true,
+            match_mode: "exact".to_string(),
+            dll_name: Some("kernel32.dll".to_string()),
+            create_time: Default::default(),
+            modify_time: Default::default(),
+        }
+    }
+
+    fn parse(format: TransferFormat, content: &str) -> KnowledgeRecord {
+        knowledge_transfer::parse_records(format, content.as_bytes())
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn partial_csv_keeps_existing_fields() {
+        let model = existing();
+        let record = parse(TransferFormat::Csv, "name,desc\nVirtualAllocEx,新的描述\n");
+        let param = record_to_param(record, Some(&model));
+        assert_eq!(param.desc.as_deref(), Some("新的描述"));
+        assert!(!param.is_sensitive);
+        assert_eq!(param.technique_ids, vec!["T1055"]);
+        assert_eq!(
+            param.tactics,
+            vec!["defense-evasion", "privilege-escalation"]
+        );
+        assert_eq!(param.categories, vec!["进程注入"]);
+        assert_eq!(param.severity.as_deref(), Some("high"));
+        assert_eq!(param.match_mode.as_deref(), Some("exact"));
+        assert_eq!(param.dll_name.as_deref(), Some("kernel32.dll"));
+    }
+
+    #[test]
+    fn partial_json_overrides_only_given_fields() {
+        let model = existing();
+        let record = parse(
+            TransferFormat::Json,
+            r#"[{"name": "VirtualAllocEx", "is_sensitive": true, "tactics": []}]"#,
+        );
+        let param = record_to_param(record, Some(&model));
+        assert!(param.is_sensitive);
+        // 明确填写的空列表会清空原值
+        assert!(param.tactics.is_empty());
+        assert_eq!(param.technique_ids, vec!["T1055"]);
+        assert_eq!(param.desc.as_deref(), Some("跨进程分配内存"));
+    }
+
+    #[test]
+    fn new_record_uses_defaults() {
+        let record = parse(TransferFormat::Csv, "name\nWriteProcessMemory\n");
+        let param = record_to_param(record, None);
+        assert!(param.is_sensitive);
+        assert!(param.technique_ids.is_empty());
+        assert!(param.categories.is_empty());
+        assert!(param.desc.is_none());
+        assert!(param.severity.is_none());
+    }
+}
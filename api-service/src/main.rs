@@ -1,9 +1,16 @@
 use crate::app::server::AppServer;
 
 mod app;
+mod attack;
+mod capability;
 mod file;
+mod hash_list;
+mod hunt;
+mod ioc;
 mod knowledge;
 mod pe;
+mod risk;
+mod rule;
 mod test;
 mod tools;
 
@@ -0,0 +1,42 @@
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::DataResponse;
+use crate::app::state::AppState;
+use crate::pe::pe_service::find_file_by_id;
+use crate::tools::crypto::{self, CryptoHit};
+use crate::tools::pe_image::PeImage;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoResult {
+    pub file_id: String,
+    pub algorithms: Vec<String>,
+    pub hits: Vec<CryptoHit>,
+}
+
+pub async fn crypto_signatures(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+) -> impl IntoResponse {
+    let file_model = match find_file_by_id(&app_state, &file_id).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    // 非PE文件同样可以扫描，只是没有RVA与节信息
+    let image = PeImage::parse(&file_model.file_buf).ok();
+    let hits = crypto::detect_crypto(image.as_ref(), &file_model.file_buf);
+    let mut algorithms = Vec::new();
+    for hit in &hits {
+        let name = hit.algorithm.as_str().to_string();
+        if !algorithms.contains(&name) {
+            algorithms.push(name);
+        }
+    }
+    DataResponse::success(CryptoResult {
+        file_id,
+        algorithms,
+        hits,
+    })
+    .into_response()
+}
@@ -0,0 +1,168 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::pe::file_analysis::{self, ANALYZER_DEOBFUSCATION};
+use crate::pe::pe_service::find_file_by_id;
+use crate::tools::deobfuscate::{self, RecoveredString, RecoveryMethod};
+use crate::tools::pe_image::PeImage;
+use crate::tools::pe_tools;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeobfuscationResult {
+    pub min_len: usize,
+    pub min_entropy: f64,
+    pub strings: Vec<RecoveredString>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeobfuscationSummary {
+    pub file_id: String,
+    pub min_len: usize,
+    pub min_entropy: f64,
+    pub total: usize,
+    pub stack_string: usize,
+    pub single_byte_xor: usize,
+    pub rolling_xor: usize,
+}
+
+async fn recover_and_save(
+    app_state: &AppState,
+    file_id: &str,
+    min_len: usize,
+    min_entropy: f64,
+) -> Result<DeobfuscationResult, DefaultResponse> {
+    let file_model = find_file_by_id(app_state, file_id).await?;
+    // 异或爆破耗时较长，放到阻塞线程中执行
+    let strings = tokio::task::spawn_blocking(move || {
+        // 非PE文件只做异或爆破
+        let image = PeImage::parse(&file_model.file_buf).ok();
+        deobfuscate::recover_strings(image.as_ref(), &file_model.file_buf, min_len, min_entropy)
+    })
+    .await
+    .map_err(|err| {
+        log::error!("recover strings task error: {} [{}]", err, file_id);
+        DefaultResponse::error().msg("字符串还原失败，请重试!".to_string())
+    })?;
+    let result = DeobfuscationResult {
+        min_len,
+        min_entropy,
+        strings,
+    };
+    if let Err(err) = file_analysis::save_analysis(
+        app_state.db_conn.as_ref(),
+        file_id,
+        ANALYZER_DEOBFUSCATION,
+        &result,
+    )
+    .await
+    {
+        log::error!("save deobfuscation result error: {} [{}]", err, file_id);
+        return Err(DefaultResponse::error().msg("保存字符串还原结果失败，请重试!".to_string()));
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeobfuscateParam {
+    pub min_len: Option<usize>,
+    pub min_entropy: Option<f64>,
+}
+pub async fn deobfuscate(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+    Json(param): Json<DeobfuscateParam>,
+) -> impl IntoResponse {
+    let min_len = param.min_len.unwrap_or(deobfuscate::DEFAULT_MIN_LEN).max(1);
+    let min_entropy = param
+        .min_entropy
+        .unwrap_or(deobfuscate::DEFAULT_MIN_ENTROPY)
+        .clamp(0.0, 8.0);
+    match recover_and_save(&app_state, &file_id, min_len, min_entropy).await {
+        Ok(result) => {
+            let count = |method: RecoveryMethod| {
+                result
+                    .strings
+                    .iter()
+                    .filter(|item| item.method == method)
+                    .count()
+            };
+            DataResponse::success(DeobfuscationSummary {
+                file_id,
+                min_len,
+                min_entropy,
+                total: result.strings.len(),
+                stack_string: count(RecoveryMethod::StackString),
+                single_byte_xor: count(RecoveryMethod::SingleByteXor),
+                rolling_xor: count(RecoveryMethod::RollingXor),
+            })
+            .into_response()
+        }
+        Err(response) => response.into_response(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageListParam {
+    page: u64,
+    size: u64,
+    keyword: Option<String>,
+    method: Option<RecoveryMethod>,
+}
+pub async fn deobfuscated_page_list(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+    Query(param): Query<PageListParam>,
+) -> impl IntoResponse {
+    let stored = file_analysis::find_analysis::<DeobfuscationResult>(
+        app_state.db_conn.as_ref(),
+        &file_id,
+        ANALYZER_DEOBFUSCATION,
+    )
+    .await
+    .unwrap_or_else(|err| {
+        log::error!("find deobfuscation result error: {} [{}]", err, file_id);
+        None
+    });
+    // 尚未分析过的文件按默认参数分析一次
+    let result = match stored {
+        Some(data) => data,
+        None => match recover_and_save(
+            &app_state,
+            &file_id,
+            deobfuscate::DEFAULT_MIN_LEN,
+            deobfuscate::DEFAULT_MIN_ENTROPY,
+        )
+        .await
+        {
+            Ok(data) => data,
+            Err(response) => return response.into_response(),
+        },
+    };
+    let filtered = result
+        .strings
+        .into_iter()
+        .filter(|item| {
+            param
+                .keyword
+                .as_deref()
+                .is_none_or(|keyword| pe_tools::fuzzy_search(&item.value, keyword))
+        })
+        .filter(|item| param.method.is_none_or(|method| item.method == method))
+        .collect::<Vec<_>>();
+    let total = filtered.len() as u64;
+    if total == 0 || param.size == 0 {
+        return PaginateResponse::<RecoveredString>::success(Vec::new(), PaginateInfo::default())
+            .into_response();
+    }
+    let pages = total.div_ceil(param.size);
+    let data = filtered
+        .into_iter()
+        .skip(param.page.saturating_mul(param.size) as usize)
+        .take(param.size as usize)
+        .collect::<Vec<_>>();
+    PaginateResponse::success(data, PaginateInfo { total, pages }).into_response()
+}
@@ -0,0 +1,194 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DataResponse, DefaultResponse};
+use crate::app::state::AppState;
+use crate::pe::pe_service::find_file_by_id;
+use crate::tools::cfg::{self, ControlFlowGraph};
+use crate::tools::disasm::{self, DisasmInstruction};
+use crate::tools::pe_image::PeImage;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DisasmTarget {
+    #[default]
+    Entry,
+    Tls,
+    Export,
+    Rva,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisasmParam {
+    pub target: Option<DisasmTarget>,
+    // tls: 回调序号, export: 导出函数名称或序号, rva: 16进制地址
+    pub value: Option<String>,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisasmResult {
+    pub file_id: String,
+    pub bitness: u32,
+    pub image_base: u64,
+    pub start_rva: u32,
+    pub instructions: Vec<DisasmInstruction>,
+}
+
+pub fn parse_hex_u32(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    u32::from_str_radix(value, 16).ok()
+}
+
+//解析反汇编起始RVA
+pub fn resolve_start_rva(
+    image: &PeImage,
+    target: DisasmTarget,
+    value: Option<&str>,
+) -> Result<u32, String> {
+    match target {
+        DisasmTarget::Entry => Ok(image.entry_point),
+        DisasmTarget::Tls => {
+            let index = match value.map(str::trim).filter(|value| !value.is_empty()) {
+                None => 0,
+                Some(value) => value
+                    .parse::<usize>()
+                    .map_err(|_| format!("TLS回调序号格式错误: {}", value))?,
+            };
+            image
+                .tls_callbacks
+                .get(index)
+                .copied()
+                .ok_or_else(|| format!("TLS回调不存在: {}", index))
+        }
+        DisasmTarget::Export => {
+            let value = value.ok_or_else(|| "导出函数名称不能为空!".to_string())?;
+            let export = match value.trim().strip_prefix('#') {
+                Some(ordinal) => ordinal.parse::<u32>().ok().and_then(|ordinal| {
+                    image
+                        .exports
+                        .iter()
+                        .find(|export| export.ordinal == ordinal)
+                }),
+                None => image.export_by_name(value.trim()),
+            };
+            match export {
+                Some(export) if export.forwarder.is_none() => Ok(export.rva),
+                Some(export) => Err(format!(
+                    "导出函数为转发函数: {}",
+                    export.forwarder.clone().unwrap_or_default()
+                )),
+                None => Err(format!("导出函数不存在: {}", value)),
+            }
+        }
+        DisasmTarget::Rva => value
+            .and_then(parse_hex_u32)
+            .ok_or_else(|| "RVA格式错误, 请输入16进制地址!".to_string()),
+    }
+}
+
+pub async fn disasm(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+    Query(param): Query<DisasmParam>,
+) -> impl IntoResponse {
+    let file_model = match find_file_by_id(&app_state, &file_id).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    let image = match PeImage::parse(&file_model.file_buf) {
+        Ok(data) => data,
+        Err(err) => {
+            return DefaultResponse::error()
+                .msg(err.to_string())
+                .into_response()
+        }
+    };
+    let start_rva = match resolve_start_rva(
+        &image,
+        param.target.unwrap_or_default(),
+        param.value.as_deref(),
+    ) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg).into_response(),
+    };
+    let count = param.count.unwrap_or(50);
+    match disasm::disassemble(&image, &file_model.file_buf, start_rva, count) {
+        Ok(instructions) => DataResponse::success(DisasmResult {
+            file_id,
+            bitness: image.bitness(),
+            image_base: image.image_base,
+            start_rva,
+            instructions,
+        })
+        .into_response(),
+        Err(err) => DefaultResponse::error()
+            .msg(err.to_string())
+            .into_response(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CfgParam {
+    pub target: Option<DisasmTarget>,
+    pub value: Option<String>,
+    pub max_instructions: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CfgResult {
+    pub file_id: String,
+    pub bitness: u32,
+    pub image_base: u64,
+    pub graph: ControlFlowGraph,
+    pub dot: String,
+}
+
+pub async fn control_flow_graph(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+    Query(param): Query<CfgParam>,
+) -> impl IntoResponse {
+    let file_model = match find_file_by_id(&app_state, &file_id).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    let image = match PeImage::parse(&file_model.file_buf) {
+        Ok(data) => data,
+        Err(err) => {
+            return DefaultResponse::error()
+                .msg(err.to_string())
+                .into_response()
+        }
+    };
+    let start_rva = match resolve_start_rva(
+        &image,
+        param.target.unwrap_or_default(),
+        param.value.as_deref(),
+    ) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg).into_response(),
+    };
+    let max_instructions = param.max_instructions.unwrap_or(5000);
+    match cfg::recover_cfg(&image, &file_model.file_buf, start_rva, max_instructions) {
+        Ok(graph) => {
+            let dot = cfg::to_dot(&graph);
+            DataResponse::success(CfgResult {
+                file_id,
+                bitness: image.bitness(),
+                image_base: image.image_base,
+                graph,
+                dot,
+            })
+            .into_response()
+        }
+        Err(err) => DefaultResponse::error()
+            .msg(err.to_string())
+            .into_response(),
+    }
+}
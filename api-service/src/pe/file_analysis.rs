@@ -0,0 +1,70 @@
+use entity::model::t_file_analysis;
+use migration::sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use migration::OnConflict;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// t_file_analysis.analyzer 的取值
+pub const ANALYZER_STRINGS: &str = "strings";
+pub const ANALYZER_DEOBFUSCATION: &str = "deobfuscation";
+pub const ANALYZER_RISK: &str = "risk";
+
+//保存单个分析器的结果，同一文件同一分析器只保留最新一份
+pub async fn save_analysis<T: Serialize>(
+    db: &DatabaseConnection,
+    file_id: &str,
+    analyzer: &str,
+    result: &T,
+) -> anyhow::Result<()> {
+    let now = chrono::Local::now().naive_local();
+    let active_model = t_file_analysis::ActiveModel {
+        id: Set(uuid::Uuid::new_v4().simple().to_string()),
+        file_id: Set(file_id.to_string()),
+        analyzer: Set(analyzer.to_string()),
+        result: Set(serde_json::to_value(result)?),
+        create_time: Set(now),
+        modify_time: Set(now),
+    };
+    t_file_analysis::Entity::insert(active_model)
+        .on_conflict(
+            OnConflict::columns([
+                t_file_analysis::Column::FileId,
+                t_file_analysis::Column::Analyzer,
+            ])
+            .update_columns([
+                t_file_analysis::Column::Result,
+                t_file_analysis::Column::ModifyTime,
+            ])
+            .to_owned(),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn find_analysis<T: DeserializeOwned>(
+    db: &DatabaseConnection,
+    file_id: &str,
+    analyzer: &str,
+) -> anyhow::Result<Option<T>> {
+    let model = t_file_analysis::Entity::find()
+        .filter(t_file_analysis::Column::FileId.eq(file_id))
+        .filter(t_file_analysis::Column::Analyzer.eq(analyzer))
+        .one(db)
+        .await?;
+    match model {
+        None => Ok(None),
+        Some(model) => Ok(Some(serde_json::from_value(model.result)?)),
+    }
+}
+
+pub async fn delete_analysis_by_file_ids(
+    db: &DatabaseConnection,
+    file_ids: Vec<String>,
+) -> anyhow::Result<()> {
+    t_file_analysis::Entity::delete_many()
+        .filter(t_file_analysis::Column::FileId.is_in(file_ids))
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,699 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::IntoResponse;
+use axum::Json;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use entity::model::t_file;
+use migration::sea_orm::{ActiveModelTrait, EntityTrait, QuerySelect, Set, TransactionTrait};
+
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::session::SessionId;
+use crate::app::state::AppState;
+use crate::pe::pe_service;
+use crate::pe::EDIT_SESSIONS;
+use crate::tools::data_inspector::{self, Inspection};
+use crate::tools::hex_edit::{DiffHunk, EditOperation, EditState, HexDocument};
+use crate::tools::hex_view::{self, HexRow};
+use crate::tools::pe_image::PeImage;
+use crate::tools::pe_overlay;
+
+// 编辑会话闲置超过该时间后释放
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+// 全部编辑会话占用的内存上限，超出时释放最久未使用的会话
+const MAX_SESSION_BYTES: usize = 512 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadFileInfo {
+    pub file_id: String,
+    pub file_name: String,
+    pub file_size: String,
+    pub address_group_information: Vec<AddressGroupInformation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddressGroupInformation {
+    pub address: String,
+    pub bytes: Vec<ByteInformation>,
+    pub translation: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ByteInformation {
+    pub index: u64,
+    pub bytes: String,
+}
+
+//一个用户对一个文件的编辑状态
+#[derive(Debug)]
+pub struct EditSession {
+    pub file_id: String,
+    pub file_name: String,
+    pub document: HexDocument,
+    last_access: Instant,
+}
+
+impl EditSession {
+    fn memory_size(&self) -> usize {
+        self.document.memory_size()
+    }
+}
+
+//按会话ID与文件ID区分的编辑状态，同一用户可以同时编辑多个文件
+#[derive(Debug, Default)]
+pub struct EditSessions {
+    sessions: HashMap<(String, String), EditSession>,
+}
+
+impl EditSessions {
+    fn purge_expired(&mut self) {
+        self.sessions
+            .retain(|_, session| session.last_access.elapsed() < SESSION_IDLE_TIMEOUT);
+    }
+
+    //打开或重置文件的编辑状态
+    pub fn open(
+        &mut self,
+        session_id: &str,
+        file_id: &str,
+        file_name: &str,
+        buf: Vec<u8>,
+    ) -> Result<(), String> {
+        self.purge_expired();
+        if buf.len() > MAX_SESSION_BYTES {
+            return Err("文件过大，无法在线编辑!".to_string());
+        }
+        let key = (session_id.to_string(), file_id.to_string());
+        self.sessions.insert(
+            key.clone(),
+            EditSession {
+                file_id: file_id.to_string(),
+                file_name: file_name.to_string(),
+                document: HexDocument::new(buf).with_memory_limit(MAX_SESSION_BYTES),
+                last_access: Instant::now(),
+            },
+        );
+        self.shrink(&key);
+        Ok(())
+    }
+
+    //超出内存上限时释放最久未使用的会话，keep对应的会话不会被释放
+    fn shrink(&mut self, keep: &(String, String)) {
+        let mut total = self
+            .sessions
+            .values()
+            .map(EditSession::memory_size)
+            .sum::<usize>();
+        while total > MAX_SESSION_BYTES {
+            let Some(oldest) = self
+                .sessions
+                .iter()
+                .filter(|(key, _)| *key != keep)
+                .min_by_key(|(_, session)| session.last_access)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some(session) = self.sessions.remove(&oldest) {
+                log::info!(
+                    "release edit session {} [{}] for memory limit",
+                    oldest.0,
+                    oldest.1
+                );
+                total -= session.memory_size();
+            }
+        }
+    }
+
+    //修改文件内容，单个文件的内存由编辑记录自身限制，修改后释放其他会话
+    pub fn edit<R>(
+        &mut self,
+        session_id: &str,
+        file_id: Option<&str>,
+        f: impl FnOnce(&mut HexDocument) -> R,
+    ) -> Option<R> {
+        let session = self.get_mut(session_id, file_id)?;
+        let key = (session_id.to_string(), session.file_id.clone());
+        let result = f(&mut session.document);
+        self.shrink(&key);
+        Some(result)
+    }
+
+    //获取编辑状态，未指定文件时返回该会话最近使用的文件
+    pub fn get_mut(&mut self, session_id: &str, file_id: Option<&str>) -> Option<&mut EditSession> {
+        self.purge_expired();
+        let session = match file_id {
+            Some(file_id) => self
+                .sessions
+                .get_mut(&(session_id.to_string(), file_id.to_string())),
+            None => self
+                .sessions
+                .iter_mut()
+                .filter(|((id, _), _)| id == session_id)
+                .map(|(_, session)| session)
+                .max_by_key(|session| session.last_access),
+        }?;
+        session.last_access = Instant::now();
+        Some(session)
+    }
+
+    pub fn close(&mut self, session_id: &str, file_id: &str) -> bool {
+        self.sessions
+            .remove(&(session_id.to_string(), file_id.to_string()))
+            .is_some()
+    }
+}
+
+pub async fn open_session(
+    session_id: &str,
+    file_id: &str,
+    file_name: &str,
+    buf: Vec<u8>,
+) -> Result<(), String> {
+    EDIT_SESSIONS
+        .lock()
+        .await
+        .open(session_id, file_id, file_name, buf)
+}
+
+//读取文件内容，当前会话正在编辑该文件时使用编辑后的内容
+//复制内容后释放会话锁，f在阻塞线程中执行，避免解析与搜索阻塞其他请求
+pub async fn with_file_buf<R: Send + 'static>(
+    app_state: &AppState,
+    session_id: Option<&SessionId>,
+    file_id: &str,
+    f: impl FnOnce(&[u8]) -> R + Send + 'static,
+) -> Result<R, DefaultResponse> {
+    let session_buf = match session_id {
+        Some(SessionId(session_id)) => EDIT_SESSIONS
+            .lock()
+            .await
+            .get_mut(session_id, Some(file_id))
+            .map(|session| session.document.data().to_vec()),
+        None => None,
+    };
+    let buf = match session_buf {
+        Some(data) => data,
+        None => {
+            pe_service::find_file_by_id(app_state, file_id)
+                .await?
+                .file_buf
+        }
+    };
+    tokio::task::spawn_blocking(move || f(&buf))
+        .await
+        .map_err(|err| {
+            log::error!("read file task error: {} [{}]", err, file_id);
+            DefaultResponse::error().msg("读取文件失败，请重试!".to_string())
+        })
+}
+
+fn no_session() -> DefaultResponse {
+    DefaultResponse::error().msg("索引信息为空，请重新上传文件!".to_string())
+}
+
+//每16字节一行，最后一行不足16字节时按实际长度显示
+fn get_address_group_information_from_file_buf(file_buf: &[u8]) -> Vec<AddressGroupInformation> {
+    file_buf
+        .par_chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let start_byte_index = row * 16;
+            let byte_information = chunk
+                .iter()
+                .enumerate()
+                .map(|(index, item)| ByteInformation {
+                    index: (start_byte_index + index) as u64,
+                    bytes: format!("{:02X}", item),
+                })
+                .collect::<Vec<_>>();
+            let translation = chunk
+                .iter()
+                .map(|item| {
+                    if (33u8..=126u8).contains(item) {
+                        *item as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            AddressGroupInformation {
+                address: format!("{:08X}", start_byte_index),
+                bytes: byte_information,
+                translation,
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileParam {
+    // 为空时使用该会话最近编辑的文件
+    pub file_id: Option<String>,
+}
+
+pub async fn get_upload_file_info(
+    SessionId(session_id): SessionId,
+    Query(param): Query<FileParam>,
+) -> impl IntoResponse {
+    // 复制文件内容后释放会话锁，再生成每行的显示内容
+    let session = EDIT_SESSIONS
+        .lock()
+        .await
+        .get_mut(&session_id, param.file_id.as_deref())
+        .map(|session| {
+            (
+                session.file_id.clone(),
+                session.file_name.clone(),
+                session.document.data().to_vec(),
+            )
+        });
+    let upload_file_info = session.map(|(file_id, file_name, buf)| UploadFileInfo {
+        file_id,
+        file_name,
+        file_size: pe_service::file_size_text(buf.len()),
+        address_group_information: get_address_group_information_from_file_buf(&buf),
+    });
+    DataResponse::success(upload_file_info).into_response()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateByteParam {
+    pub file_id: Option<String>,
+    pub index: String,
+    pub byte: String,
+}
+pub async fn update_file_byte(
+    SessionId(session_id): SessionId,
+    Json(param): Json<UpdateByteParam>,
+) -> impl IntoResponse {
+    let (Ok(index), Ok(byte)) = (
+        usize::from_str_radix(&param.index, 16),
+        u8::from_str_radix(&param.byte, 16),
+    ) else {
+        return DefaultResponse::error()
+            .msg("偏移或字节格式错误，请输入十六进制数值!".to_string())
+            .into_response();
+    };
+    // 单字节修改也记入编辑历史，可以撤销
+    let operation = EditOperation::Overwrite {
+        offset: index,
+        data: format!("{:02X}", byte),
+    };
+    match EDIT_SESSIONS
+        .lock()
+        .await
+        .edit(&session_id, param.file_id.as_deref(), |document| {
+            document.apply(&operation)
+        }) {
+        None => no_session().into_response(),
+        Some(Err(msg)) => DefaultResponse::error().msg(msg).into_response(),
+        Some(Ok(_)) => DefaultResponse::success().into_response(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HexEditParam {
+    pub file_id: Option<String>,
+    #[serde(flatten)]
+    pub operation: EditOperation,
+}
+//执行覆盖、插入、删除或填充操作
+pub async fn hex_edit(
+    SessionId(session_id): SessionId,
+    Json(param): Json<HexEditParam>,
+) -> impl IntoResponse {
+    let result =
+        EDIT_SESSIONS
+            .lock()
+            .await
+            .edit(&session_id, param.file_id.as_deref(), |document| {
+                document.apply(&param.operation).map(|_| document.state())
+            });
+    match result {
+        None => no_session().into_response(),
+        Some(Err(msg)) => DefaultResponse::error().msg(msg).into_response(),
+        Some(Ok(state)) => DataResponse::success(state).into_response(),
+    }
+}
+
+pub async fn hex_undo(
+    SessionId(session_id): SessionId,
+    Json(param): Json<FileParam>,
+) -> impl IntoResponse {
+    let result =
+        EDIT_SESSIONS
+            .lock()
+            .await
+            .edit(&session_id, param.file_id.as_deref(), |document| {
+                document.undo().then(|| document.state())
+            });
+    match result {
+        None => no_session().into_response(),
+        Some(None) => DefaultResponse::error()
+            .msg("没有可撤销的操作!".to_string())
+            .into_response(),
+        Some(Some(state)) => DataResponse::success(state).into_response(),
+    }
+}
+
+pub async fn hex_redo(
+    SessionId(session_id): SessionId,
+    Json(param): Json<FileParam>,
+) -> impl IntoResponse {
+    let result =
+        EDIT_SESSIONS
+            .lock()
+            .await
+            .edit(&session_id, param.file_id.as_deref(), |document| {
+                document.redo().then(|| document.state())
+            });
+    match result {
+        None => no_session().into_response(),
+        Some(None) => DefaultResponse::error()
+            .msg("没有可恢复的操作!".to_string())
+            .into_response(),
+        Some(Some(state)) => DataResponse::success(state).into_response(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffResult {
+    pub file_id: String,
+    #[serde(flatten)]
+    pub state: EditState,
+    pub hunks: Vec<DiffHunk>,
+}
+//列出未保存的修改与原文件的差异
+pub async fn hex_diff(
+    SessionId(session_id): SessionId,
+    Query(param): Query<FileParam>,
+) -> impl IntoResponse {
+    let mut sessions = EDIT_SESSIONS.lock().await;
+    match sessions.get_mut(&session_id, param.file_id.as_deref()) {
+        None => no_session().into_response(),
+        Some(session) => DataResponse::success(DiffResult {
+            file_id: session.file_id.clone(),
+            state: session.document.state(),
+            hunks: session.document.diff(),
+        })
+        .into_response(),
+    }
+}
+
+//从数据库重新加载文件，放弃未保存的修改；指定的文件未打开时打开该文件
+pub async fn init_upload_file(
+    app_state: State<AppState>,
+    SessionId(session_id): SessionId,
+    Query(param): Query<FileParam>,
+) -> impl IntoResponse {
+    let file_id = match param.file_id {
+        Some(file_id) => file_id,
+        None => match EDIT_SESSIONS.lock().await.get_mut(&session_id, None) {
+            Some(session) => session.file_id.clone(),
+            None => return no_session().into_response(),
+        },
+    };
+    let file_model = match pe_service::find_file_by_id(&app_state, &file_id).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    match open_session(
+        &session_id,
+        &file_model.id,
+        &file_model.file_name,
+        file_model.file_buf,
+    )
+    .await
+    {
+        Ok(_) => DefaultResponse::success().into_response(),
+        Err(msg) => DefaultResponse::error().msg(msg).into_response(),
+    }
+}
+
+pub async fn download_current_file(
+    SessionId(session_id): SessionId,
+    Query(param): Query<FileParam>,
+) -> impl IntoResponse {
+    let mut sessions = EDIT_SESSIONS.lock().await;
+    let (file_name, file_buf) = match sessions.get_mut(&session_id, param.file_id.as_deref()) {
+        None => (String::from("unknown"), vec![]),
+        Some(session) => (session.file_name.clone(), session.document.data().to_vec()),
+    };
+    drop(sessions);
+    let attachment = &format!("attachment; filename={}", file_name);
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_str(attachment).unwrap(),
+    );
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str("application/octet-stream").unwrap(),
+    );
+
+    (headers, file_buf).into_response()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloseParam {
+    pub file_id: String,
+}
+//关闭编辑会话，释放内存
+pub async fn close_upload_file(
+    SessionId(session_id): SessionId,
+    Json(param): Json<CloseParam>,
+) -> impl IntoResponse {
+    EDIT_SESSIONS
+        .lock()
+        .await
+        .close(&session_id, &param.file_id);
+    DefaultResponse::success()
+}
+
+// 未指定行数时每页返回的行数
+const DEFAULT_VIEW_ROWS: u64 = 64;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HexViewParam {
+    // 按页查看，从0开始
+    page: Option<u64>,
+    // 每页行数
+    size: Option<u64>,
+    // 按范围查看，指定后忽略page
+    offset: Option<u64>,
+    length: Option<u64>,
+    // 每行字节数，默认16
+    width: Option<usize>,
+}
+//按页或按偏移范围返回文件的十六进制视图，每次请求只计算需要的行，PE文件同时返回每行所属的结构
+pub async fn hex_view(
+    app_state: State<AppState>,
+    session_id: Option<SessionId>,
+    Path(file_id): Path<String>,
+    Query(param): Query<HexViewParam>,
+) -> impl IntoResponse {
+    let width = param.width.unwrap_or(hex_view::DEFAULT_ROW_WIDTH);
+    if !(1..=hex_view::MAX_ROW_WIDTH).contains(&width) {
+        return DefaultResponse::error()
+            .msg(format!(
+                "每行字节数必须在1到{}之间!",
+                hex_view::MAX_ROW_WIDTH
+            ))
+            .into_response();
+    }
+    let max_rows = hex_view::MAX_ROWS as u64;
+    let size = param.size.unwrap_or(DEFAULT_VIEW_ROWS).clamp(1, max_rows);
+    let (start, length) = match param.offset {
+        Some(offset) => (
+            offset,
+            param
+                .length
+                .unwrap_or(size * width as u64)
+                .min(max_rows * width as u64),
+        ),
+        None => (
+            param.page.unwrap_or(0).saturating_mul(size * width as u64),
+            size * width as u64,
+        ),
+    };
+    let result = with_file_buf(&app_state, session_id.as_ref(), &file_id, move |buf| {
+        let start = start.min(buf.len() as u64) as usize;
+        let end = start.saturating_add(length as usize);
+        let mut rows = hex_view::hex_rows(buf, start, end, width);
+        // PE文件附加结构标注，非PE文件只返回字节
+        if let Ok(image) = PeImage::parse(buf) {
+            let annotations = pe_overlay::annotations(buf, &image, start..end);
+            hex_view::annotate_rows(&mut rows, &annotations, width);
+        }
+        (buf.len() as u64, rows)
+    })
+    .await;
+    let (file_size, rows) = match result {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    let total = file_size.div_ceil(width as u64);
+    let pages = total.div_ceil(size);
+    PaginateResponse::<HexRow>::success(rows, PaginateInfo { total, pages }).into_response()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InspectParam {
+    offset: u64,
+}
+//按常见数据类型解析指定偏移处的字节
+pub async fn hex_inspect(
+    app_state: State<AppState>,
+    session_id: Option<SessionId>,
+    Path(file_id): Path<String>,
+    Query(param): Query<InspectParam>,
+) -> impl IntoResponse {
+    let offset = param.offset;
+    let result = with_file_buf(&app_state, session_id.as_ref(), &file_id, move |buf| {
+        let offset = usize::try_from(offset)
+            .ok()
+            .filter(|offset| *offset < buf.len())?;
+        let image = PeImage::parse(buf).ok();
+        Some(data_inspector::inspect(buf, offset, image.as_ref()))
+    })
+    .await;
+    match result {
+        Ok(Some(data)) => DataResponse::<Inspection>::success(data).into_response(),
+        Ok(None) => DefaultResponse::error()
+            .msg("偏移超出文件大小!".to_string())
+            .into_response(),
+        Err(response) => response.into_response(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitResult {
+    pub file_id: String,
+    pub parent_id: String,
+    pub version: i32,
+    pub hunks: usize,
+}
+//把编辑后的内容保存为新版本文件，记录补丁并在后台重新分析
+pub async fn commit_upload_file(
+    app_state: State<AppState>,
+    SessionId(session_id): SessionId,
+    Json(param): Json<FileParam>,
+) -> impl IntoResponse {
+    let mut sessions = EDIT_SESSIONS.lock().await;
+    let Some(session) = sessions.get_mut(&session_id, param.file_id.as_deref()) else {
+        return no_session().into_response();
+    };
+    if !session.document.state().modified {
+        return DefaultResponse::error()
+            .msg("没有需要保存的修改!".to_string())
+            .into_response();
+    }
+    let parent_id = session.file_id.clone();
+    let file_name = session.file_name.clone();
+    let file_buf = session.document.data().to_vec();
+    let patch = session.document.patch();
+    let generation = session.document.generation();
+    drop(sessions);
+    let parent_version = match t_file::Entity::find_by_id(&parent_id)
+        .select_only()
+        .column(t_file::Column::Version)
+        .into_tuple::<i32>()
+        .one(app_state.db_conn.as_ref())
+        .await
+    {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            return DefaultResponse::error()
+                .msg("原文件已被删除，无法保存新版本!".to_string())
+                .into_response()
+        }
+        Err(err) => {
+            log::error!("find file version error: {} [{}]", err, parent_id);
+            return DefaultResponse::error()
+                .msg("文件查找失败，请重试!".to_string())
+                .into_response();
+        }
+    };
+    let file_id = snowflake_rs::SnowFlakeId::new(1, snowflake_rs::STANDARD_EPOCH)
+        .generate_id()
+        .unwrap()
+        .to_string();
+    let now = chrono::Local::now().naive_local();
+    let active_model = t_file::ActiveModel {
+        id: Set(file_id.clone()),
+        file_name: Set(file_name.clone()),
+        file_md5: Set(format!("{:x}", md5::compute(&file_buf))),
+        file_buf: Set(file_buf.clone()),
+        file_report: Default::default(),
+        create_time: Set(now),
+        modify_time: Set(now),
+        parent_id: Set(Some(parent_id.clone())),
+        version: Set(parent_version + 1),
+        file_patch: Set(serde_json::to_value(&patch).ok()),
+    };
+    let save_error = || {
+        DefaultResponse::error()
+            .msg("保存文件失败，请检查后重试！".to_string())
+            .into_response()
+    };
+    let txn = match app_state.db_conn.begin().await {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!(
+                "begin save file version transaction error: {} [{}]",
+                err,
+                parent_id
+            );
+            return save_error();
+        }
+    };
+    let file_model = match active_model.insert(&txn).await {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("save file version error: {} [{}]", err, parent_id);
+            return save_error();
+        }
+    };
+    // 保存期间会话又有新的编辑时放弃本次保存，避免关闭会话时丢失这些修改
+    {
+        let mut sessions = EDIT_SESSIONS.lock().await;
+        let current = sessions
+            .get_mut(&session_id, Some(&parent_id))
+            .map(|session| session.document.generation());
+        if current.is_some_and(|current| current != generation) {
+            return DefaultResponse::error()
+                .msg("保存期间文件有新的修改，请重新保存!".to_string())
+                .into_response();
+        }
+        if let Err(err) = txn.commit().await {
+            log::error!("commit file version error: {} [{}]", err, parent_id);
+            return save_error();
+        }
+        // 编辑会话切换到新版本，原文件的修改记录随之清空
+        sessions.close(&session_id, &parent_id);
+        if let Err(msg) = sessions.open(&session_id, &file_id, &file_name, file_buf) {
+            log::error!("open edit session error: {} [{}]", msg, file_id);
+        }
+    }
+    // 后台重新分析新版本，analyze_file的计算部分在阻塞线程中执行
+    let state = app_state.0.clone();
+    tokio::spawn(async move {
+        let file_id = file_model.id.clone();
+        if let Err(err) = pe_service::analyze_file(&state, file_model).await {
+            log::error!("analyze file version error: {} [{}]", err, file_id);
+        }
+    });
+    DataResponse::success(CommitResult {
+        file_id,
+        parent_id,
+        version: parent_version + 1,
+        hunks: patch.len(),
+    })
+    .into_response()
+}
@@ -4,27 +4,63 @@ use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
 
 use crate::app::state::AppState;
-use crate::pe::pe_service::UploadFileInfo;
+use crate::pe::hex_service::EditSessions;
 
+pub mod crypto_service;
+pub mod deobfuscate_service;
+pub mod disasm_service;
+pub mod file_analysis;
+pub mod hex_service;
 pub mod pe_service;
+pub mod search_service;
+pub mod strings_service;
 
-pub static UPLOAD_FILE_INFO: Lazy<Mutex<Option<UploadFileInfo>>> = Lazy::new(|| Mutex::new(None));
+pub static EDIT_SESSIONS: Lazy<Mutex<EditSessions>> =
+    Lazy::new(|| Mutex::new(EditSessions::default()));
 pub fn get_routers(app_state: AppState) -> Router {
     Router::new().nest(
         "/pe",
         Router::new()
             .route("/upload", post(pe_service::upload))
-            .route("/update_file_byte", post(pe_service::update_file_byte))
-            .route("/init_upload_file", post(pe_service::init_upload_file))
+            .route("/update_file_byte", post(hex_service::update_file_byte))
+            .route("/init_upload_file", post(hex_service::init_upload_file))
             .route(
                 "/download_current_file",
-                get(pe_service::download_current_file),
+                get(hex_service::download_current_file),
             )
             .route(
                 "/get_upload_file_info",
-                get(pe_service::get_upload_file_info),
+                get(hex_service::get_upload_file_info),
             )
+            .route("/close_upload_file", post(hex_service::close_upload_file))
+            .route("/hex_view/:file_id", get(hex_service::hex_view))
+            .route("/hex_edit", post(hex_service::hex_edit))
+            .route("/hex_undo", post(hex_service::hex_undo))
+            .route("/hex_redo", post(hex_service::hex_redo))
+            .route("/hex_diff", get(hex_service::hex_diff))
+            .route("/hex_search/:file_id", get(search_service::search))
+            .route("/hex_inspect/:file_id", get(hex_service::hex_inspect))
+            .route("/commit_upload_file", post(hex_service::commit_upload_file))
             .route("/analysis/:file_id", get(pe_service::analysis))
+            .route("/disasm/:file_id", get(disasm_service::disasm))
+            .route("/cfg/:file_id", get(disasm_service::control_flow_graph))
+            .route("/crypto/:file_id", get(crypto_service::crypto_signatures))
+            .route(
+                "/extract_strings/:file_id",
+                post(strings_service::extract_strings),
+            )
+            .route(
+                "/string_page_list/:file_id",
+                get(strings_service::string_page_list),
+            )
+            .route(
+                "/deobfuscate/:file_id",
+                post(deobfuscate_service::deobfuscate),
+            )
+            .route(
+                "/deobfuscated_page_list/:file_id",
+                get(deobfuscate_service::deobfuscated_page_list),
+            )
             .with_state(app_state),
     )
 }
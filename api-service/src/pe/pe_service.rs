@@ -1,45 +1,117 @@
 use axum::extract::{Multipart, Path, State};
-use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
-use axum::http::{HeaderMap, HeaderValue};
 use axum::response::IntoResponse;
-use axum::Json;
 use byte_unit::{Byte, Unit, UnitType};
-use entity::model::{t_file, t_knowledge};
+use entity::model::t_file;
+use migration::sea_orm::prelude::DateTime;
 use migration::sea_orm::ColumnTrait;
-use migration::sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, Set};
-use migration::{Expr, Value};
-use rayon::prelude::*;
+use migration::sea_orm::{
+    ActiveModelTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use migration::{Condition, Expr, Value};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str;
+use std::sync::Arc;
 
 use crate::app::response::{DataResponse, DefaultResponse};
+use crate::app::session::SessionId;
 use crate::app::state::AppState;
-use crate::pe::UPLOAD_FILE_INFO;
-use crate::tools::{self, pe_tools};
+use crate::attack::attack_service;
+use crate::capability::capability_service;
+use crate::hash_list::hash_list_service::{self, HashListHit};
+use crate::ioc::ioc_service;
+use crate::knowledge::knowledge_service::{self, KnowledgeIndex};
+use crate::pe::file_analysis::{self, ANALYZER_RISK};
+use crate::pe::hex_service;
+use crate::risk::risk_service;
+use crate::rule::rule_service::{self, CompiledRule, RuleHit};
+use crate::tools;
+use crate::tools::anomaly::{self, PeAnomaly};
+use crate::tools::attack::{self, AttackFinding, AttackSummary, TechniqueInfo};
+use crate::tools::capability::{self, CapabilityHit, CapabilityRule};
+use crate::tools::crypto::{self, CryptoHit};
+use crate::tools::file_hash::FileHashes;
+use crate::tools::ioc::{self, Ioc};
+use crate::tools::knowledge::{self, KnowledgeHit, Severity};
+use crate::tools::packer::{self, PackerHit};
+use crate::tools::pe_image::PeImage;
+use crate::tools::pe_read::ReportSection;
+use crate::tools::risk::{self, RiskInput, RiskScore};
+use crate::tools::signature::{self, SignatureStatus};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct UploadFileInfo {
-    pub file_id: String,
-    pub file_name: String,
-    pub file_size: String,
-    pub address_group_information: Vec<AddressGroupInformation>,
+//根据ID查询文件，查询失败时返回可直接响应的错误信息
+pub async fn find_file_by_id(
+    app_state: &AppState,
+    file_id: &str,
+) -> Result<t_file::Model, DefaultResponse> {
+    match t_file::Entity::find_by_id(file_id)
+        .one(app_state.db_conn.as_ref())
+        .await
+    {
+        Ok(Some(data)) => Ok(data),
+        Ok(None) => Err(DefaultResponse::error().msg("文件为空，请重试!".to_string())),
+        Err(err) => {
+            log::error!("find file by id error: {} [{}]", err, file_id);
+            Err(DefaultResponse::error().msg("文件查找失败，请重试!".to_string()))
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct AddressGroupInformation {
-    pub address: String,
-    pub bytes: Vec<ByteInformation>,
-    pub translation: String,
+//按条件筛选文件，用于批量分析
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileFilter {
+    // 为空时不限制
+    #[serde(default)]
+    pub file_ids: Vec<String>,
+    // 按文件名或MD5模糊过滤
+    pub name: Option<String>,
+    // 按上传时间过滤
+    pub start_time: Option<DateTime>,
+    pub end_time: Option<DateTime>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ByteInformation {
-    pub index: u64,
-    pub bytes: String,
+//查询符合条件的文件ID，按上传时间排序
+pub async fn find_file_ids(
+    app_state: &AppState,
+    filter: &FileFilter,
+    limit: Option<u64>,
+) -> Result<Vec<String>, DefaultResponse> {
+    let mut select = t_file::Entity::find()
+        .select_only()
+        .column(t_file::Column::Id);
+    if !filter.file_ids.is_empty() {
+        select = select.filter(t_file::Column::Id.is_in(filter.file_ids.clone()));
+    }
+    if let Some(name) = &filter.name {
+        select = select.filter(
+            Condition::any()
+                .add(t_file::Column::FileName.like(format!("%{}%", name)))
+                .add(t_file::Column::FileMd5.like(format!("%{}%", name))),
+        );
+    }
+    if let Some(start_time) = filter.start_time {
+        select = select.filter(t_file::Column::CreateTime.gte(start_time));
+    }
+    if let Some(end_time) = filter.end_time {
+        select = select.filter(t_file::Column::CreateTime.lte(end_time));
+    }
+    select
+        .order_by_asc(t_file::Column::CreateTime)
+        .limit(limit)
+        .into_tuple::<String>()
+        .all(app_state.db_conn.as_ref())
+        .await
+        .map_err(|err| {
+            log::error!("find file ids error: {}", err);
+            DefaultResponse::error().msg("数据查询错误, 请稍后再试!".to_string())
+        })
 }
 
-pub async fn upload(app_state: State<AppState>, mut multipart: Multipart) -> impl IntoResponse {
+pub async fn upload(
+    app_state: State<AppState>,
+    session_id: Option<SessionId>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
     let filed = match multipart.next_field().await {
         Ok(data) => match data {
             None => {
@@ -91,22 +163,57 @@ pub async fn upload(app_state: State<AppState>, mut multipart: Multipart) -> imp
         file_report: Default::default(),
         create_time: Set(chrono::Local::now().naive_local()),
         modify_time: Set(chrono::Local::now().naive_local()),
+        parent_id: Set(None),
+        version: Set(1),
+        file_patch: Set(None),
     };
     match file_active_model.insert(app_state.db_conn.as_ref()).await {
         Ok(_) => {
-            let mut upload_file_info = UPLOAD_FILE_INFO.lock().await;
-            *upload_file_info = Some(UploadFileInfo {
-                file_id,
-                file_name,
-                file_size: Byte::from_f64_with_unit(file_bytes.len() as f64, Unit::B)
-                    .unwrap()
-                    .get_appropriate_unit(UnitType::Decimal)
-                    .to_string(),
-                address_group_information: get_address_group_information_from_file_buf(
+            // 先检查哈希名单，命中时直接记录判定结果
+            let image = PeImage::parse(&file_bytes).ok();
+            let hashes = FileHashes::compute(&file_bytes, image.as_ref());
+            let hash_list_hit =
+                hash_list_service::check_hashes(app_state.db_conn.as_ref(), &hashes).await;
+            if let Ok(Some(hit)) = &hash_list_hit {
+                let risk = risk::listed_score(hit.list_type, hit.message());
+                if let Err(err) = file_analysis::save_analysis(
+                    app_state.db_conn.as_ref(),
+                    &file_id,
+                    ANALYZER_RISK,
+                    &risk,
+                )
+                .await
+                {
+                    log::error!("save file risk error: {} [{}]", err, file_id);
+                }
+            }
+            // 带会话标识时直接打开该文件的编辑会话，失败时不影响上传结果
+            if let Some(SessionId(session_id)) = session_id {
+                if let Err(msg) = hex_service::open_session(
+                    &session_id,
+                    &file_id,
+                    &file_name,
                     file_bytes.to_vec(),
-                ),
-            });
-            DefaultResponse::success().into_response()
+                )
+                .await
+                {
+                    log::error!("open edit session error: {} [{}]", msg, file_id);
+                }
+            }
+            match hash_list_hit {
+                Ok(Some(hit)) => {
+                    let msg = hit.message();
+                    DataResponse::success(hit).msg(msg).into_response()
+                }
+                Ok(None) => DefaultResponse::success().into_response(),
+                // 名单查询失败时不能确定文件是否在黑名单中，需要提示用户
+                Err(err) => {
+                    log::error!("find hash list error: {} [{}]", err, file_id);
+                    DefaultResponse::error()
+                        .msg("文件已保存，但哈希名单查询失败，请稍后重新分析!".to_string())
+                        .into_response()
+                }
+            }
         }
         Err(err) => {
             log::error!("save file error: {}", err.to_string());
@@ -117,239 +224,504 @@ pub async fn upload(app_state: State<AppState>, mut multipart: Multipart) -> imp
     }
 }
 
-fn get_address_group_information_from_file_buf(file_buf: Vec<u8>) -> Vec<AddressGroupInformation> {
-    let address_group_num = (file_buf.len() as f64 / 16f64).ceil() as u64;
-    let mut information_list = (1..address_group_num)
-        .into_par_iter()
-        .map(|index| {
-            let end_byte_index = index * 16 - 1;
-            let start_byte_index = end_byte_index - 15;
-            let bytes_seq = file_buf
-                .iter()
-                .enumerate()
-                .filter(|(index, _item)| {
-                    index <= &(end_byte_index as usize) && index >= &(start_byte_index as usize)
-                })
-                .collect::<Vec<_>>();
-            let byte_information = bytes_seq
-                .iter()
-                .map(|(index, item)| ByteInformation {
-                    index: *index as u64,
-                    bytes: format!("{:02X}", item),
-                })
-                .collect::<Vec<_>>();
-            let translation = bytes_seq
-                .iter()
-                .map(|(_index, item)| {
-                    if **item >= 33u8 && **item <= 126u8 {
-                        String::from_utf8(vec![**item]).unwrap_or_else(|_| String::from("."))
-                    } else {
-                        String::from(".")
-                    }
-                })
-                .collect::<Vec<_>>();
-            AddressGroupInformation {
-                address: format!("{:08X}", end_byte_index - 15),
-                bytes: byte_information,
-                translation: translation.join(""),
-            }
-        })
-        .collect::<Vec<_>>();
-    information_list.sort_by(|a, b| a.address.cmp(&b.address));
-    information_list
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AnalysisResult {
+    pub file_id: String,
+    pub file_name: String,
+    pub message: String,
+    pub sensitive_functions: Vec<String>,
+    pub knowledge_hits: Vec<KnowledgeHit>,
+    pub iocs: Vec<Ioc>,
+    pub crypto: Vec<CryptoHit>,
+    pub rule_matches: Vec<RuleHit>,
+    pub capabilities: Vec<CapabilityHit>,
+    pub attack: AttackSummary,
+    pub anomalies: Vec<PeAnomaly>,
+    pub packers: Vec<PackerHit>,
+    pub signature: Option<SignatureStatus>,
+    pub hashes: FileHashes,
+    // 命中哈希名单时跳过其余检测
+    pub hash_list: Option<HashListHit>,
+    pub risk: RiskScore,
 }
 
-pub async fn analysis(
-    app_state: State<AppState>,
-    Path(file_id): Path<String>,
-) -> impl IntoResponse {
-    let select = t_file::Entity::find().filter(t_file::Column::Id.eq(file_id));
-    // log::info!("{}", select.build(DbBackend::Postgres).to_string());
-    let file = select.one(app_state.db_conn.as_ref()).await.unwrap();
-    let mut file_buf = Vec::new();
-    let mut file_name = "".to_string();
-    let mut id = "".to_string();
-    if let Some(model) = file {
-        file_buf = model.file_buf;
-        file_name = model.file_name;
-        id = model.id;
-    }
-    let file_size = Byte::from_f64_with_unit(file_buf.len() as f64, Unit::B)
+pub fn file_size_text(len: usize) -> String {
+    Byte::from_f64_with_unit(len as f64, Unit::B)
         .unwrap()
         .get_appropriate_unit(UnitType::Decimal)
-        .to_string();
-    let pe_study = match tools::pe_read::read_exe_file(hex::encode(file_buf), file_name, file_size)
-    {
-        Ok(data) => data,
-        Err(err) => {
-            return DefaultResponse::error()
-                .msg(err.to_string())
-                .into_response()
-        }
-    };
-    let table_byname = &pe_study.byname_information;
-    let knowledge = t_knowledge::Entity::find()
-        .all(app_state.db_conn.as_ref())
-        .await
-        .unwrap_or_else(|err| {
-            log::error!("get knowledge error: {}", err.to_string());
-            Vec::new()
-        });
-    let knowledge_map: HashMap<String, String> = knowledge
-        .iter()
-        .map(|entity| {
-            (
-                pe_tools::vec_to_string(entity.func_name.clone().as_bytes()),
-                format!(
-                    "{}:{}",
-                    entity.func_name.clone(),
-                    entity
-                        .func_desc
-                        .as_ref()
-                        .map_or_else(String::new, |desc| desc.clone()),
-                ),
-            )
-        })
-        .collect();
-    let mut error_message: Vec<String> = Vec::new();
-    for element in table_byname {
-        'map_for: for (key, value) in &knowledge_map {
-            if pe_tools::fuzzy_search(element, key) && element.len() >= 6 {
-                error_message.push(value.to_string());
-            } else {
-                continue 'map_for;
-            }
-        }
-    }
-    if !error_message.is_empty() {
-        let msg = format!(
-            "该可执行程序运行可能会尝试调用{}个系统函数，可能会对计算机造成损害。分别为：{:?}",
-            error_message.len(),
-            &error_message
-        );
-        let res = t_file::Entity::update_many()
-            .col_expr(
-                t_file::Column::FileReport,
-                Expr::value(Value::Bytes(Some(Box::new(
-                    pe_study.generate_report(msg.clone()).as_bytes().to_vec(),
-                )))),
-            )
-            .filter(t_file::Column::Id.eq(id))
-            .exec(app_state.db_conn.as_ref())
-            .await;
-        if res.is_err() {
-            log::error!("{:?}", res);
-        };
-        DefaultResponse::success().msg(msg).into_response()
-    } else {
-        let msg = "未检测到异常".to_string();
-        let res = t_file::Entity::update_many()
-            .col_expr(
-                t_file::Column::FileReport,
-                Expr::value(Value::Bytes(Some(Box::new(
-                    pe_study.generate_report(msg.clone()).as_bytes().to_vec(),
-                )))),
-            )
-            .filter(t_file::Column::Id.eq(id))
-            .exec(app_state.db_conn.as_ref())
-            .await;
-        if res.is_err() {
-            log::error!("{:?}", res);
-        };
-        DefaultResponse::success().msg(msg).into_response()
+        .to_string()
+}
+
+fn hash_section(hashes: &FileHashes) -> ReportSection {
+    ReportSection {
+        title: "文件哈希".to_string(),
+        lines: hashes
+            .values()
+            .into_iter()
+            .map(|(hash_type, value)| format!("{}: {}", hash_type.display_name(), value))
+            .collect(),
     }
 }
 
-pub async fn get_upload_file_info() -> impl IntoResponse {
-    let upload_file_info = UPLOAD_FILE_INFO.lock().await;
-    let upload_file_info = upload_file_info.clone();
-    DataResponse::success(upload_file_info).into_response()
+async fn save_report(app_state: &AppState, id: &str, report: String) {
+    let res = t_file::Entity::update_many()
+        .col_expr(
+            t_file::Column::FileReport,
+            Expr::value(Value::Bytes(Some(Box::new(report.into_bytes())))),
+        )
+        .filter(t_file::Column::Id.eq(id))
+        .exec(app_state.db_conn.as_ref())
+        .await;
+    if res.is_err() {
+        log::error!("{:?}", res);
+    };
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct UpdateByteParam {
-    pub index: String,
-    pub byte: String,
+//命中哈希名单的文件不再执行其余检测，只记录判定原因，返回分析结果与报告
+fn analyze_listed_file(
+    id: String,
+    file_name: String,
+    file_buf: &[u8],
+    hashes: FileHashes,
+    hit: HashListHit,
+) -> (AnalysisResult, Option<String>) {
+    let risk = risk::listed_score(hit.list_type, hit.message());
+    let msg = format!(
+        "风险评分{}分（{}）。{}",
+        risk.score,
+        risk.verdict.display_name(),
+        hit.message()
+    );
+    // 非PE文件无法生成结构报告，只返回判定结果
+    let file_size = file_size_text(file_buf.len());
+    let report =
+        match tools::pe_read::read_exe_file(hex::encode(file_buf), file_name.clone(), file_size) {
+            Ok(pe_study) => {
+                let sections = vec![
+                    ReportSection {
+                        title: format!(
+                            "风险评分: {}/100（{}）",
+                            risk.score,
+                            risk.verdict.display_name()
+                        ),
+                        lines: vec![hit.message()],
+                    },
+                    hash_section(&hashes),
+                ];
+                Some(pe_study.generate_report(msg.clone(), &sections))
+            }
+            Err(err) => {
+                log::error!("read listed file error: {} [{}]", err, id);
+                None
+            }
+        };
+    let result = AnalysisResult {
+        file_id: id,
+        file_name,
+        message: msg,
+        sensitive_functions: Vec::new(),
+        knowledge_hits: Vec::new(),
+        iocs: Vec::new(),
+        crypto: Vec::new(),
+        rule_matches: Vec::new(),
+        capabilities: Vec::new(),
+        attack: AttackSummary::default(),
+        anomalies: Vec::new(),
+        packers: Vec::new(),
+        signature: None,
+        hashes,
+        hash_list: Some(hit),
+        risk,
+    };
+    (result, report)
 }
-pub async fn update_file_byte(Json(param): Json<UpdateByteParam>) -> impl IntoResponse {
-    let mut file_info = UPLOAD_FILE_INFO.lock().await;
-    if file_info.is_none() {
-        return DefaultResponse::error()
-            .msg("索引信息为空，请重新上传文件!".to_string())
-            .into_response();
-    }
-    let mut file_info_clone = file_info.clone().unwrap();
-    file_info_clone
-        .address_group_information
-        .iter_mut()
-        .for_each(|item| {
-            item.bytes.iter_mut().for_each(|item| {
-                if item.index == u64::from_str_radix(&param.index, 16).unwrap() {
-                    item.bytes = param.byte.clone();
-                }
-            })
-        });
-    *file_info = Some(file_info_clone);
-    DefaultResponse::success().into_response()
+
+//分析使用的规则与配置，在异步上下文中从数据库加载
+struct AnalysisContext {
+    knowledge_index: Arc<KnowledgeIndex>,
+    rule_set: Arc<Vec<CompiledRule>>,
+    capability_rules: Arc<Vec<CapabilityRule>>,
+    techniques: HashMap<String, TechniqueInfo>,
+    weights: HashMap<String, i64>,
 }
 
-pub async fn init_upload_file(app_state: State<AppState>) -> impl IntoResponse {
-    let mut upload_file_info = UPLOAD_FILE_INFO.lock().await;
-    let upload_file_info_clone = upload_file_info.clone();
-    match upload_file_info_clone {
-        None => {}
-        Some(file_info) => {
-            let file_id = file_info.file_id;
-            let file_name = file_info.file_name;
-            let file_info = entity::model::t_file::Entity::find_by_id(&file_id)
-                .one(app_state.db_conn.as_ref())
-                .await;
-            if let Ok(Some(file_model)) = file_info {
-                let file_buf = file_model.file_buf;
-                *upload_file_info = Some(UploadFileInfo {
-                    file_id,
-                    file_name,
-                    file_size: Byte::from_f64_with_unit(file_buf.len() as f64, Unit::B)
-                        .unwrap()
-                        .get_appropriate_unit(UnitType::Decimal)
-                        .to_string(),
-                    address_group_information: get_address_group_information_from_file_buf(
-                        file_buf,
-                    ),
-                });
+//对单个文件执行完整分析，并更新文件报告
+//数据库读写在异步上下文中执行，计算部分放到阻塞线程，避免占用异步运行时的工作线程
+pub async fn analyze_file(
+    app_state: &AppState,
+    file_model: t_file::Model,
+) -> anyhow::Result<AnalysisResult> {
+    let db = app_state.db_conn.as_ref();
+    let t_file::Model {
+        id,
+        file_name,
+        file_buf,
+        ..
+    } = file_model;
+    let (file_buf, hashes) = tokio::task::spawn_blocking(move || {
+        let image = PeImage::parse(&file_buf).ok();
+        let hashes = FileHashes::compute(&file_buf, image.as_ref());
+        (file_buf, hashes)
+    })
+    .await?;
+    let hash_list_hit = hash_list_service::check_hashes(db, &hashes)
+        .await
+        .map_err(|err| {
+            log::error!("find hash list error: {} [{}]", err, id);
+            anyhow::anyhow!("哈希名单查询失败，请稍后重试!")
+        })?;
+    let file_id = id.clone();
+    let (result, report) = match hash_list_hit {
+        Some(hit) => {
+            tokio::task::spawn_blocking(move || {
+                analyze_listed_file(id, file_name, &file_buf, hashes, hit)
+            })
+            .await?
+        }
+        None => {
+            let context = AnalysisContext {
+                knowledge_index: knowledge_service::load_index(db).await,
+                rule_set: rule_service::load_rules(db).await,
+                capability_rules: capability_service::load_rules(db).await,
+                techniques: attack_service::load_techniques(db).await,
+                weights: risk_service::load_weights(db).await,
+            };
+            let (result, report) = tokio::task::spawn_blocking(move || {
+                analyze_content(id, file_name, &file_buf, hashes, &context)
+            })
+            .await??;
+            if let Err(err) = ioc_service::save_file_iocs(db, &file_id, &result.iocs).await {
+                log::error!("save file ioc error: {} [{}]", err, file_id);
             }
+            (result, Some(report))
         }
+    };
+    // 保存风险评分，回溯检测时用于对比判定结果的变化
+    if let Err(err) = file_analysis::save_analysis(db, &file_id, ANALYZER_RISK, &result.risk).await
+    {
+        log::error!("save file risk error: {} [{}]", err, file_id);
+    }
+    if let Some(report) = report {
+        save_report(app_state, &file_id, report).await;
     }
-    DefaultResponse::success().into_response()
+    Ok(result)
 }
 
-pub async fn download_current_file() -> impl IntoResponse {
-    let file = UPLOAD_FILE_INFO.lock().await;
-    let file_clone = file.clone();
-    drop(file);
-    let (file_name, file_buf) = match file_clone {
-        None => (String::from("unknown"), vec![]),
-        Some(file) => {
-            let file_name = file.file_name;
-            let mut file_buf = Vec::new();
-            file.address_group_information.iter().for_each(|item| {
-                item.bytes.iter().for_each(|item| {
-                    file_buf.push(u8::from_str_radix(&item.bytes, 16).unwrap_or(0));
-                })
+//执行各项检测并生成报告，不访问数据库，需要在阻塞线程中调用
+fn analyze_content(
+    id: String,
+    file_name: String,
+    file_buf: &[u8],
+    hashes: FileHashes,
+    context: &AnalysisContext,
+) -> anyhow::Result<(AnalysisResult, String)> {
+    let image = PeImage::parse(file_buf).ok();
+    let iocs = ioc::extract_iocs(file_buf);
+    let crypto = crypto::detect_crypto(image.as_ref(), file_buf);
+    let anomalies = image
+        .as_ref()
+        .map(|image| anomaly::detect_anomalies(image, file_buf))
+        .unwrap_or_default();
+    let packers = image
+        .as_ref()
+        .map(|image| packer::detect_packers(image, file_buf))
+        .unwrap_or_default();
+    let signature = image
+        .as_ref()
+        .map(|image| signature::check_signature(image, file_buf));
+    let rule_matches = rule_service::scan_rules(&context.rule_set, file_buf, image.as_ref());
+    let capabilities =
+        capability::detect_capabilities(image.as_ref(), file_buf, &context.capability_rules);
+    let file_size = file_size_text(file_buf.len());
+    let pe_study =
+        tools::pe_read::read_exe_file(hex::encode(file_buf), file_name.clone(), file_size)?;
+    let table_byname = &pe_study.byname_information;
+    let knowledge_index = &context.knowledge_index;
+    // 优先使用带DLL名称的导入表，无法解析时使用Byname表中的函数名
+    let imports: Vec<(Option<String>, String)> = match image.as_ref() {
+        Some(image) => knowledge::named_imports(image),
+        None => table_byname
+            .iter()
+            .map(|element| {
+                (
+                    None,
+                    String::from_utf8_lossy(&hex::decode(element).unwrap_or_default()).to_string(),
+                )
+            })
+            .collect(),
+    };
+    let mut knowledge_hits: Vec<KnowledgeHit> = Vec::new();
+    for (dll, name) in &imports {
+        for index in knowledge_index.matcher.match_import(dll.as_deref(), name) {
+            let entity = &knowledge_index.entries[index];
+            if let Some(hit) = knowledge_hits.iter_mut().find(|hit| hit.id == entity.id) {
+                if !hit.imports.contains(name) {
+                    hit.imports.push(name.clone());
+                }
+                continue;
+            }
+            // 非敏感条目只作为提示信息
+            let severity = if entity.is_sensitive {
+                Severity::parse(&entity.severity).unwrap_or(Severity::Medium)
+            } else {
+                Severity::Info
+            };
+            knowledge_hits.push(KnowledgeHit {
+                id: entity.id.clone(),
+                func_name: entity.func_name.clone(),
+                func_desc: entity.func_desc.clone(),
+                severity,
+                categories: attack::split_tags(entity.categories.as_deref()),
+                imports: vec![name.clone()],
             });
-            (file_name, file_buf)
         }
-    };
-    let attachment = &format!("attachment; filename={}", file_name);
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        CONTENT_DISPOSITION,
-        HeaderValue::from_str(attachment).unwrap(),
+    }
+    // 严重级别高的排在前面
+    knowledge_hits.sort_by_key(|hit| std::cmp::Reverse(hit.severity));
+    let mut error_message: Vec<String> = Vec::new();
+    let mut attack_findings: Vec<AttackFinding> = Vec::new();
+    for hit in &knowledge_hits {
+        if hit.severity != Severity::Info {
+            error_message.push(format!(
+                "{}:{}",
+                hit.func_name,
+                hit.func_desc.clone().unwrap_or_default()
+            ));
+        }
+    }
+    for entity in knowledge_index
+        .entries
+        .iter()
+        .filter(|entity| knowledge_hits.iter().any(|hit| hit.id == entity.id))
+    {
+        for technique_id in attack::split_tags(entity.technique_ids.as_deref()) {
+            attack_findings.push(AttackFinding {
+                technique_id,
+                tactics: attack::split_tags(entity.tactics.as_deref()),
+                source: format!("知识库: {}", entity.func_name),
+            });
+        }
+    }
+    for hit in &capabilities {
+        attack_findings.push(AttackFinding {
+            technique_id: hit.technique_id.clone(),
+            tactics: Vec::new(),
+            source: format!("能力: {}", hit.name),
+        });
+    }
+    let attack = attack::summarize(&attack_findings, &context.techniques);
+    let rule_names = rule_matches
+        .iter()
+        .map(|hit| format!("{}: {}", hit.rule_name, hit.matched.rule))
+        .collect::<Vec<_>>();
+    let capability_names = capabilities
+        .iter()
+        .map(|hit| format!("[{}] {}", hit.technique_id, hit.name))
+        .collect::<Vec<_>>();
+    let risk = risk::score(
+        &RiskInput {
+            knowledge: &knowledge_hits,
+            anomalies: &anomalies,
+            packers: &packers,
+            signature: signature.as_ref(),
+            rule_hits: &rule_names,
+            capabilities: &capability_names,
+        },
+        &context.weights,
     );
-    headers.insert(
-        CONTENT_TYPE,
-        HeaderValue::from_str("application/octet-stream").unwrap(),
+    let detail = if !error_message.is_empty() {
+        format!(
+            "该可执行程序运行可能会尝试调用{}个系统函数，可能会对计算机造成损害。分别为：{:?}",
+            error_message.len(),
+            &error_message
+        )
+    } else {
+        "未检测到异常".to_string()
+    };
+    let msg = format!(
+        "风险评分{}分（{}）。{}",
+        risk.score,
+        risk.verdict.display_name(),
+        detail
     );
+    let mut sections = vec![
+        ReportSection {
+            title: format!(
+                "风险评分: {}/100（{}）",
+                risk.score,
+                risk.verdict.display_name()
+            ),
+            lines: risk
+                .breakdown
+                .iter()
+                .map(|item| format!("[{}] {} {:+}分", item.category, item.item, item.points))
+                .collect(),
+        },
+        hash_section(&hashes),
+        ReportSection {
+            title: format!("知识库命中（共{}项）", knowledge_hits.len()),
+            lines: knowledge_hits
+                .iter()
+                .map(|hit| {
+                    let mut line = format!(
+                        "[{}] {} 导入: {}",
+                        hit.severity.display_name(),
+                        hit.func_name,
+                        hit.imports.join(", ")
+                    );
+                    if !hit.categories.is_empty() {
+                        line.push_str(&format!(" 分类: {}", hit.categories.join(", ")));
+                    }
+                    line
+                })
+                .collect(),
+        },
+        ReportSection {
+            title: format!("结构异常（共{}项）", anomalies.len()),
+            lines: anomalies
+                .iter()
+                .map(|item| item.description.clone())
+                .collect(),
+        },
+        ReportSection {
+            title: "加壳检测".to_string(),
+            lines: packers
+                .iter()
+                .map(|hit| format!("{} 依据: {}", hit.name, hit.evidence))
+                .collect(),
+        },
+        ReportSection {
+            title: "数字签名".to_string(),
+            lines: signature
+                .iter()
+                .map(|status| status.message.clone())
+                .collect(),
+        },
+        ReportSection {
+            title: format!("检测到的能力（共{}项）", capabilities.len()),
+            lines: capabilities
+                .iter()
+                .map(|hit| {
+                    let functions = hit
+                        .functions
+                        .iter()
+                        .map(|rva| format!("0x{:X}", rva))
+                        .collect::<Vec<_>>();
+                    if functions.is_empty() {
+                        format!(
+                            "[{}] {} 依据: {}",
+                            hit.technique_id,
+                            hit.name,
+                            hit.evidence.join(", ")
+                        )
+                    } else {
+                        format!(
+                            "[{}] {} 函数: {} 依据: {}",
+                            hit.technique_id,
+                            hit.name,
+                            functions.join(", "),
+                            hit.evidence.join(", ")
+                        )
+                    }
+                })
+                .collect(),
+        },
+        ReportSection {
+            title: format!("提取的IOC（共{}个）", iocs.len()),
+            lines: iocs
+                .iter()
+                .map(|item| format!("[{}] {}", item.ioc_type.as_str(), item.value))
+                .collect(),
+        },
+        ReportSection {
+            title: format!("加密算法特征（共{}处）", crypto.len()),
+            lines: crypto
+                .iter()
+                .map(|hit| {
+                    format!(
+                        "[{}] {} 偏移: 0x{:X}",
+                        hit.algorithm.as_str(),
+                        hit.constant,
+                        hit.offset
+                    )
+                })
+                .collect(),
+        },
+        ReportSection {
+            title: format!("规则命中（共{}条）", rule_matches.len()),
+            lines: rule_matches
+                .iter()
+                .map(|hit| {
+                    let offsets = hit
+                        .matched
+                        .strings
+                        .iter()
+                        .map(|item| format!("{}@0x{:X}", item.identifier, item.offset))
+                        .collect::<Vec<_>>();
+                    format!(
+                        "[{}] {} {}",
+                        hit.rule_name,
+                        hit.matched.rule,
+                        offsets.join(", ")
+                    )
+                })
+                .collect(),
+        },
+    ];
+    for tactic in &attack.tactics {
+        sections.push(ReportSection {
+            title: format!("ATT&CK战术: {}（{}）", tactic.name, tactic.display_name),
+            lines: tactic
+                .techniques
+                .iter()
+                .map(|technique| {
+                    format!(
+                        "[{}] {} 来源: {}",
+                        technique.technique_id,
+                        technique.technique_name,
+                        technique.findings.join(", ")
+                    )
+                })
+                .collect(),
+        });
+    }
+    let report = pe_study.generate_report(msg.clone(), &sections);
+    let result = AnalysisResult {
+        file_id: id,
+        file_name,
+        message: msg,
+        sensitive_functions: error_message,
+        knowledge_hits,
+        iocs,
+        crypto,
+        rule_matches,
+        capabilities,
+        attack,
+        anomalies,
+        packers,
+        signature,
+        hashes,
+        hash_list: None,
+        risk,
+    };
+    Ok((result, report))
+}
 
-    (headers, file_buf).into_response()
+pub async fn analysis(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+) -> impl IntoResponse {
+    let file_model = match find_file_by_id(&app_state, &file_id).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    match analyze_file(&app_state, file_model).await {
+        Ok(result) => {
+            let msg = result.message.clone();
+            DataResponse::success(result).msg(msg).into_response()
+        }
+        Err(err) => DefaultResponse::error()
+            .msg(err.to_string())
+            .into_response(),
+    }
 }
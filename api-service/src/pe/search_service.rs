@@ -0,0 +1,74 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::session::SessionId;
+use crate::app::state::AppState;
+use crate::pe::hex_service;
+use crate::tools::byte_search::{self, SearchMatch, SearchMode, TextEncoding};
+use crate::tools::pe_image::PeImage;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchParam {
+    page: u64,
+    size: u64,
+    mode: SearchMode,
+    pattern: String,
+    // 文本搜索的编码，默认同时搜索ASCII与UTF-16LE
+    encoding: Option<TextEncoding>,
+    #[serde(default)]
+    case_insensitive: bool,
+}
+//在文件内容中搜索十六进制、文本或正则，当前会话正在编辑该文件时搜索编辑后的内容
+pub async fn search(
+    app_state: State<AppState>,
+    session_id: Option<SessionId>,
+    Path(file_id): Path<String>,
+    Query(param): Query<SearchParam>,
+) -> impl IntoResponse {
+    let regex = match byte_search::compile(
+        param.mode,
+        &param.pattern,
+        param.encoding.unwrap_or(TextEncoding::All),
+        param.case_insensitive,
+    ) {
+        Ok(data) => data,
+        Err(msg) => return DefaultResponse::error().msg(msg).into_response(),
+    };
+    if param.size == 0 {
+        return PaginateResponse::<SearchMatch>::success(Vec::new(), PaginateInfo::default())
+            .into_response();
+    }
+    let overlapping = param.mode != SearchMode::Regex;
+    // 只生成当前页的匹配详情，其余匹配只计数
+    let skip = param.page.saturating_mul(param.size) as usize;
+    let take = param.size as usize;
+    let result =
+        hex_service::with_file_buf(&app_state, session_id.as_ref(), &file_id, move |buf| {
+            // 非PE文件同样可以搜索，只是没有RVA与节信息
+            let image = PeImage::parse(buf).ok();
+            byte_search::search(buf, image.as_ref(), &regex, overlapping, skip, take)
+        })
+        .await;
+    let result = match result {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    let total = result.total as u64;
+    if total == 0 {
+        return PaginateResponse::<SearchMatch>::success(Vec::new(), PaginateInfo::default())
+            .into_response();
+    }
+    let pages = total.div_ceil(param.size);
+    let response = PaginateResponse::success(result.matches, PaginateInfo { total, pages });
+    if result.truncated {
+        return response
+            .msg(format!(
+                "匹配结果过多，只返回前{}条!",
+                byte_search::MAX_MATCHES
+            ))
+            .into_response();
+    }
+    response.into_response()
+}
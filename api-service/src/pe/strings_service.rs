@@ -0,0 +1,174 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::pe::file_analysis::{self, ANALYZER_STRINGS};
+use crate::pe::pe_service::find_file_by_id;
+use crate::tools::pe_image::PeImage;
+use crate::tools::pe_tools;
+use crate::tools::strings::{self, ExtractedString, StringEncoding};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StringsResult {
+    pub min_len: usize,
+    pub strings: Vec<ExtractedString>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StringsSummary {
+    pub file_id: String,
+    pub min_len: usize,
+    pub total: usize,
+    pub ascii: usize,
+    pub utf16le: usize,
+}
+
+async fn extract_and_save(
+    app_state: &AppState,
+    file_id: &str,
+    min_len: usize,
+) -> Result<StringsResult, DefaultResponse> {
+    let file_model = find_file_by_id(app_state, file_id).await?;
+    // 提取字符串需要扫描整个文件，在阻塞线程中执行
+    let extracted = tokio::task::spawn_blocking(move || {
+        // 非PE文件同样可以提取字符串，只是没有RVA与节信息
+        let image = PeImage::parse(&file_model.file_buf).ok();
+        strings::extract_strings(&file_model.file_buf, image.as_ref(), min_len)
+    })
+    .await;
+    let result = match extracted {
+        Ok(strings) => StringsResult { min_len, strings },
+        Err(err) => {
+            log::error!("extract strings task error: {} [{}]", err, file_id);
+            return Err(DefaultResponse::error().msg("提取字符串失败，请重试!".to_string()));
+        }
+    };
+    if let Err(err) = file_analysis::save_analysis(
+        app_state.db_conn.as_ref(),
+        file_id,
+        ANALYZER_STRINGS,
+        &result,
+    )
+    .await
+    {
+        log::error!("save strings result error: {} [{}]", err, file_id);
+        return Err(DefaultResponse::error().msg("保存字符串结果失败，请重试!".to_string()));
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractParam {
+    pub min_len: Option<usize>,
+}
+pub async fn extract_strings(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+    Json(param): Json<ExtractParam>,
+) -> impl IntoResponse {
+    let min_len = param.min_len.unwrap_or(strings::DEFAULT_MIN_LEN);
+    match extract_and_save(&app_state, &file_id, min_len).await {
+        Ok(result) => {
+            let ascii = result
+                .strings
+                .iter()
+                .filter(|item| item.encoding == StringEncoding::Ascii)
+                .count();
+            DataResponse::success(StringsSummary {
+                file_id,
+                min_len,
+                total: result.strings.len(),
+                ascii,
+                utf16le: result.strings.len() - ascii,
+            })
+            .into_response()
+        }
+        Err(response) => response.into_response(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageListParam {
+    page: u64,
+    size: u64,
+    keyword: Option<String>,
+    regex: Option<String>,
+    encoding: Option<StringEncoding>,
+    section: Option<String>,
+}
+pub async fn string_page_list(
+    app_state: State<AppState>,
+    Path(file_id): Path<String>,
+    Query(param): Query<PageListParam>,
+) -> impl IntoResponse {
+    let regex = match param.regex.as_deref().filter(|regex| !regex.is_empty()) {
+        None => None,
+        Some(regex) => match regex::Regex::new(regex) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                return DefaultResponse::error()
+                    .msg(format!("正则表达式错误: {}", err))
+                    .into_response()
+            }
+        },
+    };
+    let stored = file_analysis::find_analysis::<StringsResult>(
+        app_state.db_conn.as_ref(),
+        &file_id,
+        ANALYZER_STRINGS,
+    )
+    .await
+    .unwrap_or_else(|err| {
+        log::error!("find strings result error: {} [{}]", err, file_id);
+        None
+    });
+    // 尚未提取过的文件按默认长度提取一次
+    let result = match stored {
+        Some(data) => data,
+        None => match extract_and_save(&app_state, &file_id, strings::DEFAULT_MIN_LEN).await {
+            Ok(data) => data,
+            Err(response) => return response.into_response(),
+        },
+    };
+    let filtered = result
+        .strings
+        .into_iter()
+        .filter(|item| {
+            param
+                .keyword
+                .as_deref()
+                .is_none_or(|keyword| pe_tools::fuzzy_search(&item.value, keyword))
+        })
+        .filter(|item| {
+            regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&item.value))
+        })
+        .filter(|item| {
+            param
+                .encoding
+                .is_none_or(|encoding| item.encoding == encoding)
+        })
+        .filter(|item| {
+            param
+                .section
+                .as_deref()
+                .is_none_or(|section| item.section.as_deref() == Some(section))
+        })
+        .collect::<Vec<_>>();
+    let total = filtered.len() as u64;
+    if total == 0 || param.size == 0 {
+        return PaginateResponse::<ExtractedString>::success(Vec::new(), PaginateInfo::default())
+            .into_response();
+    }
+    let pages = total.div_ceil(param.size);
+    let data = filtered
+        .into_iter()
+        .skip(param.page.saturating_mul(param.size) as usize)
+        .take(param.size as usize)
+        .collect::<Vec<_>>();
+    PaginateResponse::success(data, PaginateInfo { total, pages }).into_response()
+}
@@ -0,0 +1,17 @@
+use axum::routing::{get, post};
+use axum::Router;
+
+use crate::app::state::AppState;
+
+pub mod risk_service;
+
+pub fn get_routers(app_state: AppState) -> Router {
+    Router::new().nest(
+        "/risk",
+        Router::new()
+            .route("/weight_list", get(risk_service::weight_list))
+            .route("/save_weights", post(risk_service::save_weights))
+            .route("/reset_weights", post(risk_service::reset_weights))
+            .with_state(app_state),
+    )
+}
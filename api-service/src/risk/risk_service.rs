@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use entity::model::t_risk_weight;
+use migration::sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use migration::OnConflict;
+use serde::{Deserialize, Serialize};
+
+use crate::app::response::{DataResponse, DefaultResponse};
+use crate::app::state::AppState;
+use crate::tools::risk::{self, THRESHOLD_MALICIOUS, THRESHOLD_SUSPICIOUS};
+
+//加载已保存的权重，未保存的使用默认值
+pub async fn load_weights(db: &DatabaseConnection) -> HashMap<String, i64> {
+    t_risk_weight::Entity::find()
+        .all(db)
+        .await
+        .unwrap_or_else(|err| {
+            log::error!("get risk weight list error: {}", err);
+            Vec::new()
+        })
+        .into_iter()
+        .map(|model| (model.weight_key, model.weight_value))
+        .collect()
+}
+
+pub async fn weight_list(app_state: State<AppState>) -> impl IntoResponse {
+    let saved = load_weights(app_state.db_conn.as_ref()).await;
+    DataResponse::success(risk::merge_weights(&saved))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveWeightsParam {
+    weights: HashMap<String, i64>,
+}
+//合并默认值后检查可疑阈值是否小于恶意阈值
+fn check_thresholds(saved: &HashMap<String, i64>) -> Result<(), DefaultResponse> {
+    let weights = risk::merge_weights(saved);
+    let threshold = |key: &str| {
+        weights
+            .iter()
+            .find(|weight| weight.key == key)
+            .map_or(0, |weight| weight.value)
+    };
+    if threshold(THRESHOLD_SUSPICIOUS) >= threshold(THRESHOLD_MALICIOUS) {
+        return Err(DefaultResponse::error().msg("可疑阈值必须小于恶意阈值!".to_string()));
+    }
+    Ok(())
+}
+
+pub async fn save_weights(
+    app_state: State<AppState>,
+    Json(param): Json<SaveWeightsParam>,
+) -> impl IntoResponse {
+    if param.weights.is_empty() {
+        return DefaultResponse::success();
+    }
+    if let Err(msg) = risk::validate_weights(&param.weights) {
+        return DefaultResponse::error().msg(msg);
+    }
+    let mut merged = load_weights(app_state.db_conn.as_ref()).await;
+    merged.extend(param.weights.clone());
+    if let Err(response) = check_thresholds(&merged) {
+        return response;
+    }
+    let now = chrono::Local::now().naive_local();
+    let models = param
+        .weights
+        .into_iter()
+        .map(|(key, value)| t_risk_weight::ActiveModel {
+            weight_key: Set(key),
+            weight_value: Set(value),
+            modify_time: Set(now),
+        })
+        .collect::<Vec<_>>();
+    let result = t_risk_weight::Entity::insert_many(models)
+        .on_conflict(
+            OnConflict::column(t_risk_weight::Column::WeightKey)
+                .update_columns([
+                    t_risk_weight::Column::WeightValue,
+                    t_risk_weight::Column::ModifyTime,
+                ])
+                .to_owned(),
+        )
+        .exec(app_state.db_conn.as_ref())
+        .await;
+    match result {
+        Ok(_) => DefaultResponse::success(),
+        Err(err) => {
+            log::error!("save risk weight error: {}", err);
+            DefaultResponse::error().msg("保存数据失败, 请确认数据后重试!".to_string())
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetWeightsParam {
+    // 为空时全部恢复默认值
+    #[serde(default)]
+    keys: Vec<String>,
+}
+pub async fn reset_weights(
+    app_state: State<AppState>,
+    Json(param): Json<ResetWeightsParam>,
+) -> impl IntoResponse {
+    // 部分恢复默认值后阈值仍需满足大小关系
+    if !param.keys.is_empty() {
+        let mut remaining = load_weights(app_state.db_conn.as_ref()).await;
+        remaining.retain(|key, _| !param.keys.contains(key));
+        if let Err(response) = check_thresholds(&remaining) {
+            return response;
+        }
+    }
+    let mut delete = t_risk_weight::Entity::delete_many();
+    if !param.keys.is_empty() {
+        delete = delete.filter(t_risk_weight::Column::WeightKey.is_in(param.keys));
+    }
+    match delete.exec(app_state.db_conn.as_ref()).await {
+        Ok(_) => DefaultResponse::success(),
+        Err(err) => {
+            log::error!("reset risk weight error: {}", err);
+            DefaultResponse::error().msg("重置失败，请重试!".to_string())
+        }
+    }
+}
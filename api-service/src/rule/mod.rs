@@ -0,0 +1,26 @@
+use axum::routing::{get, post};
+use axum::Router;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::app::state::AppState;
+use crate::rule::rule_service::CompiledRule;
+
+pub mod rule_service;
+
+pub static RULE_SET: Lazy<RwLock<Option<Arc<Vec<CompiledRule>>>>> = Lazy::new(|| RwLock::new(None));
+
+pub fn get_routers(app_state: AppState) -> Router {
+    Router::new().nest(
+        "/rule",
+        Router::new()
+            .route("/save", post(rule_service::save))
+            .route("/delete", post(rule_service::delete))
+            .route("/page_list", get(rule_service::page_list))
+            .route("/info/:id", get(rule_service::info))
+            .route("/validate", post(rule_service::validate))
+            .route("/scan/:file_id", get(rule_service::scan))
+            .with_state(app_state),
+    )
+}
@@ -0,0 +1,295 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use migration::sea_orm::ActiveValue::Set;
+use migration::sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder,
+};
+
+use crate::app::response::{DataResponse, DefaultResponse, PaginateInfo, PaginateResponse};
+use crate::app::state::AppState;
+use crate::pe::pe_service::find_file_by_id;
+use crate::rule::RULE_SET;
+use crate::tools::pe_image::PeImage;
+use crate::tools::yara::{self, RuleMatch};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleHit {
+    pub rule_id: String,
+    pub rule_name: String,
+    #[serde(flatten)]
+    pub matched: RuleMatch,
+}
+
+//一条已保存规则编译后的结果，源码中可以包含多个规则
+#[derive(Debug)]
+pub struct CompiledRule {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub rules: Vec<yara::parser::Rule>,
+}
+
+//获取已编译的全部规则，缓存为空时从数据库重新编译，编译失败的规则会被跳过
+pub async fn load_rules(db: &DatabaseConnection) -> Arc<Vec<CompiledRule>> {
+    if let Some(rules) = RULE_SET.read().await.as_ref() {
+        return rules.clone();
+    }
+    let mut cache = RULE_SET.write().await;
+    if let Some(rules) = cache.as_ref() {
+        return rules.clone();
+    }
+    let models = match entity::model::t_rule::Entity::find()
+        .order_by_asc(entity::model::t_rule::Column::CreateTime)
+        .all(db)
+        .await
+    {
+        Ok(data) => data,
+        Err(err) => {
+            // 查询失败时不缓存，下次扫描重新加载
+            log::error!("get rule list error: {}", err);
+            return Arc::new(Vec::new());
+        }
+    };
+    let rules = models
+        .into_iter()
+        .filter_map(|model| match yara::compile(&model.rule_source) {
+            Ok(rules) => Some(CompiledRule {
+                rule_id: model.id,
+                rule_name: model.rule_name,
+                rules,
+            }),
+            Err(err) => {
+                log::error!("compile rule error: {} [{}]", err, model.id);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let rules = Arc::new(rules);
+    *cache = Some(rules.clone());
+    rules
+}
+
+//规则修改后清空缓存，下次扫描时重新编译
+pub async fn invalidate_rules() {
+    *RULE_SET.write().await = None;
+}
+
+//使用已编译的规则扫描文件，计算量较大，需要在阻塞线程中调用
+pub fn scan_rules(rules: &[CompiledRule], buf: &[u8], image: Option<&PeImage>) -> Vec<RuleHit> {
+    let entrypoint = image.and_then(|image| image.rva_to_offset(image.entry_point));
+    let mut result = Vec::new();
+    for rule in rules {
+        for matched in yara::scan(&rule.rules, buf, entrypoint) {
+            result.push(RuleHit {
+                rule_id: rule.rule_id.clone(),
+                rule_name: rule.rule_name.clone(),
+                matched,
+            });
+        }
+    }
+    result
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveParam {
+    pub id: Option<String>,
+    pub name: String,
+    pub source: String,
+    pub desc: Option<String>,
+}
+pub async fn save(app_state: State<AppState>, Json(param): Json<SaveParam>) -> impl IntoResponse {
+    if param.name.is_empty() {
+        return DefaultResponse::error().msg("规则名称不能为空!".to_string());
+    }
+    match yara::compile(&param.source) {
+        Ok(rules) if rules.is_empty() => {
+            return DefaultResponse::error().msg("规则内容不能为空!".to_string());
+        }
+        Ok(_) => {}
+        Err(err) => {
+            return DefaultResponse::error().msg(format!("规则语法错误: {}", err));
+        }
+    }
+    let active_model = match param.id {
+        None => {
+            let new_id = uuid::Uuid::new_v4().simple().to_string();
+            entity::model::t_rule::ActiveModel {
+                id: Set(new_id),
+                rule_name: Set(param.name),
+                rule_source: Set(param.source),
+                rule_desc: Set(param.desc),
+                create_time: Set(chrono::Local::now().naive_local()),
+                modify_time: Set(chrono::Local::now().naive_local()),
+            }
+        }
+        Some(ref id) => {
+            match entity::model::t_rule::Entity::find_by_id(id)
+                .one(app_state.db_conn.as_ref())
+                .await
+            {
+                Ok(data) => match data {
+                    None => {
+                        return DefaultResponse::error()
+                            .msg("数据不存在, 请确认后再试!".to_string())
+                    }
+                    Some(data) => {
+                        let mut active_model = data.into_active_model();
+                        active_model.modify_time = Set(chrono::Local::now().naive_local());
+                        active_model.rule_name = Set(param.name);
+                        active_model.rule_source = Set(param.source);
+                        active_model.rule_desc = Set(param.desc);
+                        active_model
+                    }
+                },
+                Err(err) => {
+                    log::error!("find rule by id error: {}", err);
+                    return DefaultResponse::error().msg("数据查询错误, 请稍后再试!".to_string());
+                }
+            }
+        }
+    };
+    let result = match param.id {
+        None => active_model.insert(app_state.db_conn.as_ref()).await,
+        Some(_) => active_model.update(app_state.db_conn.as_ref()).await,
+    };
+    match result {
+        Ok(_) => {
+            invalidate_rules().await;
+            DefaultResponse::success()
+        }
+        Err(err) => {
+            log::error!("保存规则失败, error: {}", err);
+            DefaultResponse::error().msg("保存数据失败, 请确认数据后重试!".to_string())
+        }
+    }
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteParam {
+    ids: Vec<String>,
+}
+pub async fn delete(
+    app_state: State<AppState>,
+    Json(param): Json<DeleteParam>,
+) -> impl IntoResponse {
+    if param.ids.is_empty() {
+        return DefaultResponse::success();
+    }
+    let result = entity::model::t_rule::Entity::delete_many()
+        .filter(entity::model::t_rule::Column::Id.is_in(param.ids))
+        .exec(app_state.db_conn.as_ref())
+        .await;
+    match result {
+        Ok(_) => {
+            invalidate_rules().await;
+            DefaultResponse::success()
+        }
+        Err(err) => {
+            log::error!("delete rule error: {}", err);
+            DefaultResponse::error().msg("删除失败，请重试!".to_string())
+        }
+    }
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageListParam {
+    page: u64,
+    size: u64,
+    name: Option<String>,
+}
+pub async fn page_list(
+    app_state: State<AppState>,
+    Query(param): Query<PageListParam>,
+) -> impl IntoResponse {
+    let mut select = entity::model::t_rule::Entity::find();
+    if let Some(name) = param.name {
+        select =
+            select.filter(entity::model::t_rule::Column::RuleName.like(format!("%{}%", &name)));
+    }
+    select = select.order_by_desc(entity::model::t_rule::Column::ModifyTime);
+    let paginate = select.paginate(app_state.db_conn.as_ref(), param.size);
+    let total = paginate.num_items().await.unwrap_or_else(|err| {
+        log::error!("get rule total num error: {}", err);
+        0
+    });
+    let pages = paginate.num_pages().await.unwrap_or(0);
+    if total == 0 {
+        return PaginateResponse::success(Vec::new(), PaginateInfo::default());
+    }
+    let data = paginate.fetch_page(param.page).await.unwrap_or_else(|err| {
+        log::error!("find rule page list error: {}", err);
+        vec![]
+    });
+    PaginateResponse::success(data, PaginateInfo { total, pages })
+}
+
+pub async fn info(app_state: State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
+    match entity::model::t_rule::Entity::find_by_id(id)
+        .one(app_state.db_conn.as_ref())
+        .await
+    {
+        Ok(data) => match data {
+            None => DefaultResponse::error()
+                .msg("数据不存在, 请检查后重试!".to_string())
+                .into_response(),
+            Some(data) => DataResponse::success(data).into_response(),
+        },
+        Err(err) => {
+            log::error!("find rule by id error: {}", err);
+            DefaultResponse::error().into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateParam {
+    source: String,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidateResult {
+    pub valid: bool,
+    pub line: Option<usize>,
+    pub message: Option<String>,
+    pub rules: Vec<String>,
+}
+pub async fn validate(Json(param): Json<ValidateParam>) -> impl IntoResponse {
+    let result = match yara::compile(&param.source) {
+        Ok(rules) => ValidateResult {
+            valid: true,
+            line: None,
+            message: None,
+            rules: rules.into_iter().map(|rule| rule.name).collect(),
+        },
+        Err(err) => ValidateResult {
+            valid: false,
+            line: Some(err.line),
+            message: Some(err.message),
+            rules: Vec::new(),
+        },
+    };
+    DataResponse::success(result)
+}
+
+pub async fn scan(app_state: State<AppState>, Path(file_id): Path<String>) -> impl IntoResponse {
+    let file_model = match find_file_by_id(&app_state, &file_id).await {
+        Ok(data) => data,
+        Err(response) => return response.into_response(),
+    };
+    let rules = load_rules(app_state.db_conn.as_ref()).await;
+    let result = tokio::task::spawn_blocking(move || {
+        let image = PeImage::parse(&file_model.file_buf).ok();
+        scan_rules(&rules, &file_model.file_buf, image.as_ref())
+    })
+    .await;
+    match result {
+        Ok(hits) => DataResponse::success(hits).into_response(),
+        Err(err) => {
+            log::error!("scan rule task error: {} [{}]", err, file_id);
+            DefaultResponse::error()
+                .msg("规则扫描失败，请重试!".to_string())
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::{read_u16, PeImage};
+
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+// 导入函数少于该数量时视为异常
+const MIN_IMPORT_COUNT: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeAnomaly {
+    pub code: String,
+    pub description: String,
+}
+
+fn anomaly(code: &str, description: String) -> PeAnomaly {
+    PeAnomaly {
+        code: code.to_string(),
+        description,
+    }
+}
+
+//按PE规范计算校验和，CheckSum字段本身按0计算
+pub fn compute_checksum(image: &PeImage, buf: &[u8]) -> u32 {
+    let checksum_offset = image.checksum_offset();
+    let mut sum: u64 = 0;
+    let mut offset = 0;
+    while offset < buf.len() {
+        if offset == checksum_offset || offset == checksum_offset + 2 {
+            offset += 2;
+            continue;
+        }
+        let word = read_u16(buf, offset).unwrap_or(buf[offset] as u16);
+        sum += word as u64;
+        sum = (sum & 0xFFFF) + (sum >> 16);
+        offset += 2;
+    }
+    sum = (sum & 0xFFFF) + (sum >> 16);
+    (sum as u32).wrapping_add(buf.len() as u32)
+}
+
+fn align_up(value: u64, alignment: u32) -> u64 {
+    if alignment == 0 {
+        return value;
+    }
+    value.div_ceil(alignment as u64) * alignment as u64
+}
+
+//检查PE结构中常见于恶意代码或加壳程序的异常
+pub fn detect_anomalies(image: &PeImage, buf: &[u8]) -> Vec<PeAnomaly> {
+    let mut result = Vec::new();
+    if image.entry_point == 0 {
+        if !image.is_dll() {
+            result.push(anomaly("entry_point_zero", "入口点为0".to_string()));
+        }
+    } else {
+        match image.section_by_rva(image.entry_point) {
+            None => result.push(anomaly(
+                "entry_point_outside_section",
+                format!("入口点0x{:X}不在任何节中", image.entry_point),
+            )),
+            Some(section) => {
+                if !section.is_executable() {
+                    result.push(anomaly(
+                        "entry_point_not_executable",
+                        format!("入口点所在节{}不可执行", section.name),
+                    ));
+                }
+                if image.sections.len() > 1
+                    && image.sections.last().map(|last| last.index) == Some(section.index)
+                {
+                    result.push(anomaly(
+                        "entry_point_in_last_section",
+                        format!("入口点位于最后一个节{}", section.name),
+                    ));
+                }
+            }
+        }
+    }
+    for section in &image.sections {
+        if section.characteristics & (IMAGE_SCN_MEM_WRITE | IMAGE_SCN_MEM_EXECUTE)
+            == IMAGE_SCN_MEM_WRITE | IMAGE_SCN_MEM_EXECUTE
+        {
+            result.push(anomaly(
+                "section_writable_executable",
+                format!("节{}同时可写可执行", section.name),
+            ));
+        }
+        if section.size_of_raw_data == 0 && section.virtual_size > 0 && section.is_executable() {
+            result.push(anomaly(
+                "section_empty_executable",
+                format!(
+                    "可执行节{}文件大小为0，虚拟大小为0x{:X}",
+                    section.name, section.virtual_size
+                ),
+            ));
+        }
+        if section.size_of_raw_data > 0
+            && section.pointer_to_raw_data as u64 + section.size_of_raw_data as u64
+                > align_up(buf.len() as u64, image.file_alignment)
+        {
+            result.push(anomaly(
+                "section_beyond_file",
+                format!("节{}的数据超出文件范围", section.name),
+            ));
+        }
+        if section.name.is_empty() || !section.name.chars().all(|c| c.is_ascii_graphic()) {
+            result.push(anomaly(
+                "section_name_invalid",
+                format!("节名称异常: {:?}", section.name),
+            ));
+        }
+    }
+    let now = chrono::Utc::now().timestamp();
+    if image.time_date_stamp == 0 || image.time_date_stamp as i64 > now {
+        result.push(anomaly(
+            "timestamp_invalid",
+            format!("编译时间戳异常: 0x{:08X}", image.time_date_stamp),
+        ));
+    }
+    if image.checksum != 0 {
+        let checksum = compute_checksum(image, buf);
+        if checksum != image.checksum {
+            result.push(anomaly(
+                "checksum_mismatch",
+                format!(
+                    "校验和不一致: 头部0x{:08X}，实际0x{:08X}",
+                    image.checksum, checksum
+                ),
+            ));
+        }
+    }
+    let image_end = image
+        .sections
+        .iter()
+        .map(|section| {
+            let size = if section.virtual_size == 0 {
+                section.size_of_raw_data
+            } else {
+                section.virtual_size
+            };
+            section.virtual_address as u64 + size as u64
+        })
+        .max()
+        .unwrap_or(image.size_of_headers as u64);
+    if align_up(image_end, image.section_alignment) > image.size_of_image as u64 {
+        result.push(anomaly(
+            "size_of_image_mismatch",
+            format!("SizeOfImage(0x{:X})小于节表范围", image.size_of_image),
+        ));
+    }
+    // .NET程序只导入mscoree.dll
+    if image.imports.len() < MIN_IMPORT_COUNT && image.data_directory(14).is_none() {
+        result.push(anomaly(
+            "few_imports",
+            format!("导入函数过少（{}个）", image.imports.len()),
+        ));
+    }
+    result
+}
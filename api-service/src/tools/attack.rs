@@ -0,0 +1,271 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Tactic {
+    pub tactic_id: &'static str,
+    pub short_name: &'static str,
+    pub name: &'static str,
+    pub display_name: &'static str,
+}
+
+// Enterprise矩阵的战术，按矩阵中的列顺序排列
+pub const TACTICS: [Tactic; 14] = [
+    Tactic {
+        tactic_id: "TA0043",
+        short_name: "reconnaissance",
+        name: "Reconnaissance",
+        display_name: "侦察",
+    },
+    Tactic {
+        tactic_id: "TA0042",
+        short_name: "resource-development",
+        name: "Resource Development",
+        display_name: "资源开发",
+    },
+    Tactic {
+        tactic_id: "TA0001",
+        short_name: "initial-access",
+        name: "Initial Access",
+        display_name: "初始访问",
+    },
+    Tactic {
+        tactic_id: "TA0002",
+        short_name: "execution",
+        name: "Execution",
+        display_name: "执行",
+    },
+    Tactic {
+        tactic_id: "TA0003",
+        short_name: "persistence",
+        name: "Persistence",
+        display_name: "持久化",
+    },
+    Tactic {
+        tactic_id: "TA0004",
+        short_name: "privilege-escalation",
+        name: "Privilege Escalation",
+        display_name: "权限提升",
+    },
+    Tactic {
+        tactic_id: "TA0005",
+        short_name: "defense-evasion",
+        name: "Defense Evasion",
+        display_name: "防御规避",
+    },
+    Tactic {
+        tactic_id: "TA0006",
+        short_name: "credential-access",
+        name: "Credential Access",
+        display_name: "凭据访问",
+    },
+    Tactic {
+        tactic_id: "TA0007",
+        short_name: "discovery",
+        name: "Discovery",
+        display_name: "发现",
+    },
+    Tactic {
+        tactic_id: "TA0008",
+        short_name: "lateral-movement",
+        name: "Lateral Movement",
+        display_name: "横向移动",
+    },
+    Tactic {
+        tactic_id: "TA0009",
+        short_name: "collection",
+        name: "Collection",
+        display_name: "收集",
+    },
+    Tactic {
+        tactic_id: "TA0011",
+        short_name: "command-and-control",
+        name: "Command and Control",
+        display_name: "命令与控制",
+    },
+    Tactic {
+        tactic_id: "TA0010",
+        short_name: "exfiltration",
+        name: "Exfiltration",
+        display_name: "数据渗出",
+    },
+    Tactic {
+        tactic_id: "TA0040",
+        short_name: "impact",
+        name: "Impact",
+        display_name: "影响",
+    },
+];
+
+pub fn tactic_by_short_name(short_name: &str) -> Option<&'static Tactic> {
+    TACTICS
+        .iter()
+        .find(|tactic| tactic.short_name == short_name)
+}
+
+//ATT&CK技术编号，如 T1055 或 T1055.012
+pub fn is_valid_technique_id(value: &str) -> bool {
+    let Some(digits) = value.strip_prefix('T') else {
+        return false;
+    };
+    let (technique, sub_technique) = match digits.split_once('.') {
+        Some((technique, sub_technique)) => (technique, Some(sub_technique)),
+        None => (digits, None),
+    };
+    let is_digits = |text: &str, len: usize| {
+        text.len() == len && text.bytes().all(|byte| byte.is_ascii_digit())
+    };
+    is_digits(technique, 4) && sub_technique.is_none_or(|text| is_digits(text, 3))
+}
+
+//拆分逗号分隔保存的标签
+pub fn split_tags(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct TechniqueInfo {
+    pub name: String,
+    pub tactics: Vec<String>,
+}
+
+//一条带有ATT&CK标签的检测结果
+#[derive(Debug, Clone)]
+pub struct AttackFinding {
+    pub technique_id: String,
+    // 为空时使用数据集中技术所属的战术
+    pub tactics: Vec<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechniqueGroup {
+    pub technique_id: String,
+    pub technique_name: String,
+    pub findings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TacticGroup {
+    pub tactic_id: String,
+    pub tactic: String,
+    pub name: String,
+    pub display_name: String,
+    pub techniques: Vec<TechniqueGroup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapCell {
+    pub technique_id: String,
+    pub technique_name: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapColumn {
+    pub tactic_id: String,
+    pub tactic: String,
+    pub name: String,
+    pub cells: Vec<HeatmapCell>,
+}
+
+//矩阵热力图，包含全部战术列，便于前端直接绘制
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttackHeatmap {
+    pub max_count: usize,
+    pub columns: Vec<HeatmapColumn>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttackSummary {
+    pub tactics: Vec<TacticGroup>,
+    pub heatmap: AttackHeatmap,
+}
+
+//子技术不在数据集中时使用父技术的信息
+fn technique_info<'a>(
+    techniques: &'a HashMap<String, TechniqueInfo>,
+    technique_id: &str,
+) -> Option<&'a TechniqueInfo> {
+    techniques.get(technique_id).or_else(|| {
+        technique_id
+            .split_once('.')
+            .and_then(|(parent, _)| techniques.get(parent))
+    })
+}
+
+//按战术汇总检测结果，并生成矩阵热力图
+pub fn summarize(
+    findings: &[AttackFinding],
+    techniques: &HashMap<String, TechniqueInfo>,
+) -> AttackSummary {
+    // 战术 -> 技术 -> 来源
+    let mut grouped: HashMap<&str, BTreeMap<String, Vec<String>>> = HashMap::new();
+    for finding in findings {
+        let info = technique_info(techniques, &finding.technique_id);
+        let tactics = if finding.tactics.is_empty() {
+            info.map(|info| info.tactics.clone()).unwrap_or_default()
+        } else {
+            finding.tactics.clone()
+        };
+        for tactic in tactics {
+            let Some(tactic) = tactic_by_short_name(&tactic) else {
+                continue;
+            };
+            let sources = grouped
+                .entry(tactic.short_name)
+                .or_default()
+                .entry(finding.technique_id.clone())
+                .or_default();
+            if !sources.contains(&finding.source) {
+                sources.push(finding.source.clone());
+            }
+        }
+    }
+    let technique_name = |technique_id: &str| {
+        technique_info(techniques, technique_id)
+            .map_or_else(|| technique_id.to_string(), |info| info.name.clone())
+    };
+    let mut summary = AttackSummary::default();
+    for tactic in TACTICS.iter() {
+        let entries = grouped.remove(tactic.short_name).unwrap_or_default();
+        let mut cells = Vec::new();
+        let mut groups = Vec::new();
+        for (technique_id, sources) in entries {
+            summary.heatmap.max_count = summary.heatmap.max_count.max(sources.len());
+            cells.push(HeatmapCell {
+                technique_id: technique_id.clone(),
+                technique_name: technique_name(&technique_id),
+                count: sources.len(),
+            });
+            groups.push(TechniqueGroup {
+                technique_name: technique_name(&technique_id),
+                technique_id,
+                findings: sources,
+            });
+        }
+        summary.heatmap.columns.push(HeatmapColumn {
+            tactic_id: tactic.tactic_id.to_string(),
+            tactic: tactic.short_name.to_string(),
+            name: tactic.name.to_string(),
+            cells,
+        });
+        if !groups.is_empty() {
+            summary.tactics.push(TacticGroup {
+                tactic_id: tactic.tactic_id.to_string(),
+                tactic: tactic.short_name.to_string(),
+                name: tactic.name.to_string(),
+                display_name: tactic.display_name.to_string(),
+                techniques: groups,
+            });
+        }
+    }
+    summary
+}
@@ -0,0 +1,251 @@
+use regex::bytes::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::PeImage;
+
+// 单次搜索最多统计的匹配数，超出后停止搜索
+pub const MAX_MATCHES: usize = 100_000;
+// 每个匹配返回的内容最多字节数
+const MAX_MATCH_PREVIEW: usize = 64;
+// 正则表达式编译后的大小上限
+const MAX_REGEX_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    // 十六进制，??匹配任意字节
+    Hex,
+    Text,
+    // 按字节匹配的正则表达式
+    Regex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    Ascii,
+    Utf16le,
+    // 同时搜索ASCII与UTF-16LE
+    All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchMatch {
+    pub offset: u64,
+    pub length: u64,
+    pub rva: Option<u32>,
+    pub section: Option<String>,
+    // 匹配内容的十六进制，超过64字节只返回开头部分
+    pub hex: String,
+}
+
+fn escape_byte(byte: u8) -> String {
+    format!("\\x{:02X}", byte)
+}
+
+//文本中的一个字节，忽略大小写时字母同时匹配大小写
+fn text_byte(byte: u8, case_insensitive: bool) -> String {
+    if case_insensitive && byte.is_ascii_alphabetic() {
+        format!(
+            "[{}{}]",
+            escape_byte(byte.to_ascii_lowercase()),
+            escape_byte(byte.to_ascii_uppercase())
+        )
+    } else {
+        escape_byte(byte)
+    }
+}
+
+//十六进制搜索内容转为正则，忽略空白字符
+fn hex_pattern(pattern: &str) -> Result<String, String> {
+    let digits = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let mut result = String::new();
+    for pair in digits.chunks(2) {
+        match pair {
+            ['?', '?'] => result.push('.'),
+            [high, low] => {
+                let byte = high
+                    .to_digit(16)
+                    .zip(low.to_digit(16))
+                    .map(|(high, low)| (high * 16 + low) as u8)
+                    .ok_or_else(|| format!("十六进制格式错误: {}", pattern))?;
+                result.push_str(&escape_byte(byte));
+            }
+            _ => return Err(format!("十六进制格式错误: {}", pattern)),
+        }
+    }
+    Ok(result)
+}
+
+fn text_pattern(pattern: &str, encoding: TextEncoding, case_insensitive: bool) -> String {
+    let ascii = || {
+        pattern
+            .bytes()
+            .map(|byte| text_byte(byte, case_insensitive))
+            .collect::<String>()
+    };
+    let utf16 = || {
+        pattern
+            .encode_utf16()
+            .map(|unit| {
+                let [low, high] = unit.to_le_bytes();
+                format!(
+                    "{}{}",
+                    text_byte(low, case_insensitive && high == 0),
+                    escape_byte(high)
+                )
+            })
+            .collect::<String>()
+    };
+    match encoding {
+        TextEncoding::Ascii => ascii(),
+        TextEncoding::Utf16le => utf16(),
+        TextEncoding::All => format!("(?:{})|(?:{})", ascii(), utf16()),
+    }
+}
+
+//把搜索条件编译为按字节匹配的正则表达式
+pub fn compile(
+    mode: SearchMode,
+    pattern: &str,
+    encoding: TextEncoding,
+    case_insensitive: bool,
+) -> Result<Regex, String> {
+    if pattern.trim().is_empty() {
+        return Err("搜索内容不能为空!".to_string());
+    }
+    let expression = match mode {
+        SearchMode::Hex => hex_pattern(pattern)?,
+        SearchMode::Text => text_pattern(pattern, encoding, case_insensitive),
+        SearchMode::Regex => pattern.to_string(),
+    };
+    RegexBuilder::new(&expression)
+        .unicode(false)
+        .dot_matches_new_line(true)
+        .case_insensitive(mode == SearchMode::Regex && case_insensitive)
+        .size_limit(MAX_REGEX_SIZE)
+        .build()
+        .map_err(|err| format!("搜索条件错误: {}", err))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    // 跳过skip条后最多take条的匹配
+    pub matches: Vec<SearchMatch>,
+    // 匹配总数，超过MAX_MATCHES时只统计到MAX_MATCHES
+    pub total: usize,
+    pub truncated: bool,
+}
+
+//统计全部匹配，只返回[skip, skip + take)范围内匹配的详细信息，十六进制与文本搜索允许匹配重叠
+pub fn search(
+    buf: &[u8],
+    image: Option<&PeImage>,
+    regex: &Regex,
+    overlapping: bool,
+    skip: usize,
+    take: usize,
+) -> SearchResult {
+    let mut result = SearchResult::default();
+    let mut start = 0;
+    while start <= buf.len() {
+        let Some(found) = regex.find_at(buf, start) else {
+            break;
+        };
+        if result.total >= MAX_MATCHES {
+            result.truncated = true;
+            break;
+        }
+        let offset = found.start();
+        let index = result.total;
+        result.total += 1;
+        // 空匹配时向后移动一个字节，避免死循环
+        start = if overlapping || found.is_empty() {
+            offset + 1
+        } else {
+            found.end()
+        };
+        if index < skip || index - skip >= take {
+            continue;
+        }
+        let bytes = found.as_bytes();
+        result.matches.push(SearchMatch {
+            offset: offset as u64,
+            length: bytes.len() as u64,
+            rva: image.and_then(|image| image.offset_to_rva(offset)),
+            section: image
+                .and_then(|image| image.section_by_offset(offset))
+                .map(|section| section.name.clone()),
+            hex: hex::encode_upper(&bytes[..bytes.len().min(MAX_MATCH_PREVIEW)]),
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(regex: &Regex, buf: &[u8]) -> Vec<u64> {
+        search(buf, None, regex, true, 0, usize::MAX)
+            .matches
+            .iter()
+            .map(|found| found.offset)
+            .collect()
+    }
+
+    #[test]
+    fn hex_wildcards_match_any_byte() {
+        assert_eq!(hex_pattern("4D ?? 90").unwrap(), "\\x4D.\\x90");
+        let regex = compile(SearchMode::Hex, "4d??90", TextEncoding::All, false).unwrap();
+        assert_eq!(
+            offsets(&regex, b"\x4D\x00\x90\x4D\x0A\x90\x4D\x90"),
+            vec![0, 3]
+        );
+    }
+
+    #[test]
+    fn hex_rejects_odd_digits_and_bad_wildcards() {
+        assert!(hex_pattern("4D5").is_err());
+        assert!(hex_pattern("4D 5A 9").is_err());
+        assert!(hex_pattern("?A").is_err());
+        assert!(hex_pattern("4G").is_err());
+        assert_eq!(hex_pattern(" 4D\t5A ").unwrap(), "\\x4D\\x5A");
+    }
+
+    #[test]
+    fn utf16_case_folding_only_for_ascii_units() {
+        let regex = compile(SearchMode::Text, "aB", TextEncoding::Utf16le, true).unwrap();
+        assert_eq!(offsets(&regex, b"A\0b\0"), vec![0]);
+        assert!(offsets(&regex, b"ab").is_empty());
+        let regex = compile(SearchMode::Text, "aB", TextEncoding::Utf16le, false).unwrap();
+        assert!(offsets(&regex, b"A\0b\0").is_empty());
+        // 非ASCII字符按原样匹配，高字节不参与大小写转换
+        let regex = compile(SearchMode::Text, "中", TextEncoding::Utf16le, true).unwrap();
+        assert_eq!(offsets(&regex, &[0x2D, 0x4E]), vec![0]);
+    }
+
+    #[test]
+    fn all_encodings_match_ascii_and_utf16() {
+        let regex = compile(SearchMode::Text, "ab", TextEncoding::All, true).unwrap();
+        assert_eq!(offsets(&regex, b"AB..a\0B\0"), vec![0, 4]);
+    }
+
+    #[test]
+    fn search_counts_all_but_returns_one_page() {
+        let regex = compile(SearchMode::Hex, "AA", TextEncoding::All, false).unwrap();
+        let buf = [0xAA; 10];
+        let result = search(&buf, None, &regex, true, 4, 3);
+        assert_eq!(result.total, 10);
+        assert!(!result.truncated);
+        let offsets = result
+            .matches
+            .iter()
+            .map(|found| found.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![4, 5, 6]);
+    }
+}
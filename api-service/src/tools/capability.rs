@@ -0,0 +1,401 @@
+use std::collections::HashSet;
+
+use iced_x86::{Instruction, OpKind};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::disasm;
+use crate::tools::pe_image::{PeImage, RuntimeFunction};
+use crate::tools::strings;
+
+// 整个文件最多解码的指令数量
+const MAX_CAPABILITY_INSTRUCTIONS: usize = 2_000_000;
+const MIN_STRING_LEN: usize = 4;
+const MAX_REFERENCED_STRING_LEN: usize = 512;
+// 每条能力最多返回的命中函数数量
+const MAX_REPORTED_FUNCTIONS: usize = 20;
+
+//能力规则的条件，JSON形式如 {"and": [{"api": "OpenProcess"}, {"string": "cmd.exe"}]}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    NOf { count: usize, items: Vec<Condition> },
+    // 导入函数名称，忽略大小写，同时匹配A/W/Ex版本
+    Api(String),
+    // 字符串包含，忽略大小写
+    String(String),
+    // 指令中的立即数
+    Constant(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityScope {
+    File,
+    // 条件需要在同一个函数中满足，依赖.pdata划分函数
+    Function,
+}
+
+impl CapabilityScope {
+    pub fn parse(value: &str) -> Option<CapabilityScope> {
+        match value {
+            "file" => Some(CapabilityScope::File),
+            "function" => Some(CapabilityScope::Function),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CapabilityRule {
+    pub name: String,
+    pub technique_id: String,
+    pub description: Option<String>,
+    pub scope: CapabilityScope,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityHit {
+    pub name: String,
+    pub technique_id: String,
+    pub description: Option<String>,
+    // 实际使用的范围，没有.pdata时函数范围的规则退化为文件范围
+    pub scope: CapabilityScope,
+    // 满足条件的函数起始RVA
+    pub functions: Vec<u32>,
+    pub evidence: Vec<String>,
+}
+
+//校验条件结构，返回错误描述
+pub fn validate_condition(condition: &Condition) -> Result<(), String> {
+    match condition {
+        Condition::And(items) | Condition::Or(items) => {
+            if items.is_empty() {
+                return Err("and/or 条件不能为空".to_string());
+            }
+            items.iter().try_for_each(validate_condition)
+        }
+        Condition::NOf { count, items } => {
+            if *count == 0 || *count > items.len() {
+                return Err(format!("n_of 的数量必须在1到{}之间", items.len().max(1)));
+            }
+            items.iter().try_for_each(validate_condition)
+        }
+        Condition::Api(value) | Condition::String(value) => {
+            if value.trim().is_empty() {
+                return Err("api/string 条件不能为空".to_string());
+            }
+            Ok(())
+        }
+        Condition::Constant(_) => Ok(()),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Features {
+    // 小写的函数名称，不含DLL
+    apis: HashSet<String>,
+    strings: HashSet<String>,
+    constants: HashSet<u64>,
+}
+
+impl Features {
+    fn merge(&mut self, other: &Features) {
+        self.apis.extend(other.apis.iter().cloned());
+        self.strings.extend(other.strings.iter().cloned());
+        self.constants.extend(other.constants.iter().copied());
+    }
+
+    fn add_api(&mut self, name: &str) {
+        let name = name.rsplit('!').next().unwrap_or(name);
+        self.apis.insert(name.to_ascii_lowercase());
+    }
+
+    fn match_api(&self, name: &str) -> bool {
+        let name = name.rsplit('!').next().unwrap_or(name).to_ascii_lowercase();
+        ["", "a", "w", "ex", "exa", "exw"]
+            .iter()
+            .any(|suffix| self.apis.contains(&format!("{}{}", name, suffix)))
+    }
+}
+
+fn evaluate(condition: &Condition, features: &Features) -> Option<Vec<String>> {
+    match condition {
+        Condition::And(items) => {
+            let mut evidence = Vec::new();
+            for item in items {
+                evidence.extend(evaluate(item, features)?);
+            }
+            Some(evidence)
+        }
+        Condition::Or(items) => {
+            let matched = items
+                .iter()
+                .filter_map(|item| evaluate(item, features))
+                .collect::<Vec<_>>();
+            if matched.is_empty() {
+                return None;
+            }
+            Some(matched.into_iter().flatten().collect())
+        }
+        Condition::NOf { count, items } => {
+            let matched = items
+                .iter()
+                .filter_map(|item| evaluate(item, features))
+                .collect::<Vec<_>>();
+            if matched.len() < *count {
+                return None;
+            }
+            Some(matched.into_iter().flatten().collect())
+        }
+        Condition::Api(name) => features
+            .match_api(name)
+            .then(|| vec![format!("api: {}", name)]),
+        Condition::String(text) => {
+            let text = text.to_lowercase();
+            let mut found = features
+                .strings
+                .iter()
+                .filter(|value| value.contains(&text))
+                .collect::<Vec<_>>();
+            found.sort();
+            found
+                .first()
+                .map(|value| vec![format!("string: {}", value)])
+        }
+        Condition::Constant(value) => features
+            .constants
+            .contains(value)
+            .then(|| vec![format!("constant: 0x{:X}", value)]),
+    }
+}
+
+//读取RVA处被引用的ASCII或UTF-16LE字符串
+fn referenced_string(image: &PeImage, buf: &[u8], rva: u32) -> Option<String> {
+    let section = image.section_by_rva(rva)?;
+    if section.is_executable() {
+        return None;
+    }
+    let offset = image.rva_to_offset(rva)?;
+    let bytes = buf.get(offset..(offset + MAX_REFERENCED_STRING_LEN * 2).min(buf.len()))?;
+    let ascii = bytes
+        .iter()
+        .take_while(|byte| (0x20..0x7F).contains(*byte) || **byte == b'\t')
+        .count();
+    if ascii >= MIN_STRING_LEN && bytes.get(ascii) == Some(&0) {
+        return Some(String::from_utf8_lossy(&bytes[..ascii]).to_string());
+    }
+    let wide = bytes
+        .chunks_exact(2)
+        .take_while(|pair| (0x20..0x7F).contains(&pair[0]) && pair[1] == 0)
+        .count();
+    if wide >= MIN_STRING_LEN {
+        return Some(
+            bytes
+                .chunks_exact(2)
+                .take(wide)
+                .map(|pair| pair[0] as char)
+                .collect(),
+        );
+    }
+    None
+}
+
+//收集单条指令引用的导入函数、字符串与立即数
+fn collect_instruction(
+    image: &PeImage,
+    buf: &[u8],
+    instruction: &Instruction,
+    features: &mut Features,
+) {
+    if let Some(name) = disasm::resolve_import_call(image, buf, instruction) {
+        features.add_api(&name);
+    }
+    for operand in 0..instruction.op_count() {
+        match instruction.op_kind(operand) {
+            OpKind::Immediate8
+            | OpKind::Immediate16
+            | OpKind::Immediate32
+            | OpKind::Immediate64
+            | OpKind::Immediate8to16
+            | OpKind::Immediate8to32
+            | OpKind::Immediate8to64
+            | OpKind::Immediate32to64 => {
+                let value = instruction.immediate(operand);
+                features.constants.insert(value);
+                features.constants.insert(value & 0xFFFF_FFFF);
+                // 32位程序通过立即数引用字符串，如 push offset
+                if let Some(text) = image
+                    .va_to_rva(value)
+                    .and_then(|rva| referenced_string(image, buf, rva))
+                {
+                    features.strings.insert(text.to_lowercase());
+                }
+            }
+            OpKind::Memory if instruction.is_ip_rel_memory_operand() => {
+                if let Some(text) = image
+                    .va_to_rva(instruction.ip_rel_memory_address())
+                    .and_then(|rva| referenced_string(image, buf, rva))
+                {
+                    features.strings.insert(text.to_lowercase());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+//解码RVA范围内的指令，返回实际解码的数量
+fn collect_range(
+    image: &PeImage,
+    buf: &[u8],
+    begin: u32,
+    end: u32,
+    budget: usize,
+    features: &mut Features,
+) -> usize {
+    let Some(mut decoder) = disasm::decoder_at(image, buf, begin) else {
+        return 0;
+    };
+    let end_va = image.image_base.wrapping_add(end as u64);
+    let mut instruction = Instruction::default();
+    let mut count = 0;
+    while decoder.can_decode() && count < budget {
+        decoder.decode_out(&mut instruction);
+        if instruction.ip() >= end_va {
+            break;
+        }
+        count += 1;
+        if !instruction.is_invalid() {
+            collect_instruction(image, buf, &instruction, features);
+        }
+    }
+    count
+}
+
+fn function_features(
+    image: &PeImage,
+    buf: &[u8],
+    functions: &[Vec<RuntimeFunction>],
+) -> Vec<(u32, Features)> {
+    let mut budget = MAX_CAPABILITY_INSTRUCTIONS;
+    let mut result = Vec::new();
+    for ranges in functions {
+        if budget == 0 {
+            break;
+        }
+        let mut features = Features::default();
+        for range in ranges {
+            budget -= collect_range(image, buf, range.begin, range.end, budget, &mut features);
+        }
+        result.push((ranges[0].begin, features));
+    }
+    result
+}
+
+//没有.pdata时线性扫描可执行节
+fn section_features(image: &PeImage, buf: &[u8]) -> Features {
+    let mut budget = MAX_CAPABILITY_INSTRUCTIONS;
+    let mut features = Features::default();
+    for section in image
+        .sections
+        .iter()
+        .filter(|section| section.is_executable())
+    {
+        let end = section
+            .virtual_address
+            .saturating_add(section.virtual_size.max(section.size_of_raw_data));
+        budget -= collect_range(
+            image,
+            buf,
+            section.virtual_address,
+            end,
+            budget,
+            &mut features,
+        );
+    }
+    features
+}
+
+pub fn detect_capabilities(
+    image: Option<&PeImage>,
+    buf: &[u8],
+    rules: &[CapabilityRule],
+) -> Vec<CapabilityHit> {
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    let functions = image
+        .map(|image| function_features(image, buf, &image.runtime_functions(buf)))
+        .unwrap_or_default();
+    let mut file_features = Features::default();
+    for (_, features) in &functions {
+        file_features.merge(features);
+    }
+    if let Some(image) = image {
+        if functions.is_empty() {
+            file_features.merge(&section_features(image, buf));
+        }
+        for import in &image.imports {
+            if let Some(name) = &import.name {
+                file_features.add_api(name);
+            }
+        }
+    }
+    for (_, text) in strings::extract_ascii(buf, MIN_STRING_LEN)
+        .into_iter()
+        .chain(strings::extract_utf16le(buf, MIN_STRING_LEN))
+    {
+        file_features.strings.insert(text.to_lowercase());
+    }
+
+    let mut result = Vec::new();
+    for rule in rules {
+        let scoped = rule.scope == CapabilityScope::Function && !functions.is_empty();
+        let (functions, evidence) = if scoped {
+            let mut matched_functions = Vec::new();
+            let mut evidence: Vec<String> = Vec::new();
+            for (begin, features) in &functions {
+                if let Some(items) = evaluate(&rule.condition, features) {
+                    matched_functions.push(*begin);
+                    for item in items {
+                        if !evidence.contains(&item) {
+                            evidence.push(item);
+                        }
+                    }
+                }
+            }
+            if matched_functions.is_empty() {
+                continue;
+            }
+            matched_functions.truncate(MAX_REPORTED_FUNCTIONS);
+            (matched_functions, evidence)
+        } else {
+            let Some(items) = evaluate(&rule.condition, &file_features) else {
+                continue;
+            };
+            let mut evidence: Vec<String> = Vec::new();
+            for item in items {
+                if !evidence.contains(&item) {
+                    evidence.push(item);
+                }
+            }
+            (Vec::new(), evidence)
+        };
+        result.push(CapabilityHit {
+            name: rule.name.clone(),
+            technique_id: rule.technique_id.clone(),
+            description: rule.description.clone(),
+            scope: if scoped {
+                CapabilityScope::Function
+            } else {
+                CapabilityScope::File
+            },
+            functions,
+            evidence,
+        });
+    }
+    result
+}
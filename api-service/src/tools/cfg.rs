@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use iced_x86::{FlowControl, Instruction, OpKind};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::disasm::{self, DisasmInstruction};
+use crate::tools::pe_image::PeImage;
+
+pub const MAX_CFG_INSTRUCTIONS: usize = 20000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    Fallthrough,
+    Conditional,
+    Unconditional,
+    Call,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockTerminator {
+    Return,
+    IndirectJump,
+    ConditionalJump,
+    Jump,
+    Fallthrough,
+    SectionEnd,
+    Invalid,
+    Limit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasicBlock {
+    pub start_rva: u32,
+    pub end_rva: u32,
+    pub terminator: BlockTerminator,
+    pub instructions: Vec<DisasmInstruction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CfgEdge {
+    pub from: u32,
+    // 通过IAT的间接调用没有目标RVA
+    pub to: Option<u32>,
+    pub kind: EdgeKind,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlFlowGraph {
+    pub start_rva: u32,
+    pub truncated: bool,
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<CfgEdge>,
+}
+
+fn is_near_branch(instruction: &Instruction) -> bool {
+    matches!(
+        instruction.op0_kind(),
+        OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64
+    )
+}
+
+//从起始地址开始的基本块恢复，不跟进被调用函数，遇到返回、间接跳转和节边界时停止
+pub fn recover_cfg(
+    image: &PeImage,
+    buf: &[u8],
+    start_rva: u32,
+    max_instructions: usize,
+) -> anyhow::Result<ControlFlowGraph> {
+    let section_index = image
+        .section_by_rva(start_rva)
+        .map(|section| section.index)
+        .ok_or_else(|| anyhow::anyhow!("地址 {:#X} 不属于任何节", start_rva))?;
+    let in_section = |rva: u32| {
+        image
+            .section_by_rva(rva)
+            .is_some_and(|section| section.index == section_index)
+    };
+    let max_instructions = max_instructions.min(MAX_CFG_INSTRUCTIONS);
+    let mut instructions: BTreeMap<u32, Instruction> = BTreeMap::new();
+    let mut leaders: BTreeSet<u32> = BTreeSet::from([start_rva]);
+    let mut queue: VecDeque<u32> = VecDeque::from([start_rva]);
+    let mut truncated = false;
+
+    'queue: while let Some(rva) = queue.pop_front() {
+        if instructions.contains_key(&rva) {
+            continue;
+        }
+        let Some(mut decoder) = disasm::decoder_at(image, buf, rva) else {
+            continue;
+        };
+        while decoder.can_decode() {
+            if instructions.len() >= max_instructions {
+                truncated = true;
+                break 'queue;
+            }
+            let instruction = decoder.decode();
+            let current = image.va_to_rva(instruction.ip()).unwrap_or(rva);
+            if instructions.contains_key(&current) {
+                leaders.insert(current);
+                break;
+            }
+            instructions.insert(current, instruction);
+            if instruction.is_invalid() {
+                break;
+            }
+            match instruction.flow_control() {
+                FlowControl::ConditionalBranch => {
+                    let next = current.wrapping_add(instruction.len() as u32);
+                    for target in [
+                        image.va_to_rva(instruction.near_branch_target()),
+                        Some(next),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    {
+                        if in_section(target) {
+                            leaders.insert(target);
+                            queue.push_back(target);
+                        }
+                    }
+                    break;
+                }
+                FlowControl::UnconditionalBranch => {
+                    if is_near_branch(&instruction) {
+                        if let Some(target) = image.va_to_rva(instruction.near_branch_target()) {
+                            if in_section(target) {
+                                leaders.insert(target);
+                                queue.push_back(target);
+                            }
+                        }
+                    }
+                    break;
+                }
+                FlowControl::IndirectBranch | FlowControl::Return | FlowControl::Exception => break,
+                _ => {}
+            }
+        }
+    }
+
+    let mut formatter = disasm::new_formatter();
+    let mut blocks: Vec<BasicBlock> = Vec::new();
+    let mut edges: Vec<CfgEdge> = Vec::new();
+    let mut current: Option<BasicBlock> = None;
+    let mut iter = instructions.iter().peekable();
+    while let Some((&rva, instruction)) = iter.next() {
+        let block = current.get_or_insert_with(|| BasicBlock {
+            start_rva: rva,
+            end_rva: rva,
+            terminator: BlockTerminator::Fallthrough,
+            instructions: Vec::new(),
+        });
+        let described = disasm::describe_instruction(image, buf, &mut formatter, instruction);
+        if matches!(
+            instruction.flow_control(),
+            FlowControl::Call | FlowControl::IndirectCall
+        ) {
+            let to = if is_near_branch(instruction) {
+                image.va_to_rva(instruction.near_branch_target())
+            } else {
+                None
+            };
+            edges.push(CfgEdge {
+                from: block.start_rva,
+                to,
+                kind: EdgeKind::Call,
+                label: described.comment.clone(),
+            });
+        }
+        block.instructions.push(described);
+        let next = rva.wrapping_add(instruction.len() as u32);
+        block.end_rva = next;
+        let next_is_contiguous = iter.peek().is_some_and(|(&following, _)| following == next);
+        let ends_block = instruction.is_invalid()
+            || matches!(
+                instruction.flow_control(),
+                FlowControl::ConditionalBranch
+                    | FlowControl::UnconditionalBranch
+                    | FlowControl::IndirectBranch
+                    | FlowControl::Return
+                    | FlowControl::Exception
+            )
+            || !next_is_contiguous
+            || leaders.contains(&next);
+        if !ends_block {
+            continue;
+        }
+        let mut block = current.take().unwrap();
+        block.terminator = if instruction.is_invalid() {
+            BlockTerminator::Invalid
+        } else {
+            match instruction.flow_control() {
+                FlowControl::Return => BlockTerminator::Return,
+                FlowControl::IndirectBranch => BlockTerminator::IndirectJump,
+                FlowControl::ConditionalBranch => BlockTerminator::ConditionalJump,
+                FlowControl::UnconditionalBranch if is_near_branch(instruction) => {
+                    BlockTerminator::Jump
+                }
+                FlowControl::UnconditionalBranch => BlockTerminator::IndirectJump,
+                FlowControl::Exception => BlockTerminator::Invalid,
+                _ if next_is_contiguous => BlockTerminator::Fallthrough,
+                _ if !in_section(next) || disasm::decoder_at(image, buf, next).is_none() => {
+                    BlockTerminator::SectionEnd
+                }
+                _ => BlockTerminator::Limit,
+            }
+        };
+        match block.terminator {
+            BlockTerminator::ConditionalJump => {
+                if let Some(target) = image.va_to_rva(instruction.near_branch_target()) {
+                    if instructions.contains_key(&target) {
+                        edges.push(CfgEdge {
+                            from: block.start_rva,
+                            to: Some(target),
+                            kind: EdgeKind::Conditional,
+                            label: None,
+                        });
+                    }
+                }
+                if instructions.contains_key(&next) {
+                    edges.push(CfgEdge {
+                        from: block.start_rva,
+                        to: Some(next),
+                        kind: EdgeKind::Fallthrough,
+                        label: None,
+                    });
+                }
+            }
+            BlockTerminator::Jump => {
+                if let Some(target) = image.va_to_rva(instruction.near_branch_target()) {
+                    if instructions.contains_key(&target) {
+                        edges.push(CfgEdge {
+                            from: block.start_rva,
+                            to: Some(target),
+                            kind: EdgeKind::Unconditional,
+                            label: None,
+                        });
+                    }
+                }
+            }
+            BlockTerminator::Fallthrough => edges.push(CfgEdge {
+                from: block.start_rva,
+                to: Some(next),
+                kind: EdgeKind::Fallthrough,
+                label: None,
+            }),
+            _ => {}
+        }
+        blocks.push(block);
+    }
+    if let Some(mut block) = current.take() {
+        block.terminator = BlockTerminator::Limit;
+        blocks.push(block);
+    }
+    Ok(ControlFlowGraph {
+        start_rva,
+        truncated,
+        blocks,
+        edges,
+    })
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('{', "\\{")
+        .replace('}', "\\}")
+        .replace('<', "\\<")
+        .replace('>', "\\>")
+        .replace('|', "\\|")
+}
+
+//输出Graphviz DOT格式
+pub fn to_dot(graph: &ControlFlowGraph) -> String {
+    let mut dot = String::new();
+    dot.push_str("digraph cfg {\n");
+    dot.push_str("    node [shape=box, fontname=\"Courier\"];\n");
+    let block_starts = graph
+        .blocks
+        .iter()
+        .map(|block| block.start_rva)
+        .collect::<BTreeSet<_>>();
+    for block in &graph.blocks {
+        let mut label = format!("{:08X}:\\l", block.start_rva);
+        for instruction in &block.instructions {
+            let mut line = format!(
+                "{:08X}  {} {}",
+                instruction.rva, instruction.mnemonic, instruction.operands
+            );
+            if let Some(comment) = &instruction.comment {
+                line.push_str(&format!(" ; {}", comment));
+            }
+            label.push_str(&escape_dot(line.trim_end()));
+            label.push_str("\\l");
+        }
+        let style = if block.start_rva == graph.start_rva {
+            ", style=bold"
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "    \"b_{:08X}\" [label=\"{}\"{}];\n",
+            block.start_rva, label, style
+        ));
+    }
+    let conditional_blocks = graph
+        .blocks
+        .iter()
+        .filter(|block| block.terminator == BlockTerminator::ConditionalJump)
+        .map(|block| block.start_rva)
+        .collect::<BTreeSet<_>>();
+    let mut call_nodes = BTreeSet::new();
+    for edge in &graph.edges {
+        let (to, attributes) = match (edge.kind, edge.to) {
+            (EdgeKind::Call, to) => {
+                let name = match (&edge.label, to) {
+                    (Some(label), _) => label.clone(),
+                    (None, Some(to)) => format!("sub_{:08X}", to),
+                    (None, None) => continue,
+                };
+                if call_nodes.insert(name.clone()) {
+                    dot.push_str(&format!(
+                        "    \"call_{}\" [label=\"{}\", shape=ellipse];\n",
+                        escape_dot(&name),
+                        escape_dot(&name)
+                    ));
+                }
+                (
+                    format!("call_{}", escape_dot(&name)),
+                    "style=dashed, color=gray",
+                )
+            }
+            (_, Some(to)) if block_starts.contains(&to) => (
+                format!("b_{:08X}", to),
+                match edge.kind {
+                    EdgeKind::Conditional => "color=green, label=\"T\"",
+                    EdgeKind::Unconditional => "color=blue",
+                    _ if conditional_blocks.contains(&edge.from) => "color=red, label=\"F\"",
+                    _ => "color=black",
+                },
+            ),
+            _ => continue,
+        };
+        dot.push_str(&format!(
+            "    \"b_{:08X}\" -> \"{}\" [{}];\n",
+            edge.from, to, attributes
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,224 @@
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::PeImage;
+
+// 字符串预览最多读取的字节数
+const MAX_STRING_PREVIEW: usize = 64;
+// 返回的原始字节数
+const RAW_BYTES: usize = 16;
+// FILETIME起点1601-01-01到Unix时间起点的秒数
+const FILETIME_UNIX_DIFF: i64 = 11_644_473_600;
+
+//同一类型按小端与大端解析的结果，剩余字节不足时为空
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InspectedValue {
+    pub name: String,
+    pub little_endian: Option<String>,
+    pub big_endian: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inspection {
+    pub offset: u64,
+    pub rva: Option<u32>,
+    pub section: Option<String>,
+    pub bytes: String,
+    pub values: Vec<InspectedValue>,
+}
+
+fn take<const N: usize>(buf: &[u8]) -> Option<[u8; N]> {
+    buf.get(..N)?.try_into().ok()
+}
+
+//大端时反转字节，之后统一按小端解析
+fn ordered<const N: usize>(mut bytes: [u8; N], little_endian: bool) -> [u8; N] {
+    if !little_endian {
+        bytes.reverse();
+    }
+    bytes
+}
+
+//数值过大或过小时使用科学计数法
+fn float_text<T: std::fmt::Display + std::fmt::LowerExp>(value: T, magnitude: f64) -> String {
+    if magnitude != 0.0 && !(1e-6..1e15).contains(&magnitude) {
+        format!("{:e}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_time(seconds: i64, nanos: u32) -> Option<String> {
+    DateTime::from_timestamp(seconds, nanos)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S%.f UTC").to_string())
+}
+
+//FILETIME是自1601年起的100纳秒数
+fn filetime_text(value: u64) -> Option<String> {
+    let seconds = (value / 10_000_000) as i64 - FILETIME_UNIX_DIFF;
+    let nanos = (value % 10_000_000) as u32 * 100;
+    format_time(seconds, nanos)
+}
+
+//低16位为时间、高16位为日期，秒数以2秒为单位
+fn dos_datetime_text(time: u16, date: u16) -> Option<String> {
+    let datetime = NaiveDate::from_ymd_opt(
+        1980 + (date >> 9) as i32,
+        ((date >> 5) & 0x0F) as u32,
+        (date & 0x1F) as u32,
+    )?
+    .and_hms_opt(
+        (time >> 11) as u32,
+        ((time >> 5) & 0x3F) as u32,
+        ((time & 0x1F) * 2) as u32,
+    )?;
+    Some(datetime.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+fn guid_text(data1: u32, data2: u16, data3: u16, data4: &[u8]) -> String {
+    format!(
+        "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}}}",
+        data1,
+        data2,
+        data3,
+        data4[0],
+        data4[1],
+        hex::encode_upper(&data4[2..8])
+    )
+}
+
+//遇到0结束，预览长度有限
+fn utf16_text(buf: &[u8], little_endian: bool) -> String {
+    let units = buf[..buf.len().min(MAX_STRING_PREVIEW)]
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes(ordered([unit[0], unit[1]], little_endian)))
+        .take_while(|unit| *unit != 0)
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+}
+
+fn utf8_text(buf: &[u8]) -> String {
+    let bytes = &buf[..buf.len().min(MAX_STRING_PREVIEW)];
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+struct Values<'a> {
+    buf: &'a [u8],
+    values: Vec<InspectedValue>,
+}
+
+impl Values<'_> {
+    fn push(&mut self, name: &str, little_endian: Option<String>, big_endian: Option<String>) {
+        self.values.push(InspectedValue {
+            name: name.to_string(),
+            little_endian,
+            big_endian,
+        });
+    }
+
+    //按N字节读取并分别以小端、大端解析
+    fn pair<const N: usize>(&mut self, name: &str, f: impl Fn([u8; N], bool) -> Option<String>) {
+        let bytes = take::<N>(self.buf);
+        let little_endian = bytes.and_then(|bytes| f(bytes, true));
+        let big_endian = bytes.and_then(|bytes| f(bytes, false));
+        self.push(name, little_endian, big_endian);
+    }
+}
+
+//RVA所在的文件偏移与节
+fn rva_text(image: &PeImage, rva: u32) -> Option<String> {
+    let offset = image.rva_to_offset(rva)?;
+    let section = image
+        .section_by_rva(rva)
+        .map(|section| format!(" ({})", section.name))
+        .unwrap_or_default();
+    Some(format!("0x{:08X} → 0x{:08X}{}", rva, offset, section))
+}
+
+//按常见数据类型解析offset处的字节
+pub fn inspect(buf: &[u8], offset: usize, image: Option<&PeImage>) -> Inspection {
+    let data = buf.get(offset..).unwrap_or_default();
+    let mut values = Values {
+        buf: data,
+        values: Vec::new(),
+    };
+    values.pair("int8", |bytes, _| {
+        Some(i8::from_le_bytes(bytes).to_string())
+    });
+    values.pair("uint8", |bytes, _| {
+        Some(u8::from_le_bytes(bytes).to_string())
+    });
+    values.pair("int16", |bytes, le| {
+        Some(i16::from_le_bytes(ordered(bytes, le)).to_string())
+    });
+    values.pair("uint16", |bytes, le| {
+        Some(u16::from_le_bytes(ordered(bytes, le)).to_string())
+    });
+    values.pair("int32", |bytes, le| {
+        Some(i32::from_le_bytes(ordered(bytes, le)).to_string())
+    });
+    values.pair("uint32", |bytes, le| {
+        Some(u32::from_le_bytes(ordered(bytes, le)).to_string())
+    });
+    values.pair("int64", |bytes, le| {
+        Some(i64::from_le_bytes(ordered(bytes, le)).to_string())
+    });
+    values.pair("uint64", |bytes, le| {
+        Some(u64::from_le_bytes(ordered(bytes, le)).to_string())
+    });
+    values.pair("float32", |bytes, le| {
+        let value = f32::from_le_bytes(ordered(bytes, le));
+        Some(float_text(value, (value as f64).abs()))
+    });
+    values.pair("float64", |bytes, le| {
+        let value = f64::from_le_bytes(ordered(bytes, le));
+        Some(float_text(value, value.abs()))
+    });
+    values.pair("FILETIME", |bytes, le| {
+        filetime_text(u64::from_le_bytes(ordered(bytes, le)))
+    });
+    values.pair("Unix time", |bytes, le| {
+        format_time(u32::from_le_bytes(ordered(bytes, le)) as i64, 0)
+    });
+    // 时间与日期各占2字节，分别按字节序读取
+    values.pair("DOS date/time", |bytes: [u8; 4], le| {
+        dos_datetime_text(
+            u16::from_le_bytes(ordered([bytes[0], bytes[1]], le)),
+            u16::from_le_bytes(ordered([bytes[2], bytes[3]], le)),
+        )
+    });
+    // 小端为Windows的GUID结构，大端为RFC 4122的字节顺序
+    values.pair("GUID", |bytes: [u8; 16], le| {
+        Some(guid_text(
+            u32::from_le_bytes(ordered([bytes[0], bytes[1], bytes[2], bytes[3]], le)),
+            u16::from_le_bytes(ordered([bytes[4], bytes[5]], le)),
+            u16::from_le_bytes(ordered([bytes[6], bytes[7]], le)),
+            &bytes[8..],
+        ))
+    });
+    let utf8 = (!data.is_empty()).then(|| utf8_text(data));
+    values.push("UTF-8", utf8.clone(), utf8);
+    values.push(
+        "UTF-16",
+        (data.len() >= 2).then(|| utf16_text(data, true)),
+        (data.len() >= 2).then(|| utf16_text(data, false)),
+    );
+    if let Some(image) = image {
+        values.pair("RVA", |bytes, le| {
+            rva_text(image, u32::from_le_bytes(ordered(bytes, le)))
+        });
+    }
+    Inspection {
+        offset: offset as u64,
+        rva: image.and_then(|image| image.offset_to_rva(offset)),
+        section: image
+            .and_then(|image| image.section_by_offset(offset))
+            .map(|section| section.name.clone()),
+        bytes: hex::encode_upper(&data[..data.len().min(RAW_BYTES)]),
+        values: values.values,
+    }
+}
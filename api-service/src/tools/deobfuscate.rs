@@ -0,0 +1,454 @@
+use std::collections::{BTreeMap, HashSet};
+
+use iced_x86::{FlowControl, Instruction, Mnemonic, OpKind, Register};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::disasm;
+use crate::tools::ioc;
+use crate::tools::pe_image::PeImage;
+use crate::tools::pe_tools;
+use crate::tools::strings::{self, StringEncoding};
+
+pub const DEFAULT_MIN_LEN: usize = 6;
+pub const DEFAULT_MIN_ENTROPY: f64 = 3.0;
+const MAX_RECOVERED_COUNT: usize = 5000;
+const XOR_WINDOW_SIZE: usize = 256;
+// 相邻窗口重叠，避免字符串被窗口边界截断
+const XOR_WINDOW_OVERLAP: usize = 64;
+// 异或爆破开销较大，只扫描前若干字节
+const MAX_XOR_SCAN_SIZE: usize = 4 * 1024 * 1024;
+// 异或爆破的误报较多，字符串长度至少为8
+const MIN_XOR_LEN: usize = 8;
+// 连续写栈指令之间允许夹杂的其他指令数量
+const MAX_STACK_GAP: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryMethod {
+    StackString,
+    SingleByteXor,
+    RollingXor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredString {
+    pub method: RecoveryMethod,
+    pub key: Option<String>,
+    pub offset: usize,
+    pub rva: Option<u32>,
+    pub encoding: StringEncoding,
+    pub value: String,
+}
+
+struct StackWrite {
+    displacement: i64,
+    bytes: Vec<u8>,
+}
+
+//mov byte/word/dword/qword ptr [reg+disp], imm
+fn immediate_store(instruction: &Instruction) -> Option<(Register, StackWrite)> {
+    if instruction.mnemonic() != Mnemonic::Mov
+        || instruction.op0_kind() != OpKind::Memory
+        || instruction.memory_index() != Register::None
+        || instruction.memory_base() == Register::None
+    {
+        return None;
+    }
+    if !matches!(
+        instruction.op1_kind(),
+        OpKind::Immediate8
+            | OpKind::Immediate16
+            | OpKind::Immediate32
+            | OpKind::Immediate8to16
+            | OpKind::Immediate8to32
+            | OpKind::Immediate8to64
+            | OpKind::Immediate32to64
+    ) {
+        return None;
+    }
+    let size = instruction.memory_size().size().min(8);
+    if size == 0 {
+        return None;
+    }
+    let value = instruction.immediate(1).to_le_bytes();
+    Some((
+        instruction.memory_base(),
+        StackWrite {
+            displacement: instruction.memory_displacement64() as i64,
+            bytes: value[..size].to_vec(),
+        },
+    ))
+}
+
+//把一组写栈指令还原为内存中的字节，再从中提取字符串
+fn flush_stack_writes(
+    writes: &mut Vec<StackWrite>,
+    min_len: usize,
+) -> Vec<(StringEncoding, String)> {
+    if writes.len() < 2 {
+        writes.clear();
+        return Vec::new();
+    }
+    let mut memory: BTreeMap<i64, u8> = BTreeMap::new();
+    for write in writes.drain(..) {
+        for (index, byte) in write.bytes.iter().enumerate() {
+            memory.insert(write.displacement + index as i64, *byte);
+        }
+    }
+    let mut result = Vec::new();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut last: Option<i64> = None;
+    let mut regions: Vec<Vec<u8>> = Vec::new();
+    for (displacement, byte) in memory {
+        if last.is_some_and(|last| displacement != last + 1) {
+            regions.push(std::mem::take(&mut buffer));
+        }
+        buffer.push(byte);
+        last = Some(displacement);
+    }
+    regions.push(buffer);
+    for region in regions {
+        result.extend(
+            strings::extract_ascii(&region, min_len)
+                .into_iter()
+                .map(|(_, value)| (StringEncoding::Ascii, value)),
+        );
+        result.extend(
+            strings::extract_utf16le(&region, min_len)
+                .into_iter()
+                .map(|(_, value)| (StringEncoding::Utf16le, value)),
+        );
+    }
+    result
+}
+
+//在可执行节中识别通过立即数逐字节写入栈中的字符串
+pub fn recover_stack_strings(image: &PeImage, buf: &[u8], min_len: usize) -> Vec<RecoveredString> {
+    let mut result = Vec::new();
+    for section in image
+        .sections
+        .iter()
+        .filter(|section| section.is_executable())
+    {
+        let Some(mut decoder) = disasm::decoder_at(image, buf, section.virtual_address) else {
+            continue;
+        };
+        let mut instruction = Instruction::default();
+        let mut writes: Vec<StackWrite> = Vec::new();
+        let mut base = Register::None;
+        let mut first_rva = section.virtual_address;
+        let mut gap = 0;
+        while decoder.can_decode() {
+            decoder.decode_out(&mut instruction);
+            let rva = image.va_to_rva(instruction.ip()).unwrap_or(0);
+            let store = immediate_store(&instruction);
+            let continues_run = match &store {
+                Some((register, _)) => writes.is_empty() || *register == base,
+                None => {
+                    gap += 1;
+                    gap <= MAX_STACK_GAP
+                        && !matches!(
+                            instruction.flow_control(),
+                            FlowControl::Call
+                                | FlowControl::IndirectCall
+                                | FlowControl::Return
+                                | FlowControl::UnconditionalBranch
+                                | FlowControl::IndirectBranch
+                                | FlowControl::ConditionalBranch
+                        )
+                }
+            };
+            if !continues_run {
+                for (encoding, value) in flush_stack_writes(&mut writes, min_len) {
+                    result.push(RecoveredString {
+                        method: RecoveryMethod::StackString,
+                        key: None,
+                        offset: image.rva_to_offset(first_rva).unwrap_or(0),
+                        rva: Some(first_rva),
+                        encoding,
+                        value,
+                    });
+                }
+            }
+            if let Some((register, write)) = store {
+                if writes.is_empty() {
+                    base = register;
+                    first_rva = rva;
+                }
+                writes.push(write);
+                gap = 0;
+            }
+        }
+        for (encoding, value) in flush_stack_writes(&mut writes, min_len) {
+            result.push(RecoveredString {
+                method: RecoveryMethod::StackString,
+                key: None,
+                offset: image.rva_to_offset(first_rva).unwrap_or(0),
+                rva: Some(first_rva),
+                encoding,
+                value,
+            });
+        }
+    }
+    result
+}
+
+//文本评分，不像自然文本时返回None，分值越高越像英文
+fn text_score(value: &str, min_len: usize) -> Option<f64> {
+    let chars = value.chars().collect::<Vec<_>>();
+    if chars.len() < min_len {
+        return None;
+    }
+    let pairs = chars.windows(2);
+    // 重复字符、递增递减字节表都不是文本
+    let repeated = pairs.clone().filter(|pair| pair[0] == pair[1]).count();
+    let sequential = pairs
+        .clone()
+        .filter(|pair| (pair[0] as i32 - pair[1] as i32).abs() == 1)
+        .count();
+    // 按固定间隔重复的字符多来自结构体数组
+    let periodic = (2..=4)
+        .map(|period| {
+            chars
+                .iter()
+                .zip(chars.iter().skip(period))
+                .filter(|(a, b)| a == b)
+                .count()
+        })
+        .max()
+        .unwrap_or(0);
+    if repeated * 5 > chars.len() || sequential * 5 > chars.len() || periodic * 5 > chars.len() {
+        return None;
+    }
+    let letters = chars.iter().filter(|c| c.is_ascii_alphabetic()).count();
+    let readable = chars
+        .iter()
+        .filter(|c| c.is_ascii_alphanumeric() || " ._-:/\\@%".contains(**c))
+        .count();
+    let common = chars
+        .iter()
+        .filter(|c| "etaoinshrdlcu".contains(c.to_ascii_lowercase()))
+        .count();
+    // 大小写来回切换（如 LiBrArY）说明密钥不对
+    let case_switches = pairs
+        .filter(|pair| pair[0].is_ascii_lowercase() && pair[1].is_ascii_uppercase())
+        .count();
+    let vowels = chars
+        .iter()
+        .filter(|c| "aeiouy".contains(c.to_ascii_lowercase()))
+        .count();
+    let distinct = chars.iter().collect::<HashSet<_>>().len();
+    if readable * 20 < chars.len() * 19 || distinct < 4.max(chars.len() / 4) || letters == 0 {
+        return None;
+    }
+    let score = common as f64 / letters as f64 - case_switches as f64 / letters as f64;
+    // 能匹配到IOC的结果不再要求像英文
+    if !ioc::match_iocs(value).is_empty() {
+        return Some(score + 1.0);
+    }
+    if letters * 5 < chars.len() * 3
+        || common * 2 < letters
+        || case_switches * 8 > letters
+        || vowels * 5 < letters
+        || vowels * 5 > letters * 3
+    {
+        return None;
+    }
+    Some(score)
+}
+
+//明文中的0x00异或后会变成密钥本身，密钥字符占比过高说明只是填充数据
+fn is_key_padding(value: &str, key: &[u8]) -> bool {
+    let count = value.bytes().filter(|byte| key.contains(byte)).count();
+    count * 4 > value.len()
+}
+
+//密文本身已经以字母为主时（如大小写翻转），不算还原结果
+fn is_letter_text(original: &[u8], step: usize) -> bool {
+    let total = original.iter().step_by(step).count();
+    let letters = original
+        .iter()
+        .step_by(step)
+        .filter(|byte| byte.is_ascii_alphabetic())
+        .count();
+    letters * 2 >= total
+}
+
+struct Candidate {
+    offset: usize,
+    encoding: StringEncoding,
+    value: String,
+    score: f64,
+}
+
+fn decode_candidates(
+    decoded: &[u8],
+    original: &[u8],
+    key: &[u8],
+    min_len: usize,
+) -> Vec<Candidate> {
+    let ascii = strings::extract_ascii(decoded, min_len)
+        .into_iter()
+        .map(|(offset, value)| {
+            let end = offset + value.len();
+            (offset, end, 1, StringEncoding::Ascii, value)
+        });
+    let utf16 = strings::extract_utf16le(decoded, min_len)
+        .into_iter()
+        .map(|(offset, value)| {
+            let end = (offset + value.len() * 2).min(original.len());
+            (offset, end, 2, StringEncoding::Utf16le, value)
+        });
+    ascii
+        .chain(utf16)
+        .filter(|(offset, end, step, _, value)| {
+            !is_key_padding(value, key) && !is_letter_text(&original[*offset..*end], *step)
+        })
+        .filter_map(|(offset, _, _, encoding, value)| {
+            text_score(&value, min_len).map(|score| Candidate {
+                offset,
+                encoding,
+                value,
+                score,
+            })
+        })
+        .collect()
+}
+
+//同一位置往往有多个密钥都能解出可打印文本，只保留得分最高的一个
+fn keep_best(
+    best: &mut BTreeMap<(usize, usize), (Candidate, RecoveryMethod, Vec<u8>)>,
+    candidates: Vec<Candidate>,
+    method: RecoveryMethod,
+    key: &[u8],
+) {
+    for candidate in candidates {
+        let slot = (candidate.offset, candidate.encoding as usize);
+        if best
+            .get(&slot)
+            .is_none_or(|(current, _, _)| candidate.score > current.score)
+        {
+            best.insert(slot, (candidate, method, key.to_vec()));
+        }
+    }
+}
+
+//对非代码区域中熵值足够的数据块尝试单字节与短循环密钥异或
+pub fn recover_xor_strings(
+    image: Option<&PeImage>,
+    buf: &[u8],
+    min_len: usize,
+    min_entropy: f64,
+) -> Vec<RecoveredString> {
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    match image {
+        Some(image) => {
+            for section in image
+                .sections
+                .iter()
+                .filter(|section| !section.is_executable())
+            {
+                let start = section.pointer_to_raw_data as usize;
+                let end = (start + section.size_of_raw_data as usize).min(buf.len());
+                if start < end {
+                    regions.push((start, end));
+                }
+            }
+            // 附加数据
+            let image_end = image
+                .sections
+                .iter()
+                .map(|section| {
+                    section.pointer_to_raw_data as usize + section.size_of_raw_data as usize
+                })
+                .max()
+                .unwrap_or(buf.len());
+            if image_end < buf.len() {
+                regions.push((image_end, buf.len()));
+            }
+        }
+        None => regions.push((0, buf.len())),
+    }
+    let min_len = min_len.max(MIN_XOR_LEN);
+    let mut result = Vec::new();
+    let mut scanned = 0;
+    for (start, end) in regions {
+        if scanned >= MAX_XOR_SCAN_SIZE {
+            break;
+        }
+        let end = end.min(start + MAX_XOR_SCAN_SIZE - scanned);
+        scanned += end - start;
+        let mut window_start = start;
+        while window_start < end {
+            let window_end = (window_start + XOR_WINDOW_SIZE).min(end);
+            let window = &buf[window_start..window_end];
+            let base_offset = window_start;
+            window_start = if window_end < end {
+                window_end - XOR_WINDOW_OVERLAP
+            } else {
+                end
+            };
+            if pe_tools::shannon_entropy(window) < min_entropy {
+                continue;
+            }
+            let mut best = BTreeMap::new();
+            for key in 1..=255u8 {
+                let decoded = window.iter().map(|byte| byte ^ key).collect::<Vec<_>>();
+                let candidates = decode_candidates(&decoded, window, &[key], min_len);
+                keep_best(&mut best, candidates, RecoveryMethod::SingleByteXor, &[key]);
+            }
+            // 循环密钥：假设明文中0x00最多，每个位置出现最多的字节即为密钥
+            for key_len in 2..=4usize {
+                let key = (0..key_len)
+                    .map(|position| {
+                        let mut counts = [0usize; 256];
+                        for byte in window.iter().skip(position).step_by(key_len) {
+                            counts[*byte as usize] += 1;
+                        }
+                        (0..256).max_by_key(|value| counts[*value]).unwrap_or(0) as u8
+                    })
+                    .collect::<Vec<_>>();
+                // 退化为单字节密钥的情况已经处理过
+                if key.iter().all(|byte| *byte == key[0]) {
+                    continue;
+                }
+                let decoded = window
+                    .iter()
+                    .enumerate()
+                    .map(|(index, byte)| byte ^ key[index % key_len])
+                    .collect::<Vec<_>>();
+                let candidates = decode_candidates(&decoded, window, &key, min_len);
+                keep_best(&mut best, candidates, RecoveryMethod::RollingXor, &key);
+            }
+            for (candidate, method, key) in best.into_values() {
+                let offset = base_offset + candidate.offset;
+                result.push(RecoveredString {
+                    method,
+                    key: Some(hex::encode_upper(&key)),
+                    offset,
+                    rva: image.and_then(|image| image.offset_to_rva(offset)),
+                    encoding: candidate.encoding,
+                    value: candidate.value,
+                });
+            }
+        }
+    }
+    result
+}
+
+pub fn recover_strings(
+    image: Option<&PeImage>,
+    buf: &[u8],
+    min_len: usize,
+    min_entropy: f64,
+) -> Vec<RecoveredString> {
+    let mut result = match image {
+        Some(image) => recover_stack_strings(image, buf, min_len),
+        None => Vec::new(),
+    };
+    result.extend(recover_xor_strings(image, buf, min_len, min_entropy));
+    let mut seen = HashSet::new();
+    result.retain(|item| seen.insert(item.value.clone()));
+    result.truncate(MAX_RECOVERED_COUNT);
+    result
+}
@@ -0,0 +1,143 @@
+use iced_x86::{
+    Decoder, DecoderOptions, FlowControl, Formatter, Instruction, IntelFormatter, OpKind, Register,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::PeImage;
+
+pub const MAX_INSTRUCTION_COUNT: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisasmInstruction {
+    pub address: u64,
+    pub rva: u32,
+    pub offset: usize,
+    pub bytes: String,
+    pub mnemonic: String,
+    pub operands: String,
+    pub comment: Option<String>,
+}
+
+pub fn new_formatter() -> IntelFormatter {
+    let mut formatter = IntelFormatter::new();
+    formatter.options_mut().set_uppercase_hex(true);
+    formatter.options_mut().set_hex_prefix("0x");
+    formatter.options_mut().set_hex_suffix("");
+    formatter
+        .options_mut()
+        .set_space_after_operand_separator(true);
+    formatter
+}
+
+//从RVA开始构造解码器，解码范围限制在所在节内
+pub fn decoder_at<'a>(image: &PeImage, buf: &'a [u8], rva: u32) -> Option<Decoder<'a>> {
+    let bytes = image.bytes_at_rva(buf, rva)?;
+    Some(Decoder::with_ip(
+        image.bitness(),
+        bytes,
+        image.image_base.wrapping_add(rva as u64),
+        DecoderOptions::NONE,
+    ))
+}
+
+//间接调用/跳转所引用的内存地址（VA）
+pub fn memory_target(instruction: &Instruction) -> Option<u64> {
+    if instruction.op_count() == 0 || instruction.op0_kind() != OpKind::Memory {
+        return None;
+    }
+    if instruction.is_ip_rel_memory_operand() {
+        return Some(instruction.ip_rel_memory_address());
+    }
+    if instruction.memory_base() == Register::None && instruction.memory_index() == Register::None {
+        return Some(instruction.memory_displacement64());
+    }
+    None
+}
+
+//通过IAT调用的导入函数名称，同时识别 call thunk -> jmp [IAT] 的跳板形式
+pub fn resolve_import_call(
+    image: &PeImage,
+    buf: &[u8],
+    instruction: &Instruction,
+) -> Option<String> {
+    let iat_import = |instruction: &Instruction| {
+        memory_target(instruction)
+            .and_then(|va| image.va_to_rva(va))
+            .and_then(|rva| image.import_by_iat_rva(rva))
+            .map(|import| import.display_name())
+    };
+    match instruction.flow_control() {
+        FlowControl::IndirectCall | FlowControl::IndirectBranch => iat_import(instruction),
+        FlowControl::Call | FlowControl::UnconditionalBranch => {
+            let target_rva = image.va_to_rva(instruction.near_branch_target())?;
+            let mut decoder = decoder_at(image, buf, target_rva)?;
+            if !decoder.can_decode() {
+                return None;
+            }
+            let thunk = decoder.decode();
+            if thunk.flow_control() == FlowControl::IndirectBranch {
+                iat_import(&thunk)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+//将解码后的指令转换为接口输出格式
+pub fn describe_instruction(
+    image: &PeImage,
+    buf: &[u8],
+    formatter: &mut IntelFormatter,
+    instruction: &Instruction,
+) -> DisasmInstruction {
+    let rva = image.va_to_rva(instruction.ip()).unwrap_or(0);
+    let offset = image.rva_to_offset(rva).unwrap_or(0);
+    let bytes = buf
+        .get(offset..offset + instruction.len())
+        .map(hex::encode_upper)
+        .unwrap_or_default();
+    let mut mnemonic = String::new();
+    let mut operands = String::new();
+    let comment = if instruction.is_invalid() {
+        mnemonic.push_str("(bad)");
+        None
+    } else {
+        formatter.format_mnemonic(instruction, &mut mnemonic);
+        formatter.format_all_operands(instruction, &mut operands);
+        resolve_import_call(image, buf, instruction)
+    };
+    DisasmInstruction {
+        address: instruction.ip(),
+        rva,
+        offset,
+        bytes,
+        mnemonic,
+        operands,
+        comment,
+    }
+}
+
+pub fn disassemble(
+    image: &PeImage,
+    buf: &[u8],
+    rva: u32,
+    count: usize,
+) -> anyhow::Result<Vec<DisasmInstruction>> {
+    let mut decoder = decoder_at(image, buf, rva)
+        .ok_or_else(|| anyhow::anyhow!("地址 {:#X} 不在文件数据范围内", rva))?;
+    let mut formatter = new_formatter();
+    let mut instruction = Instruction::default();
+    let mut result = Vec::new();
+    while decoder.can_decode() && result.len() < count.min(MAX_INSTRUCTION_COUNT) {
+        decoder.decode_out(&mut instruction);
+        result.push(describe_instruction(
+            image,
+            buf,
+            &mut formatter,
+            &instruction,
+        ));
+    }
+    Ok(result)
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::tools::pe_image::PeImage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashType {
+    Md5,
+    Sha1,
+    Sha256,
+    Imphash,
+}
+
+impl HashType {
+    pub const ALL: [HashType; 4] = [
+        HashType::Md5,
+        HashType::Sha1,
+        HashType::Sha256,
+        HashType::Imphash,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashType::Md5 => "md5",
+            HashType::Sha1 => "sha1",
+            HashType::Sha256 => "sha256",
+            HashType::Imphash => "imphash",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HashType::Md5 => "MD5",
+            HashType::Sha1 => "SHA-1",
+            HashType::Sha256 => "SHA-256",
+            HashType::Imphash => "导入表哈希",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<HashType> {
+        HashType::ALL
+            .into_iter()
+            .find(|item| item.as_str() == value.to_ascii_lowercase())
+    }
+
+    fn hex_len(&self) -> usize {
+        match self {
+            HashType::Md5 | HashType::Imphash => 32,
+            HashType::Sha1 => 40,
+            HashType::Sha256 => 64,
+        }
+    }
+
+    //根据长度识别哈希类型，32位按MD5处理
+    pub fn detect(value: &str) -> Option<HashType> {
+        match value.len() {
+            32 => Some(HashType::Md5),
+            40 => Some(HashType::Sha1),
+            64 => Some(HashType::Sha256),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListType {
+    Allow,
+    Block,
+}
+
+impl ListType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListType::Allow => "allow",
+            ListType::Block => "block",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ListType::Allow => "白名单",
+            ListType::Block => "黑名单",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ListType> {
+        match value.to_ascii_lowercase().as_str() {
+            "allow" => Some(ListType::Allow),
+            "block" => Some(ListType::Block),
+            _ => None,
+        }
+    }
+}
+
+//校验并规范化哈希值，返回小写十六进制字符串
+pub fn normalize_hash(hash_type: HashType, value: &str) -> Result<String, String> {
+    let value = value.trim().to_ascii_lowercase();
+    if value.len() != hash_type.hex_len() || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!(
+            "{}必须是{}位十六进制字符串: {}",
+            hash_type.display_name(),
+            hash_type.hex_len(),
+            value
+        ));
+    }
+    Ok(value)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHashes {
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+    // 非PE文件或没有导入表时为空
+    pub imphash: Option<String>,
+}
+
+impl FileHashes {
+    pub fn compute(buf: &[u8], image: Option<&PeImage>) -> FileHashes {
+        FileHashes {
+            md5: format!("{:x}", md5::compute(buf)),
+            sha1: hex::encode(Sha1::digest(buf)),
+            sha256: hex::encode(Sha256::digest(buf)),
+            imphash: image.and_then(imphash),
+        }
+    }
+
+    //按类型列出已计算的哈希值
+    pub fn values(&self) -> Vec<(HashType, String)> {
+        let mut values = vec![
+            (HashType::Md5, self.md5.clone()),
+            (HashType::Sha1, self.sha1.clone()),
+            (HashType::Sha256, self.sha256.clone()),
+        ];
+        if let Some(imphash) = &self.imphash {
+            values.push((HashType::Imphash, imphash.clone()));
+        }
+        values
+    }
+}
+
+//按序号导入时与pefile一致的函数名称，只包含Winsock的固定序号
+fn ordinal_name(dll: &str, ordinal: u16) -> Option<&'static str> {
+    if dll != "ws2_32" && dll != "wsock32" {
+        return None;
+    }
+    let name = match ordinal {
+        1 => "accept",
+        2 => "bind",
+        3 => "closesocket",
+        4 => "connect",
+        5 => "getpeername",
+        6 => "getsockname",
+        7 => "getsockopt",
+        8 => "htonl",
+        9 => "htons",
+        10 => "ioctlsocket",
+        11 => "inet_addr",
+        12 => "inet_ntoa",
+        13 => "listen",
+        14 => "ntohl",
+        15 => "ntohs",
+        16 => "recv",
+        17 => "recvfrom",
+        18 => "select",
+        19 => "send",
+        20 => "sendto",
+        21 => "setsockopt",
+        22 => "shutdown",
+        23 => "socket",
+        51 => "gethostbyaddr",
+        52 => "gethostbyname",
+        53 => "getprotobyname",
+        54 => "getprotobynumber",
+        55 => "getservbyname",
+        56 => "getservbyport",
+        57 => "gethostname",
+        101 => "WSAAsyncSelect",
+        102 => "WSAAsyncGetHostByAddr",
+        103 => "WSAAsyncGetHostByName",
+        104 => "WSAAsyncGetProtoByNumber",
+        105 => "WSAAsyncGetProtoByName",
+        106 => "WSAAsyncGetServByPort",
+        107 => "WSAAsyncGetServByName",
+        108 => "WSACancelAsyncRequest",
+        109 => "WSASetBlockingHook",
+        110 => "WSAUnhookBlockingHook",
+        111 => "WSAGetLastError",
+        112 => "WSASetLastError",
+        113 => "WSACancelBlockingCall",
+        114 => "WSAIsBlocking",
+        115 => "WSAStartup",
+        116 => "WSACleanup",
+        151 => "__WSAFDIsSet",
+        500 => "WEP",
+        _ => return None,
+    };
+    Some(name)
+}
+
+//计算导入表哈希，算法与pefile的get_imphash一致
+pub fn imphash(image: &PeImage) -> Option<String> {
+    let mut items: Vec<String> = Vec::new();
+    for import in &image.imports {
+        let dll = match import.dll.rsplit_once('.') {
+            Some((stem, "dll" | "ocx" | "sys")) => stem,
+            _ => import.dll.as_str(),
+        };
+        let name = match (&import.name, import.ordinal) {
+            (Some(name), _) => name.clone(),
+            (None, Some(ordinal)) => ordinal_name(dll, ordinal)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("ord{}", ordinal)),
+            (None, None) => continue,
+        };
+        items.push(format!("{}.{}", dll, name.to_lowercase()));
+    }
+    if items.is_empty() {
+        return None;
+    }
+    Some(format!("{:x}", md5::compute(items.join(","))))
+}
@@ -0,0 +1,549 @@
+use serde::{Deserialize, Serialize};
+
+// 单次编辑最多写入的字节数
+pub const MAX_EDIT_BYTES: usize = 16 * 1024 * 1024;
+// 保留的撤销步数，超出后丢弃最早的记录
+pub const MAX_HISTORY: usize = 500;
+// 差异中每段最多返回的字节数
+const MAX_DIFF_PREVIEW: usize = 256;
+
+//编辑操作，字节内容使用十六进制字符串，可以包含空格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EditOperation {
+    Overwrite {
+        offset: usize,
+        data: String,
+    },
+    Insert {
+        offset: usize,
+        data: String,
+    },
+    Delete {
+        offset: usize,
+        length: usize,
+    },
+    Fill {
+        offset: usize,
+        length: usize,
+        pattern: String,
+    },
+}
+
+//解析十六进制字符串，忽略空白字符
+pub fn parse_hex_bytes(value: &str) -> Result<Vec<u8>, String> {
+    let digits = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    hex::decode(&digits).map_err(|_| format!("十六进制格式错误: {}", value))
+}
+
+// 文件内容的来源，用于计算与原文件的差异
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Piece {
+    Original { start: usize, len: usize },
+    Added { len: usize },
+}
+
+impl Piece {
+    fn len(&self) -> usize {
+        match self {
+            Piece::Original { len, .. } | Piece::Added { len } => *len,
+        }
+    }
+
+    //在piece内部的at处拆分
+    fn split(&self, at: usize) -> (Piece, Piece) {
+        match *self {
+            Piece::Original { start, len } => (
+                Piece::Original { start, len: at },
+                Piece::Original {
+                    start: start + at,
+                    len: len - at,
+                },
+            ),
+            Piece::Added { len } => (Piece::Added { len: at }, Piece::Added { len: len - at }),
+        }
+    }
+}
+
+//一次编辑等价于在offset处用inserted替换removed
+#[derive(Debug, Clone)]
+struct Splice {
+    offset: usize,
+    removed: Vec<u8>,
+    inserted: Vec<u8>,
+    // 编辑前的来源记录，撤销时直接恢复
+    pieces_before: Vec<Piece>,
+}
+
+impl Splice {
+    fn memory_size(&self) -> usize {
+        self.removed.len() + self.inserted.len()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    // 在编辑后文件中的偏移
+    pub offset: u64,
+    // 在原文件中的偏移
+    pub original_offset: u64,
+    pub removed_length: u64,
+    pub inserted_length: u64,
+    // 查看差异时超过256字节只返回开头部分
+    pub removed: String,
+    pub inserted: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditState {
+    pub file_size: u64,
+    pub undo_count: usize,
+    pub redo_count: usize,
+    // 与原文件是否存在差异
+    pub modified: bool,
+}
+
+//可撤销的文件编辑状态
+#[derive(Debug)]
+pub struct HexDocument {
+    original: Vec<u8>,
+    buf: Vec<u8>,
+    pieces: Vec<Piece>,
+    undo: Vec<Splice>,
+    redo: Vec<Splice>,
+    // 占用内存的上限，超出时丢弃最早的撤销记录
+    memory_limit: usize,
+    // 每次修改内容后递增，用于判断保存期间是否有新的编辑
+    generation: u64,
+}
+
+impl HexDocument {
+    pub fn new(original: Vec<u8>) -> HexDocument {
+        let pieces = if original.is_empty() {
+            Vec::new()
+        } else {
+            vec![Piece::Original {
+                start: 0,
+                len: original.len(),
+            }]
+        };
+        HexDocument {
+            buf: original.clone(),
+            original,
+            pieces,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            memory_limit: usize::MAX,
+            generation: 0,
+        }
+    }
+
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> HexDocument {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    //估算占用的内存，用于限制编辑会话的总内存
+    pub fn memory_size(&self) -> usize {
+        let history = self
+            .undo
+            .iter()
+            .chain(self.redo.iter())
+            .map(Splice::memory_size)
+            .sum::<usize>();
+        self.original.len() + self.buf.len() + history
+    }
+
+    pub fn state(&self) -> EditState {
+        EditState {
+            file_size: self.buf.len() as u64,
+            undo_count: self.undo.len(),
+            redo_count: self.redo.len(),
+            modified: self.buf != self.original,
+        }
+    }
+
+    pub fn apply(&mut self, operation: &EditOperation) -> Result<(), String> {
+        let size = self.buf.len();
+        let check_range = |offset: usize, length: usize| {
+            if offset.checked_add(length).is_none_or(|end| end > size) {
+                Err(format!("编辑范围超出文件大小({}字节)!", size))
+            } else {
+                Ok(())
+            }
+        };
+        let (offset, remove, inserted) = match operation {
+            EditOperation::Overwrite { offset, data } => {
+                let data = parse_hex_bytes(data)?;
+                check_range(*offset, data.len())?;
+                (*offset, data.len(), data)
+            }
+            EditOperation::Insert { offset, data } => {
+                if *offset > size {
+                    return Err(format!("插入位置超出文件大小({}字节)!", size));
+                }
+                (*offset, 0, parse_hex_bytes(data)?)
+            }
+            EditOperation::Delete { offset, length } => {
+                check_range(*offset, *length)?;
+                (*offset, *length, Vec::new())
+            }
+            EditOperation::Fill {
+                offset,
+                length,
+                pattern,
+            } => {
+                let pattern = parse_hex_bytes(pattern)?;
+                if pattern.is_empty() {
+                    return Err("填充内容不能为空!".to_string());
+                }
+                check_range(*offset, *length)?;
+                let data = pattern.iter().copied().cycle().take(*length).collect();
+                (*offset, *length, data)
+            }
+        };
+        if inserted.is_empty() && remove == 0 {
+            return Err("编辑内容不能为空!".to_string());
+        }
+        if inserted.len() > MAX_EDIT_BYTES {
+            return Err(format!("单次编辑不能超过{}字节!", MAX_EDIT_BYTES));
+        }
+        // 编辑后的内容与本次记录，重做记录会被清空
+        let required = self.original.len() + self.buf.len() + 2 * inserted.len();
+        if required > self.memory_limit {
+            return Err("编辑后文件过大，无法继续编辑!".to_string());
+        }
+        let mut history = self.undo.iter().map(Splice::memory_size).sum::<usize>();
+        while required + history > self.memory_limit && !self.undo.is_empty() {
+            history -= self.undo.remove(0).memory_size();
+        }
+        let splice = Splice {
+            offset,
+            removed: self.buf[offset..offset + remove].to_vec(),
+            inserted,
+            pieces_before: self.pieces.clone(),
+        };
+        self.redo_splice(&splice);
+        self.undo.push(splice);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        Ok(())
+    }
+
+    fn redo_splice(&mut self, splice: &Splice) {
+        self.generation += 1;
+        let end = splice.offset + splice.removed.len();
+        self.buf
+            .splice(splice.offset..end, splice.inserted.iter().copied());
+        self.pieces = splice_pieces(
+            &splice.pieces_before,
+            splice.offset,
+            splice.removed.len(),
+            splice.inserted.len(),
+        );
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(splice) = self.undo.pop() else {
+            return false;
+        };
+        self.generation += 1;
+        let end = splice.offset + splice.inserted.len();
+        self.buf
+            .splice(splice.offset..end, splice.removed.iter().copied());
+        self.pieces = splice.pieces_before.clone();
+        self.redo.push(splice);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(splice) = self.redo.pop() else {
+            return false;
+        };
+        self.redo_splice(&splice);
+        self.undo.push(splice);
+        true
+    }
+
+    //与原文件的差异，内容只返回开头部分
+    pub fn diff(&self) -> Vec<DiffHunk> {
+        self.hunks(MAX_DIFF_PREVIEW)
+    }
+
+    //完整的补丁，保存新版本时记录
+    pub fn patch(&self) -> Vec<DiffHunk> {
+        self.hunks(usize::MAX)
+    }
+
+    //按来源记录计算与原文件的差异，相邻的删除与插入合并为一段
+    fn hunks(&self, preview_len: usize) -> Vec<DiffHunk> {
+        let mut hunks = Vec::new();
+        let mut offset = 0;
+        let mut original_offset = 0;
+        // 当前差异段: (编辑后偏移, 原文件偏移, 删除长度, 插入长度)
+        let mut pending: Option<(usize, usize, usize, usize)> = None;
+        let mut flush = |pending: &mut Option<(usize, usize, usize, usize)>| {
+            if let Some((offset, original_offset, removed, inserted)) = pending.take() {
+                if let Some(hunk) =
+                    self.hunk(offset, original_offset, removed, inserted, preview_len)
+                {
+                    hunks.push(hunk);
+                }
+            }
+        };
+        for piece in &self.pieces {
+            match *piece {
+                Piece::Original { start, len } => {
+                    if start > original_offset {
+                        let hunk = pending.get_or_insert((offset, original_offset, 0, 0));
+                        hunk.2 += start - original_offset;
+                    }
+                    flush(&mut pending);
+                    original_offset = start + len;
+                }
+                Piece::Added { len } => {
+                    let hunk = pending.get_or_insert((offset, original_offset, 0, 0));
+                    hunk.3 += len;
+                }
+            }
+            offset += piece.len();
+        }
+        if original_offset < self.original.len() {
+            let hunk = pending.get_or_insert((offset, original_offset, 0, 0));
+            hunk.2 += self.original.len() - original_offset;
+        }
+        flush(&mut pending);
+        hunks
+    }
+
+    //去掉首尾相同的字节，内容完全相同时返回空
+    fn hunk(
+        &self,
+        offset: usize,
+        original_offset: usize,
+        removed: usize,
+        inserted: usize,
+        preview_len: usize,
+    ) -> Option<DiffHunk> {
+        let mut old = &self.original[original_offset..original_offset + removed];
+        let mut new = &self.buf[offset..offset + inserted];
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        old = &old[prefix..];
+        new = &new[prefix..];
+        let suffix = old
+            .iter()
+            .rev()
+            .zip(new.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        old = &old[..old.len() - suffix];
+        new = &new[..new.len() - suffix];
+        if old.is_empty() && new.is_empty() {
+            return None;
+        }
+        let preview = |bytes: &[u8]| hex::encode_upper(&bytes[..bytes.len().min(preview_len)]);
+        Some(DiffHunk {
+            offset: (offset + prefix) as u64,
+            original_offset: (original_offset + prefix) as u64,
+            removed_length: old.len() as u64,
+            inserted_length: new.len() as u64,
+            removed: preview(old),
+            inserted: preview(new),
+        })
+    }
+}
+
+//在来源记录上执行替换，返回新的来源记录
+fn splice_pieces(pieces: &[Piece], offset: usize, remove: usize, insert: usize) -> Vec<Piece> {
+    let end = offset + remove;
+    let mut result = Vec::with_capacity(pieces.len() + 2);
+    let mut position = 0;
+    let mut inserted = insert == 0;
+    for piece in pieces {
+        let piece_start = position;
+        let piece_end = position + piece.len();
+        position = piece_end;
+        // 保留编辑范围之前的部分
+        if piece_start < offset {
+            let keep = piece.len().min(offset - piece_start);
+            result.push(piece.split(keep).0);
+        }
+        if !inserted && piece_end >= offset {
+            result.push(Piece::Added { len: insert });
+            inserted = true;
+        }
+        // 保留编辑范围之后的部分
+        if piece_end > end {
+            let skip = end.saturating_sub(piece_start);
+            result.push(piece.split(skip).1);
+        }
+    }
+    if !inserted {
+        result.push(Piece::Added { len: insert });
+    }
+    result.retain(|piece| piece.len() > 0);
+    // 合并相邻的同类记录
+    let mut merged: Vec<Piece> = Vec::with_capacity(result.len());
+    for piece in result {
+        match (merged.last_mut(), piece) {
+            (Some(Piece::Added { len }), Piece::Added { len: next }) => *len += next,
+            (
+                Some(Piece::Original { start, len }),
+                Piece::Original {
+                    start: next,
+                    len: l,
+                },
+            ) if *start + *len == next => *len += l,
+            _ => merged.push(piece),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> HexDocument {
+        HexDocument::new((0u8..16).collect())
+    }
+
+    fn insert(offset: usize, data: &str) -> EditOperation {
+        EditOperation::Insert {
+            offset,
+            data: data.to_string(),
+        }
+    }
+
+    fn delete(offset: usize, length: usize) -> EditOperation {
+        EditOperation::Delete { offset, length }
+    }
+
+    #[test]
+    fn insert_at_piece_boundaries() {
+        let mut document = document();
+        document.apply(&insert(0, "AA")).unwrap();
+        document.apply(&insert(17, "BB")).unwrap();
+        // 插入到已插入内容与原内容的交界处
+        document.apply(&insert(1, "CC")).unwrap();
+        let mut expected = vec![0xAA, 0xCC];
+        expected.extend(0u8..16);
+        expected.push(0xBB);
+        assert_eq!(document.data(), expected.as_slice());
+        assert_eq!(document.state().file_size, 19);
+    }
+
+    #[test]
+    fn delete_across_piece_boundaries() {
+        let mut document = document();
+        document.apply(&insert(4, "AABB")).unwrap();
+        // 删除范围跨越原内容与插入内容
+        document.apply(&delete(3, 2)).unwrap();
+        let mut expected = vec![0, 1, 2, 0xBB];
+        expected.extend(4u8..16);
+        assert_eq!(document.data(), expected.as_slice());
+        document.apply(&delete(0, document.data().len())).unwrap();
+        assert!(document.data().is_empty());
+    }
+
+    #[test]
+    fn undo_redo_round_trip() {
+        let mut document = document();
+        document.apply(&insert(8, "AA BB")).unwrap();
+        document
+            .apply(&EditOperation::Overwrite {
+                offset: 0,
+                data: "FF".to_string(),
+            })
+            .unwrap();
+        document.apply(&delete(12, 2)).unwrap();
+        let edited = document.data().to_vec();
+        assert!(document.state().modified);
+        while document.undo() {}
+        assert_eq!(document.data(), (0u8..16).collect::<Vec<_>>().as_slice());
+        assert!(!document.state().modified);
+        assert!(document.diff().is_empty());
+        while document.redo() {}
+        assert_eq!(document.data(), edited.as_slice());
+        assert_eq!(document.state().undo_count, 3);
+        assert_eq!(document.state().redo_count, 0);
+    }
+
+    #[test]
+    fn generation_changes_on_every_edit() {
+        let mut document = document();
+        let generation = document.generation();
+        document.apply(&insert(0, "AA")).unwrap();
+        document.undo();
+        // 内容相同但经过了编辑
+        assert_eq!(document.data(), (0u8..16).collect::<Vec<_>>().as_slice());
+        assert_eq!(document.generation(), generation + 2);
+        assert!(document.apply(&delete(0, 0)).is_err());
+        assert_eq!(document.generation(), generation + 2);
+    }
+
+    #[test]
+    fn overwrite_with_same_bytes_is_not_a_hunk() {
+        let mut document = document();
+        document
+            .apply(&EditOperation::Overwrite {
+                offset: 2,
+                data: "02 03".to_string(),
+            })
+            .unwrap();
+        assert!(document.diff().is_empty());
+    }
+
+    #[test]
+    fn diff_hunk_offsets() {
+        let mut document = document();
+        document.apply(&insert(2, "AABB")).unwrap();
+        document.apply(&delete(12, 2)).unwrap();
+        let hunks = document.diff();
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(
+            (
+                hunks[0].offset,
+                hunks[0].original_offset,
+                hunks[0].removed_length,
+                hunks[0].inserted_length
+            ),
+            (2, 2, 0, 2)
+        );
+        assert_eq!(hunks[0].inserted, "AABB");
+        // 删除位置在插入内容之后，编辑后偏移比原偏移多2
+        assert_eq!(
+            (
+                hunks[1].offset,
+                hunks[1].original_offset,
+                hunks[1].removed_length,
+                hunks[1].inserted_length
+            ),
+            (12, 10, 2, 0)
+        );
+        assert_eq!(hunks[1].removed, "0A0B");
+    }
+
+    #[test]
+    fn memory_limit_trims_history() {
+        let mut document = HexDocument::new(vec![0; 16]).with_memory_limit(39);
+        document.apply(&insert(0, "AABBCC")).unwrap();
+        document.apply(&insert(0, "DD")).unwrap();
+        assert_eq!(document.state().undo_count, 1);
+        assert!(document.memory_size() <= 39);
+        assert!(document.apply(&insert(0, "00".repeat(8).as_str())).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_overlay::Annotation;
+
+pub const DEFAULT_ROW_WIDTH: usize = 16;
+pub const MAX_ROW_WIDTH: usize = 64;
+// 单次请求最多返回的行数
+pub const MAX_ROWS: usize = 4096;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HexRow {
+    pub offset: u64,
+    pub address: String,
+    // 以空格分隔的十六进制字节
+    pub hex: String,
+    // 不可打印字符显示为.
+    pub ascii: String,
+    // 该行字节所属的PE结构
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+pub fn printable(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+//生成[start, end)范围内的行，每行width个字节，最后一行可能不满
+pub fn hex_rows(buf: &[u8], start: usize, end: usize, width: usize) -> Vec<HexRow> {
+    let end = end.min(buf.len());
+    if start >= end || width == 0 {
+        return Vec::new();
+    }
+    buf[start..end]
+        .chunks(width)
+        .enumerate()
+        .map(|(index, bytes)| {
+            let offset = start + index * width;
+            HexRow {
+                offset: offset as u64,
+                address: format!("{:08X}", offset),
+                hex: bytes
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                ascii: bytes.iter().map(|byte| printable(*byte)).collect(),
+                annotations: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+//为每行附加与该行字节有交集的标注
+pub fn annotate_rows(rows: &mut [HexRow], annotations: &[Annotation], width: usize) {
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        return;
+    };
+    let (start, end) = (first.offset, last.offset + width as u64);
+    let visible = annotations
+        .iter()
+        .filter(|annotation| {
+            annotation.offset < end && annotation.offset + annotation.length > start
+        })
+        .collect::<Vec<_>>();
+    for row in rows {
+        let row_end = row.offset + width as u64;
+        row.annotations = visible
+            .iter()
+            .filter(|annotation| {
+                annotation.offset < row_end && annotation.offset + annotation.length > row.offset
+            })
+            .map(|annotation| (*annotation).clone())
+            .collect();
+    }
+}
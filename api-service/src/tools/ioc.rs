@@ -0,0 +1,343 @@
+use std::collections::HashSet;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::tools::strings::{self, StringEncoding};
+
+const IOC_MIN_STRING_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IocType {
+    Ipv4,
+    Ipv6,
+    Url,
+    Domain,
+    Email,
+    RegistryKey,
+    FilePath,
+    NamedPipe,
+    Mutex,
+    BitcoinAddress,
+    MoneroAddress,
+}
+
+impl IocType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IocType::Ipv4 => "ipv4",
+            IocType::Ipv6 => "ipv6",
+            IocType::Url => "url",
+            IocType::Domain => "domain",
+            IocType::Email => "email",
+            IocType::RegistryKey => "registry_key",
+            IocType::FilePath => "file_path",
+            IocType::NamedPipe => "named_pipe",
+            IocType::Mutex => "mutex",
+            IocType::BitcoinAddress => "bitcoin_address",
+            IocType::MoneroAddress => "monero_address",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ioc {
+    pub ioc_type: IocType,
+    pub value: String,
+    pub offset: usize,
+    pub encoding: StringEncoding,
+}
+
+// 内置的顶级域名列表，用于过滤形如域名的普通字符串
+static TLDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    include_str!("tlds.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+});
+// 同时也是常见文件扩展名的顶级域名，只有两级时不认为是域名
+const AMBIGUOUS_TLDS: [&str; 12] = [
+    "py", "sh", "pl", "so", "rs", "md", "cc", "ps", "cs", "do", "in", "is",
+];
+
+static URL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(?:https?|ftps?|wss?)://[a-z0-9\-._~:/?#\[\]@!$&'()*+,;=%]+").unwrap()
+});
+static EMAIL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b[a-z0-9._%+\-]+@((?:[a-z0-9\-]+\.)+[a-z]{2,24})\b").unwrap());
+static IPV4_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+    )
+    .unwrap()
+});
+static IPV6_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)(?:[0-9a-f]{0,4}:){2,7}[0-9a-f]{0,4}").unwrap());
+static DOMAIN_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(?:[a-z0-9](?:[a-z0-9\-]{0,61}[a-z0-9])?\.)+([a-z]{2,24})\b").unwrap()
+});
+static REGISTRY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)\b(?:HKEY_LOCAL_MACHINE|HKEY_CURRENT_USER|HKEY_CLASSES_ROOT|HKEY_USERS|HKEY_CURRENT_CONFIG|HKLM|HKCU|HKCR|HKU|HKCC|SOFTWARE\\(?:Microsoft|Classes|Policies|Wow6432Node)|SYSTEM\\(?:CurrentControlSet|ControlSet\d{3}))\\[^\x00-\x1f"<>|]+"#,
+    )
+    .unwrap()
+});
+static NAMED_PIPE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\\\\[^\\\s"]+\\pipe\\[^\x00-\x20"<>|]+"#).unwrap());
+static FILE_PATH_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(?:\b[a-z]:\\|%[a-z_]+%\\|\\\\[a-z0-9.\-_$]+\\)(?:[^\\/:*?"<>|\x00-\x1f]+\\)*[^\\/:*?"<>|\x00-\x1f]*"#,
+    )
+    .unwrap()
+});
+static MUTEX_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(?:Global|Local|Session\\\d+)\\[a-z0-9_\-{}.#@$]{3,}").unwrap()
+});
+static BITCOIN_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:[13][a-km-zA-HJ-NP-Z1-9]{25,34}|bc1[ac-hj-np-z02-9]{11,71})\b").unwrap()
+});
+static MONERO_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b[48][0-9AB][1-9A-HJ-NP-Za-km-z]{93}(?:[1-9A-HJ-NP-Za-km-z]{11})?\b").unwrap()
+});
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+pub fn is_valid_tld(tld: &str) -> bool {
+    TLDS.contains(tld.to_lowercase().as_str())
+}
+
+fn is_plausible_domain(domain: &str) -> bool {
+    let labels = domain.split('.').collect::<Vec<_>>();
+    let Some(tld) = labels.last().map(|tld| tld.to_lowercase()) else {
+        return false;
+    };
+    if !is_valid_tld(&tld) {
+        return false;
+    }
+    if labels.len() == 2 && AMBIGUOUS_TLDS.contains(&tld.as_str()) {
+        return false;
+    }
+    // 纯数字的标签通常是版本号
+    !labels[..labels.len() - 1]
+        .iter()
+        .all(|label| label.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_plausible_ipv4(ip: &str) -> bool {
+    let octets = ip
+        .split('.')
+        .filter_map(|octet| octet.parse::<u8>().ok())
+        .collect::<Vec<_>>();
+    if octets.len() != 4 || octets[0] == 0 || octets == [255, 255, 255, 255] {
+        return false;
+    }
+    // x.0.0.0 形式基本都是版本号
+    octets[1..] != [0, 0, 0]
+}
+
+fn base58_decode(value: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in value.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|b| *b == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = (carry & 0xFF) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, (carry & 0xFF) as u8);
+            carry >>= 8;
+        }
+    }
+    let leading_zeros = value.bytes().take_while(|c| *c == b'1').count();
+    let mut result = vec![0u8; leading_zeros];
+    result.extend(bytes);
+    Some(result)
+}
+
+fn is_valid_base58check(value: &str) -> bool {
+    let Some(decoded) = base58_decode(value) else {
+        return false;
+    };
+    if decoded.len() != 25 {
+        return false;
+    }
+    let checksum = Sha256::digest(Sha256::digest(&decoded[..21]));
+    checksum[..4] == decoded[21..]
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ *value as u32;
+        for (index, generator) in GENERATOR.iter().enumerate() {
+            if (top >> index) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn is_valid_bech32(value: &str) -> bool {
+    let Some((hrp, data)) = value.rsplit_once('1') else {
+        return false;
+    };
+    let mut values = hrp.bytes().map(|c| c >> 5).collect::<Vec<_>>();
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 31));
+    for c in data.bytes() {
+        match BECH32_CHARSET.iter().position(|b| *b == c) {
+            Some(position) => values.push(position as u8),
+            None => return false,
+        }
+    }
+    // bech32 与 bech32m 两种校验常量
+    matches!(bech32_polymod(&values), 1 | 0x2bc830a3)
+}
+
+fn is_valid_bitcoin_address(value: &str) -> bool {
+    if value.starts_with("bc1") {
+        is_valid_bech32(value)
+    } else {
+        is_valid_base58check(value)
+    }
+}
+
+//从单个字符串中识别IOC，返回类型、值与在字符串内的字符位置
+pub fn match_iocs(value: &str) -> Vec<(IocType, String, usize)> {
+    let mut result = Vec::new();
+    let mut covered: Vec<(usize, usize)> = Vec::new();
+    for found in URL_REGEX.find_iter(value) {
+        let url = found.as_str().trim_end_matches(['.', ',', ')', '\'', ';']);
+        result.push((IocType::Url, url.to_string(), found.start()));
+        covered.push((found.start(), found.end()));
+    }
+    for found in NAMED_PIPE_REGEX.find_iter(value) {
+        result.push((
+            IocType::NamedPipe,
+            found.as_str().to_string(),
+            found.start(),
+        ));
+        covered.push((found.start(), found.end()));
+    }
+    for found in REGISTRY_REGEX.find_iter(value) {
+        let key = found.as_str().trim_end();
+        result.push((IocType::RegistryKey, key.to_string(), found.start()));
+        covered.push((found.start(), found.end()));
+    }
+    for found in MUTEX_REGEX.find_iter(value) {
+        result.push((IocType::Mutex, found.as_str().to_string(), found.start()));
+    }
+    let is_covered = |start: usize| {
+        covered
+            .iter()
+            .any(|(from, to)| start >= *from && start < *to)
+    };
+    for found in FILE_PATH_REGEX.find_iter(value) {
+        let path = found.as_str().trim_end();
+        if !is_covered(found.start()) && path.len() > 3 {
+            result.push((IocType::FilePath, path.to_string(), found.start()));
+        }
+    }
+    for captures in EMAIL_REGEX.captures_iter(value) {
+        let found = captures.get(0).unwrap();
+        if captures
+            .get(1)
+            .is_some_and(|domain| is_plausible_domain(domain.as_str()))
+        {
+            result.push((IocType::Email, found.as_str().to_string(), found.start()));
+        }
+    }
+    for found in IPV4_REGEX.find_iter(value) {
+        // 排除更长的点分序列（如版本号 1.2.3.4.5）
+        let longer_sequence = value[..found.start()].ends_with('.')
+            || value[found.end()..]
+                .strip_prefix('.')
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+        if !longer_sequence && is_plausible_ipv4(found.as_str()) {
+            result.push((IocType::Ipv4, found.as_str().to_string(), found.start()));
+        }
+    }
+    for found in IPV6_REGEX.find_iter(value) {
+        let candidate = found.as_str();
+        let is_boundary =
+            |c: Option<char>| c.is_none_or(|c| !c.is_ascii_alphanumeric() && c != ':');
+        // C++ 符号（如 std::cab）同样能被解析为IPv6地址，要求前后都是边界
+        if candidate.len() < 3
+            || !is_boundary(value[..found.start()].chars().last())
+            || !is_boundary(value[found.end()..].chars().next())
+        {
+            continue;
+        }
+        if let Ok(ip) = Ipv6Addr::from_str(candidate) {
+            if !ip.is_unspecified() {
+                result.push((IocType::Ipv6, candidate.to_string(), found.start()));
+            }
+        }
+    }
+    for captures in DOMAIN_REGEX.captures_iter(value) {
+        let found = captures.get(0).unwrap();
+        let before = value[..found.start()].chars().last();
+        // 邮箱地址中的域名已经单独记录
+        if before == Some('@') || !is_plausible_domain(found.as_str()) {
+            continue;
+        }
+        result.push((
+            IocType::Domain,
+            found.as_str().to_lowercase(),
+            found.start(),
+        ));
+    }
+    for found in BITCOIN_REGEX.find_iter(value) {
+        if is_valid_bitcoin_address(found.as_str()) {
+            result.push((
+                IocType::BitcoinAddress,
+                found.as_str().to_string(),
+                found.start(),
+            ));
+        }
+    }
+    // 门罗币地址使用分块base58编码，这里只按前缀和长度识别
+    for found in MONERO_REGEX.find_iter(value) {
+        result.push((
+            IocType::MoneroAddress,
+            found.as_str().to_string(),
+            found.start(),
+        ));
+    }
+    result
+}
+
+//扫描文件内容中的ASCII与UTF-16字符串，同一类型同一值只保留第一次出现的位置
+pub fn extract_iocs(buf: &[u8]) -> Vec<Ioc> {
+    let mut seen: HashSet<(IocType, String)> = HashSet::new();
+    let mut result: Vec<Ioc> = Vec::new();
+    for item in strings::extract_strings(buf, None, IOC_MIN_STRING_LEN) {
+        let char_width = match item.encoding {
+            StringEncoding::Ascii => 1,
+            StringEncoding::Utf16le => 2,
+        };
+        for (ioc_type, value, position) in match_iocs(&item.value) {
+            if !seen.insert((ioc_type, value.clone())) {
+                continue;
+            }
+            result.push(Ioc {
+                ioc_type,
+                value,
+                offset: item.offset + position * char_width,
+                encoding: item.encoding,
+            });
+        }
+    }
+    result
+}
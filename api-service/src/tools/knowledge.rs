@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+
+use aho_corasick::AhoCorasick;
+use regex::{RegexSet, RegexSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::PeImage;
+
+// 精确匹配时自动追加的函数名后缀
+const NAME_VARIANT_SUFFIXES: [&str; 5] = ["A", "W", "Ex", "ExA", "ExW"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 5] = [
+        Severity::Info,
+        Severity::Low,
+        Severity::Medium,
+        Severity::High,
+        Severity::Critical,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Severity::Info => "信息",
+            Severity::Low => "低危",
+            Severity::Medium => "中危",
+            Severity::High => "高危",
+            Severity::Critical => "严重",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Severity> {
+        Severity::ALL
+            .into_iter()
+            .find(|severity| severity.as_str() == value)
+    }
+}
+
+//一条命中的知识库条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeHit {
+    pub id: String,
+    pub func_name: String,
+    pub func_desc: Option<String>,
+    // 非敏感条目只作为提示信息，不参与评分
+    pub severity: Severity,
+    pub categories: Vec<String>,
+    pub imports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    Exact,
+    Prefix,
+    Regex,
+    // 精确匹配，同时匹配 A/W/Ex/ExA/ExW 后缀
+    ExactVariants,
+}
+
+impl MatchMode {
+    pub const ALL: [MatchMode; 4] = [
+        MatchMode::Exact,
+        MatchMode::Prefix,
+        MatchMode::Regex,
+        MatchMode::ExactVariants,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchMode::Exact => "exact",
+            MatchMode::Prefix => "prefix",
+            MatchMode::Regex => "regex",
+            MatchMode::ExactVariants => "exact_variants",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            MatchMode::Exact => "精确匹配",
+            MatchMode::Prefix => "前缀匹配",
+            MatchMode::Regex => "正则匹配",
+            MatchMode::ExactVariants => "精确匹配（含A/W/Ex变体）",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<MatchMode> {
+        MatchMode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == value)
+    }
+}
+
+//一条待编译的匹配规则
+#[derive(Debug, Clone)]
+pub struct KnowledgeRule {
+    pub pattern: String,
+    pub mode: MatchMode,
+    // 限定导入的DLL，为空时不限
+    pub dll: Option<String>,
+}
+
+//统一DLL名称，忽略大小写与扩展名
+pub fn normalize_dll(name: &str) -> String {
+    let name = name.trim().to_ascii_lowercase();
+    match name.strip_suffix(".dll") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+//校验匹配规则，返回错误描述
+pub fn validate_rule(rule: &KnowledgeRule) -> Result<(), String> {
+    if rule.mode == MatchMode::Regex {
+        regex::Regex::new(&rule.pattern).map_err(|err| format!("正则表达式错误: {}", err))?;
+    } else if rule.pattern.chars().any(|c| c.is_whitespace()) {
+        return Err("函数名称不能包含空白字符!".to_string());
+    }
+    Ok(())
+}
+
+//预编译的知识库匹配器，返回的下标对应构建时规则的顺序
+pub struct KnowledgeMatcher {
+    // 小写函数名 -> 规则下标
+    exact: HashMap<String, Vec<usize>>,
+    prefix: Option<AhoCorasick>,
+    prefix_rules: Vec<usize>,
+    regex: Option<RegexSet>,
+    regex_rules: Vec<usize>,
+    dlls: Vec<Option<String>>,
+}
+
+impl KnowledgeMatcher {
+    //编译全部规则，无法编译的规则会被跳过并记录日志
+    pub fn build(rules: &[KnowledgeRule]) -> KnowledgeMatcher {
+        let mut exact: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut prefixes = Vec::new();
+        let mut prefix_rules = Vec::new();
+        let mut regexes = Vec::new();
+        let mut regex_rules = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            match rule.mode {
+                MatchMode::Exact => {
+                    exact
+                        .entry(rule.pattern.to_ascii_lowercase())
+                        .or_default()
+                        .push(index);
+                }
+                MatchMode::ExactVariants => {
+                    let name = rule.pattern.to_ascii_lowercase();
+                    for suffix in std::iter::once("").chain(NAME_VARIANT_SUFFIXES) {
+                        let indexes = exact
+                            .entry(format!("{}{}", name, suffix.to_ascii_lowercase()))
+                            .or_default();
+                        if !indexes.contains(&index) {
+                            indexes.push(index);
+                        }
+                    }
+                }
+                MatchMode::Prefix => {
+                    prefixes.push(rule.pattern.clone());
+                    prefix_rules.push(index);
+                }
+                MatchMode::Regex => {
+                    if regex::Regex::new(&rule.pattern).is_ok() {
+                        regexes.push(rule.pattern.clone());
+                        regex_rules.push(index);
+                    } else {
+                        log::error!("invalid knowledge regex: {}", rule.pattern);
+                    }
+                }
+            }
+        }
+        let prefix = if prefixes.is_empty() {
+            None
+        } else {
+            AhoCorasick::builder()
+                .ascii_case_insensitive(true)
+                .build(&prefixes)
+                .map_err(|err| log::error!("build knowledge prefix matcher error: {}", err))
+                .ok()
+        };
+        let regex = if regexes.is_empty() {
+            None
+        } else {
+            RegexSetBuilder::new(&regexes)
+                .case_insensitive(true)
+                .build()
+                .map_err(|err| log::error!("build knowledge regex set error: {}", err))
+                .ok()
+        };
+        KnowledgeMatcher {
+            exact,
+            prefix,
+            prefix_rules,
+            regex,
+            regex_rules,
+            dlls: rules
+                .iter()
+                .map(|rule| rule.dll.as_deref().map(normalize_dll))
+                .collect(),
+        }
+    }
+
+    //匹配一个导入函数，返回命中的规则下标
+    pub fn match_import(&self, dll: Option<&str>, name: &str) -> Vec<usize> {
+        let mut result = Vec::new();
+        if let Some(indexes) = self.exact.get(&name.to_ascii_lowercase()) {
+            result.extend(indexes.iter().copied());
+        }
+        if let Some(prefix) = &self.prefix {
+            // 重叠匹配不支持锚定搜索，只保留从开头匹配的结果
+            for found in prefix
+                .find_overlapping_iter(name)
+                .filter(|found| found.start() == 0)
+            {
+                result.push(self.prefix_rules[found.pattern().as_usize()]);
+            }
+        }
+        if let Some(regex) = &self.regex {
+            for found in regex.matches(name).iter() {
+                result.push(self.regex_rules[found]);
+            }
+        }
+        let dll = dll.map(normalize_dll);
+        result.retain(|index| match &self.dlls[*index] {
+            None => true,
+            Some(scope) => dll.as_ref() == Some(scope),
+        });
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+}
+
+//按名称导入的函数，(DLL名称, 函数名)，按序号导入的函数没有名称可供匹配
+pub fn named_imports(image: &PeImage) -> Vec<(Option<String>, String)> {
+    image
+        .imports
+        .iter()
+        .filter_map(|import| Some((Some(import.dll.clone()), import.name.clone()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, mode: MatchMode, dll: Option<&str>) -> KnowledgeRule {
+        KnowledgeRule {
+            pattern: pattern.to_string(),
+            mode,
+            dll: dll.map(|dll| dll.to_string()),
+        }
+    }
+
+    #[test]
+    fn exact_variants_match_suffixes() {
+        let matcher =
+            KnowledgeMatcher::build(&[rule("CreateProcess", MatchMode::ExactVariants, None)]);
+        for name in [
+            "CreateProcess",
+            "CreateProcessA",
+            "CreateProcessW",
+            "createprocessex",
+            "CreateProcessExA",
+            "CreateProcessExW",
+        ] {
+            assert_eq!(matcher.match_import(None, name), vec![0], "{}", name);
+        }
+        assert!(matcher
+            .match_import(None, "CreateProcessAsUserW")
+            .is_empty());
+        assert!(matcher.match_import(None, "CreateProces").is_empty());
+    }
+
+    #[test]
+    fn exact_does_not_match_variants() {
+        let matcher = KnowledgeMatcher::build(&[rule("connect", MatchMode::Exact, None)]);
+        assert_eq!(matcher.match_import(None, "CONNECT"), vec![0]);
+        assert!(matcher.match_import(None, "connectA").is_empty());
+    }
+
+    #[test]
+    fn prefix_is_anchored_at_start() {
+        let matcher = KnowledgeMatcher::build(&[
+            rule("Crypt", MatchMode::Prefix, None),
+            rule("CryptAcquire", MatchMode::Prefix, None),
+        ]);
+        assert_eq!(
+            matcher.match_import(None, "CryptAcquireContextW"),
+            vec![0, 1]
+        );
+        assert_eq!(matcher.match_import(None, "cryptencrypt"), vec![0]);
+        assert!(matcher.match_import(None, "BCryptEncrypt").is_empty());
+    }
+
+    #[test]
+    fn regex_is_case_insensitive() {
+        let matcher = KnowledgeMatcher::build(&[
+            rule("^nt(query|set)", MatchMode::Regex, None),
+            rule("(", MatchMode::Regex, None),
+        ]);
+        assert_eq!(
+            matcher.match_import(None, "NtQueryInformationProcess"),
+            vec![0]
+        );
+        assert!(matcher
+            .match_import(None, "ZwQuerySystemInformation")
+            .is_empty());
+    }
+
+    #[test]
+    fn dll_scope_limits_matches() {
+        let matcher = KnowledgeMatcher::build(&[
+            rule("connect", MatchMode::Exact, Some("WS2_32.dll")),
+            rule("connect", MatchMode::Exact, None),
+        ]);
+        assert_eq!(
+            matcher.match_import(Some("ws2_32.dll"), "connect"),
+            vec![0, 1]
+        );
+        assert_eq!(matcher.match_import(Some("WS2_32"), "connect"), vec![0, 1]);
+        assert_eq!(
+            matcher.match_import(Some("wsock32.dll"), "connect"),
+            vec![1]
+        );
+        assert_eq!(matcher.match_import(None, "connect"), vec![1]);
+    }
+}
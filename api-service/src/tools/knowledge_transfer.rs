@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tools::attack;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferFormat {
+    Json,
+    Csv,
+    Yaml,
+}
+
+impl TransferFormat {
+    pub fn parse(value: &str) -> Option<TransferFormat> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Some(TransferFormat::Json),
+            "csv" => Some(TransferFormat::Csv),
+            "yaml" | "yml" => Some(TransferFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    //根据文件扩展名识别格式
+    pub fn from_file_name(file_name: &str) -> Option<TransferFormat> {
+        file_name
+            .rsplit_once('.')
+            .and_then(|(_, extension)| TransferFormat::parse(extension))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TransferFormat::Json => "json",
+            TransferFormat::Csv => "csv",
+            TransferFormat::Yaml => "yaml",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TransferFormat::Json => "application/json",
+            TransferFormat::Csv => "text/csv; charset=utf-8",
+            TransferFormat::Yaml => "application/yaml",
+        }
+    }
+}
+
+//导入导出使用的知识库条目，不包含ID与时间，按函数名称对应已有数据
+//未填写的字段为空，导入时已有条目保留原值，新增条目使用默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeRecord {
+    pub name: String,
+    #[serde(default)]
+    pub desc: Option<String>,
+    #[serde(default)]
+    pub is_sensitive: Option<bool>,
+    #[serde(default)]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub categories: Option<Vec<String>>,
+    #[serde(default)]
+    pub technique_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub tactics: Option<Vec<String>>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub match_mode: Option<String>,
+    #[serde(default)]
+    pub dll_name: Option<String>,
+}
+
+//CSV不支持数组，多个值用逗号拼接后放在同一列，缺少的列与空白单元格都视为未填写
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRecord {
+    name: String,
+    desc: Option<String>,
+    is_sensitive: Option<bool>,
+    severity: Option<String>,
+    categories: Option<String>,
+    technique_ids: Option<String>,
+    tactics: Option<String>,
+    enabled: Option<bool>,
+    match_mode: Option<String>,
+    dll_name: Option<String>,
+}
+
+impl From<CsvRecord> for KnowledgeRecord {
+    fn from(record: CsvRecord) -> Self {
+        KnowledgeRecord {
+            name: record.name,
+            desc: record.desc,
+            is_sensitive: record.is_sensitive,
+            severity: record.severity,
+            categories: record
+                .categories
+                .map(|value| attack::split_tags(Some(&value))),
+            technique_ids: record
+                .technique_ids
+                .map(|value| attack::split_tags(Some(&value))),
+            tactics: record.tactics.map(|value| attack::split_tags(Some(&value))),
+            enabled: record.enabled,
+            match_mode: record.match_mode,
+            dll_name: record.dll_name,
+        }
+    }
+}
+
+impl From<&KnowledgeRecord> for CsvRecord {
+    fn from(record: &KnowledgeRecord) -> Self {
+        let join = |values: &Option<Vec<String>>| {
+            values
+                .as_ref()
+                .filter(|values| !values.is_empty())
+                .map(|values| values.join(","))
+        };
+        CsvRecord {
+            name: record.name.clone(),
+            desc: record.desc.clone(),
+            is_sensitive: record.is_sensitive,
+            severity: record.severity.clone(),
+            categories: join(&record.categories),
+            technique_ids: join(&record.technique_ids),
+            tactics: join(&record.tactics),
+            enabled: record.enabled,
+            match_mode: record.match_mode.clone(),
+            dll_name: record.dll_name.clone(),
+        }
+    }
+}
+
+//解析导入文件，返回错误描述
+pub fn parse_records(format: TransferFormat, buf: &[u8]) -> Result<Vec<KnowledgeRecord>, String> {
+    // 去掉Excel等工具写入的BOM
+    let buf = buf.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(buf);
+    match format {
+        TransferFormat::Json => {
+            serde_json::from_slice(buf).map_err(|err| format!("JSON格式错误: {}", err))
+        }
+        TransferFormat::Yaml => {
+            serde_yaml::from_slice(buf).map_err(|err| format!("YAML格式错误: {}", err))
+        }
+        TransferFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(buf);
+            reader
+                .deserialize::<CsvRecord>()
+                .map(|record| {
+                    record
+                        .map(KnowledgeRecord::from)
+                        .map_err(|err| format!("CSV格式错误: {}", err))
+                })
+                .collect()
+        }
+    }
+}
+
+//按格式生成导出文件
+pub fn write_records(
+    format: TransferFormat,
+    records: &[KnowledgeRecord],
+) -> anyhow::Result<Vec<u8>> {
+    match format {
+        TransferFormat::Json => Ok(serde_json::to_vec_pretty(records)?),
+        TransferFormat::Yaml => Ok(serde_yaml::to_string(records)?.into_bytes()),
+        TransferFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for record in records {
+                writer.serialize(CsvRecord::from(record))?;
+            }
+            Ok(writer.into_inner()?)
+        }
+    }
+}
@@ -2,10 +2,32 @@ use axum::{routing::get, Router};
 
 use crate::app::state::AppState;
 
+pub mod anomaly;
+pub mod attack;
+pub mod byte_search;
+pub mod capability;
+pub mod cfg;
+pub mod crypto;
+pub mod data_inspector;
+pub mod deobfuscate;
+pub mod disasm;
+pub mod file_hash;
 pub mod hex;
+pub mod hex_edit;
+pub mod hex_view;
+pub mod ioc;
+pub mod knowledge;
+pub mod knowledge_transfer;
+pub mod packer;
 pub mod param_convert;
+pub mod pe_image;
+pub mod pe_overlay;
 pub mod pe_read;
 pub mod pe_tools;
+pub mod risk;
+pub mod signature;
+pub mod strings;
+pub mod yara;
 
 pub fn routers(state: AppState) -> Router {
     Router::new().nest(
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::PeImage;
+use crate::tools::pe_tools;
+
+// 可执行节熵超过该值时视为被压缩或加密
+const PACKED_ENTROPY: f64 = 7.2;
+const HEADER_SCAN_SIZE: usize = 0x400;
+
+// (节名称, 壳名称)，节名称忽略大小写
+const SECTION_SIGNATURES: [(&str, &str); 24] = [
+    ("upx0", "UPX"),
+    ("upx1", "UPX"),
+    ("upx2", "UPX"),
+    (".upx", "UPX"),
+    (".aspack", "ASPack"),
+    (".adata", "ASPack"),
+    (".mpress1", "MPRESS"),
+    (".mpress2", "MPRESS"),
+    (".petite", "Petite"),
+    (".nsp0", "NsPack"),
+    (".nsp1", "NsPack"),
+    (".themida", "Themida"),
+    (".winlice", "WinLicense"),
+    (".vmp0", "VMProtect"),
+    (".vmp1", "VMProtect"),
+    (".enigma1", "Enigma Protector"),
+    (".enigma2", "Enigma Protector"),
+    ("pec2", "PECompact"),
+    ("pecompact2", "PECompact"),
+    (".rlpack", "RLPack"),
+    (".packed", "RLPack"),
+    ("mew", "MEW"),
+    (".yp", "Y0da Protector"),
+    (".spack", "Simple Pack"),
+];
+
+// 头部中的特征串
+const HEADER_SIGNATURES: [(&[u8], &str); 2] = [(b"UPX!", "UPX"), (b"FSG!", "FSG")];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackerHit {
+    pub name: String,
+    pub evidence: String,
+}
+
+fn push_hit(result: &mut Vec<PackerHit>, name: &str, evidence: String) {
+    if !result.iter().any(|hit| hit.name == name) {
+        result.push(PackerHit {
+            name: name.to_string(),
+            evidence,
+        });
+    }
+}
+
+//根据节名称、头部特征与代码熵识别加壳
+pub fn detect_packers(image: &PeImage, buf: &[u8]) -> Vec<PackerHit> {
+    let mut result = Vec::new();
+    for section in &image.sections {
+        let name = section.name.to_ascii_lowercase();
+        if let Some((_, packer)) = SECTION_SIGNATURES
+            .iter()
+            .find(|(signature, _)| *signature == name)
+        {
+            push_hit(&mut result, packer, format!("节名称 {}", section.name));
+        }
+    }
+    let header = &buf[..buf.len().min(HEADER_SCAN_SIZE)];
+    for (signature, packer) in HEADER_SIGNATURES {
+        if header
+            .windows(signature.len())
+            .any(|window| window == signature)
+        {
+            push_hit(
+                &mut result,
+                packer,
+                format!("头部特征 {}", String::from_utf8_lossy(signature)),
+            );
+        }
+    }
+    if result.is_empty() {
+        for section in image
+            .sections
+            .iter()
+            .filter(|section| section.is_executable())
+        {
+            let start = section.pointer_to_raw_data as usize;
+            let end = (start + section.size_of_raw_data as usize).min(buf.len());
+            if start >= end {
+                continue;
+            }
+            let entropy = pe_tools::shannon_entropy(&buf[start..end]);
+            if entropy > PACKED_ENTROPY {
+                push_hit(
+                    &mut result,
+                    "未知壳",
+                    format!("可执行节{}熵为{:.2}", section.name, entropy),
+                );
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+const IMAGE_DOS_SIGNATURE: u16 = 0x5A4D;
+const IMAGE_NT_SIGNATURE: u32 = 0x0000_4550;
+const IMAGE_NT_OPTIONAL_HDR32_MAGIC: u16 = 0x10B;
+const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20B;
+const IMAGE_SIZEOF_SECTION_HEADER: usize = 40;
+const IMAGE_SIZEOF_IMPORT_DESCRIPTOR: usize = 20;
+// 防止畸形文件导致的超长循环
+const MAX_IMPORT_DESCRIPTORS: usize = 4096;
+const MAX_THUNKS: usize = 65536;
+const MAX_TLS_CALLBACKS: usize = 256;
+const MAX_NAME_LEN: usize = 512;
+const MAX_RUNTIME_FUNCTIONS: usize = 1 << 20;
+const IMAGE_SIZEOF_RUNTIME_FUNCTION: usize = 12;
+const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+pub const DATA_DIRECTORY_NAMES: [&str; 16] = [
+    "Export",
+    "Import",
+    "Resource",
+    "Exception",
+    "Security",
+    "BaseReloc",
+    "Debug",
+    "Architecture",
+    "GlobalPtr",
+    "TLS",
+    "LoadConfig",
+    "BoundImport",
+    "IAT",
+    "DelayImport",
+    "CLR",
+    "Reserved",
+];
+pub const DIRECTORY_EXPORT: usize = 0;
+pub const DIRECTORY_IMPORT: usize = 1;
+pub const DIRECTORY_RESOURCE: usize = 2;
+pub const DIRECTORY_EXCEPTION: usize = 3;
+pub const DIRECTORY_SECURITY: usize = 4;
+pub const DIRECTORY_TLS: usize = 9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataDirectory {
+    pub index: usize,
+    pub name: String,
+    pub rva: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionHeader {
+    pub index: usize,
+    pub name: String,
+    pub header_offset: usize,
+    pub virtual_size: u32,
+    pub virtual_address: u32,
+    pub size_of_raw_data: u32,
+    pub pointer_to_raw_data: u32,
+    pub characteristics: u32,
+}
+
+impl SectionHeader {
+    //节在内存中的范围，虚拟大小为0时按文件大小计算
+    pub fn contains_rva(&self, rva: u32) -> bool {
+        let size = self.virtual_size.max(self.size_of_raw_data);
+        rva >= self.virtual_address && (rva as u64) < self.virtual_address as u64 + size as u64
+    }
+    pub fn contains_offset(&self, offset: usize) -> bool {
+        let start = self.pointer_to_raw_data as usize;
+        self.size_of_raw_data > 0
+            && offset >= start
+            && offset < start + self.size_of_raw_data as usize
+    }
+    //IMAGE_SCN_CNT_CODE或IMAGE_SCN_MEM_EXECUTE
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & 0x2000_0020 != 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportFunction {
+    pub dll: String,
+    pub name: Option<String>,
+    pub ordinal: Option<u16>,
+    pub hint: Option<u16>,
+    pub iat_rva: u32,
+}
+
+impl ImportFunction {
+    pub fn display_name(&self) -> String {
+        match (&self.name, self.ordinal) {
+            (Some(name), _) => format!("{}!{}", self.dll, name),
+            (None, Some(ordinal)) => format!("{}!#{}", self.dll, ordinal),
+            (None, None) => format!("{}!?", self.dll),
+        }
+    }
+}
+
+//x64 异常目录(.pdata)中的函数范围 [begin, end)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RuntimeFunction {
+    pub begin: u32,
+    pub end: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportFunction {
+    pub name: Option<String>,
+    pub ordinal: u32,
+    pub rva: u32,
+    pub forwarder: Option<String>,
+}
+
+//结构化解析后的PE信息，只保存解析结果，不持有文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeImage {
+    pub e_lfanew: u32,
+    pub machine: u16,
+    pub number_of_sections: u16,
+    pub time_date_stamp: u32,
+    pub size_of_optional_header: u16,
+    pub characteristics: u16,
+    pub optional_header_offset: usize,
+    pub is_64: bool,
+    pub entry_point: u32,
+    pub image_base: u64,
+    pub section_alignment: u32,
+    pub file_alignment: u32,
+    pub size_of_image: u32,
+    pub size_of_headers: u32,
+    pub checksum: u32,
+    pub subsystem: u16,
+    pub dll_characteristics: u16,
+    pub data_directories: Vec<DataDirectory>,
+    pub sections: Vec<SectionHeader>,
+    pub imports: Vec<ImportFunction>,
+    pub exports: Vec<ExportFunction>,
+    pub tls_callbacks: Vec<u32>,
+}
+
+pub fn read_u16(buf: &[u8], offset: usize) -> Option<u16> {
+    let bytes = buf.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+pub fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buf.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+pub fn read_u64(buf: &[u8], offset: usize) -> Option<u64> {
+    let bytes = buf.get(offset..offset.checked_add(8)?)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+//读取以0结尾的ASCII字符串
+pub fn read_c_string(buf: &[u8], offset: usize) -> Option<String> {
+    let tail = buf.get(offset..)?;
+    let end = tail.iter().take(MAX_NAME_LEN).position(|b| *b == 0)?;
+    Some(String::from_utf8_lossy(&tail[..end]).to_string())
+}
+
+impl PeImage {
+    pub fn parse(buf: &[u8]) -> anyhow::Result<PeImage> {
+        let invalid = || anyhow!("文件不是合法的PE文件");
+        if read_u16(buf, 0).ok_or_else(invalid)? != IMAGE_DOS_SIGNATURE {
+            bail!("缺少MZ标识, 文件不是合法的PE文件");
+        }
+        let e_lfanew = read_u32(buf, 0x3C).ok_or_else(invalid)?;
+        let nt = e_lfanew as usize;
+        if read_u32(buf, nt).ok_or_else(invalid)? != IMAGE_NT_SIGNATURE {
+            bail!("缺少PE标识, 文件不是合法的PE文件");
+        }
+        let file_header = nt + 4;
+        let machine = read_u16(buf, file_header).ok_or_else(invalid)?;
+        let number_of_sections = read_u16(buf, file_header + 2).ok_or_else(invalid)?;
+        let time_date_stamp = read_u32(buf, file_header + 4).ok_or_else(invalid)?;
+        let size_of_optional_header = read_u16(buf, file_header + 16).ok_or_else(invalid)?;
+        let characteristics = read_u16(buf, file_header + 18).ok_or_else(invalid)?;
+
+        let opt = file_header + 20;
+        let is_64 = match read_u16(buf, opt).ok_or_else(invalid)? {
+            IMAGE_NT_OPTIONAL_HDR32_MAGIC => false,
+            IMAGE_NT_OPTIONAL_HDR64_MAGIC => true,
+            magic => bail!("不支持的可选头类型: {:#X}", magic),
+        };
+        let entry_point = read_u32(buf, opt + 16).ok_or_else(invalid)?;
+        let image_base = if is_64 {
+            read_u64(buf, opt + 24).ok_or_else(invalid)?
+        } else {
+            read_u32(buf, opt + 28).ok_or_else(invalid)? as u64
+        };
+        let section_alignment = read_u32(buf, opt + 32).ok_or_else(invalid)?;
+        let file_alignment = read_u32(buf, opt + 36).ok_or_else(invalid)?;
+        let size_of_image = read_u32(buf, opt + 56).ok_or_else(invalid)?;
+        let size_of_headers = read_u32(buf, opt + 60).ok_or_else(invalid)?;
+        let checksum = read_u32(buf, opt + 64).ok_or_else(invalid)?;
+        let subsystem = read_u16(buf, opt + 68).ok_or_else(invalid)?;
+        let dll_characteristics = read_u16(buf, opt + 70).ok_or_else(invalid)?;
+        let (rva_count_offset, directory_offset) = if is_64 {
+            (opt + 108, opt + 112)
+        } else {
+            (opt + 92, opt + 96)
+        };
+        let number_of_rva_and_sizes =
+            read_u32(buf, rva_count_offset).ok_or_else(invalid)?.min(16) as usize;
+        let data_directories = (0..number_of_rva_and_sizes)
+            .map_while(|index| {
+                let offset = directory_offset + index * 8;
+                Some(DataDirectory {
+                    index,
+                    name: DATA_DIRECTORY_NAMES[index].to_string(),
+                    rva: read_u32(buf, offset)?,
+                    size: read_u32(buf, offset + 4)?,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let section_table = opt + size_of_optional_header as usize;
+        let sections = (0..number_of_sections as usize)
+            .map_while(|index| {
+                let offset = section_table + index * IMAGE_SIZEOF_SECTION_HEADER;
+                let raw_name = buf.get(offset..offset + 8)?;
+                let name_end = raw_name.iter().position(|b| *b == 0).unwrap_or(8);
+                Some(SectionHeader {
+                    index,
+                    name: String::from_utf8_lossy(&raw_name[..name_end]).to_string(),
+                    header_offset: offset,
+                    virtual_size: read_u32(buf, offset + 8)?,
+                    virtual_address: read_u32(buf, offset + 12)?,
+                    size_of_raw_data: read_u32(buf, offset + 16)?,
+                    pointer_to_raw_data: read_u32(buf, offset + 20)?,
+                    characteristics: read_u32(buf, offset + 36)?,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut image = PeImage {
+            e_lfanew,
+            machine,
+            number_of_sections,
+            time_date_stamp,
+            size_of_optional_header,
+            characteristics,
+            optional_header_offset: opt,
+            is_64,
+            entry_point,
+            image_base,
+            section_alignment,
+            file_alignment,
+            size_of_image,
+            size_of_headers,
+            checksum,
+            subsystem,
+            dll_characteristics,
+            data_directories,
+            sections,
+            imports: Vec::new(),
+            exports: Vec::new(),
+            tls_callbacks: Vec::new(),
+        };
+        image.imports = image.parse_imports(buf);
+        image.exports = image.parse_exports(buf);
+        image.tls_callbacks = image.parse_tls_callbacks(buf);
+        Ok(image)
+    }
+
+    pub fn bitness(&self) -> u32 {
+        if self.is_64 {
+            64
+        } else {
+            32
+        }
+    }
+
+    pub fn is_dll(&self) -> bool {
+        self.characteristics & 0x2000 != 0
+    }
+
+    //可选头中CheckSum字段的文件偏移
+    pub fn checksum_offset(&self) -> usize {
+        self.optional_header_offset + 64
+    }
+
+    //数据目录表项的文件偏移
+    pub fn data_directory_entry_offset(&self, index: usize) -> usize {
+        let directory_offset = if self.is_64 { 112 } else { 96 };
+        self.optional_header_offset + directory_offset + index * 8
+    }
+
+    pub fn data_directory(&self, index: usize) -> Option<&DataDirectory> {
+        self.data_directories
+            .get(index)
+            .filter(|directory| directory.rva != 0)
+    }
+
+    pub fn section_by_rva(&self, rva: u32) -> Option<&SectionHeader> {
+        self.sections
+            .iter()
+            .find(|section| section.contains_rva(rva))
+    }
+
+    pub fn section_by_offset(&self, offset: usize) -> Option<&SectionHeader> {
+        self.sections
+            .iter()
+            .find(|section| section.contains_offset(offset))
+    }
+
+    pub fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        if rva < self.size_of_headers {
+            return Some(rva as usize);
+        }
+        let section = self.section_by_rva(rva)?;
+        let delta = rva - section.virtual_address;
+        if delta >= section.size_of_raw_data {
+            return None;
+        }
+        Some(section.pointer_to_raw_data as usize + delta as usize)
+    }
+
+    pub fn offset_to_rva(&self, offset: usize) -> Option<u32> {
+        if offset < self.size_of_headers as usize {
+            return Some(offset as u32);
+        }
+        let section = self.section_by_offset(offset)?;
+        Some(
+            section
+                .virtual_address
+                .wrapping_add((offset - section.pointer_to_raw_data as usize) as u32),
+        )
+    }
+
+    pub fn va_to_rva(&self, va: u64) -> Option<u32> {
+        let rva = va.checked_sub(self.image_base)?;
+        u32::try_from(rva).ok()
+    }
+
+    //当前RVA所在节在文件中剩余的字节
+    pub fn bytes_at_rva<'a>(&self, buf: &'a [u8], rva: u32) -> Option<&'a [u8]> {
+        let offset = self.rva_to_offset(rva)?;
+        let end = match self.section_by_rva(rva) {
+            Some(section) if rva >= self.size_of_headers => (section.pointer_to_raw_data as usize
+                + section.size_of_raw_data as usize)
+                .min(buf.len()),
+            _ => (self.size_of_headers as usize).min(buf.len()),
+        };
+        buf.get(offset..end)
+    }
+
+    pub fn import_by_iat_rva(&self, rva: u32) -> Option<&ImportFunction> {
+        self.imports.iter().find(|import| import.iat_rva == rva)
+    }
+
+    pub fn export_by_name(&self, name: &str) -> Option<&ExportFunction> {
+        self.exports
+            .iter()
+            .find(|export| export.name.as_deref() == Some(name))
+    }
+
+    fn read_string_at_rva(&self, buf: &[u8], rva: u32) -> Option<String> {
+        read_c_string(buf, self.rva_to_offset(rva)?)
+    }
+
+    fn parse_imports(&self, buf: &[u8]) -> Vec<ImportFunction> {
+        let mut imports = Vec::new();
+        let Some(directory) = self.data_directory(DIRECTORY_IMPORT) else {
+            return imports;
+        };
+        let Some(mut descriptor) = self.rva_to_offset(directory.rva) else {
+            return imports;
+        };
+        let thunk_size = if self.is_64 { 8 } else { 4 };
+        for _ in 0..MAX_IMPORT_DESCRIPTORS {
+            let (Some(original_first_thunk), Some(name_rva), Some(first_thunk)) = (
+                read_u32(buf, descriptor),
+                read_u32(buf, descriptor + 12),
+                read_u32(buf, descriptor + 16),
+            ) else {
+                break;
+            };
+            if name_rva == 0 && first_thunk == 0 {
+                break;
+            }
+            let dll = self
+                .read_string_at_rva(buf, name_rva)
+                .unwrap_or_default()
+                .to_lowercase();
+            let lookup_rva = if original_first_thunk != 0 {
+                original_first_thunk
+            } else {
+                first_thunk
+            };
+            if let Some(lookup_offset) = self.rva_to_offset(lookup_rva) {
+                for index in 0..MAX_THUNKS {
+                    let thunk_offset = lookup_offset + index * thunk_size;
+                    let (thunk, by_ordinal) = if self.is_64 {
+                        match read_u64(buf, thunk_offset) {
+                            Some(thunk) => (thunk, thunk & (1 << 63) != 0),
+                            None => break,
+                        }
+                    } else {
+                        match read_u32(buf, thunk_offset) {
+                            Some(thunk) => (thunk as u64, thunk & (1 << 31) != 0),
+                            None => break,
+                        }
+                    };
+                    if thunk == 0 {
+                        break;
+                    }
+                    let iat_rva = first_thunk.wrapping_add((index * thunk_size) as u32);
+                    let function = if by_ordinal {
+                        ImportFunction {
+                            dll: dll.clone(),
+                            name: None,
+                            ordinal: Some((thunk & 0xFFFF) as u16),
+                            hint: None,
+                            iat_rva,
+                        }
+                    } else {
+                        let hint_name = self.rva_to_offset((thunk & 0x7FFF_FFFF) as u32);
+                        ImportFunction {
+                            dll: dll.clone(),
+                            name: hint_name.and_then(|offset| read_c_string(buf, offset + 2)),
+                            ordinal: None,
+                            hint: hint_name.and_then(|offset| read_u16(buf, offset)),
+                            iat_rva,
+                        }
+                    };
+                    imports.push(function);
+                }
+            }
+            descriptor += IMAGE_SIZEOF_IMPORT_DESCRIPTOR;
+        }
+        imports
+    }
+
+    fn parse_exports(&self, buf: &[u8]) -> Vec<ExportFunction> {
+        let mut exports = Vec::new();
+        let Some(directory) = self.data_directory(DIRECTORY_EXPORT) else {
+            return exports;
+        };
+        let Some(offset) = self.rva_to_offset(directory.rva) else {
+            return exports;
+        };
+        let (
+            Some(base),
+            Some(number_of_functions),
+            Some(number_of_names),
+            Some(functions_rva),
+            Some(names_rva),
+            Some(ordinals_rva),
+        ) = (
+            read_u32(buf, offset + 16),
+            read_u32(buf, offset + 20),
+            read_u32(buf, offset + 24),
+            read_u32(buf, offset + 28),
+            read_u32(buf, offset + 32),
+            read_u32(buf, offset + 36),
+        )
+        else {
+            return exports;
+        };
+        let (Some(functions), Some(names), Some(ordinals)) = (
+            self.rva_to_offset(functions_rva),
+            self.rva_to_offset(names_rva),
+            self.rva_to_offset(ordinals_rva),
+        ) else {
+            return exports;
+        };
+        let number_of_functions = (number_of_functions as usize).min(MAX_THUNKS);
+        let number_of_names = (number_of_names as usize).min(MAX_THUNKS);
+        let mut function_names: Vec<Option<String>> = vec![None; number_of_functions];
+        for index in 0..number_of_names {
+            let (Some(name_rva), Some(ordinal_index)) = (
+                read_u32(buf, names + index * 4),
+                read_u16(buf, ordinals + index * 2),
+            ) else {
+                break;
+            };
+            if let Some(slot) = function_names.get_mut(ordinal_index as usize) {
+                *slot = self.read_string_at_rva(buf, name_rva);
+            }
+        }
+        let directory_end = directory.rva as u64 + directory.size as u64;
+        for (index, name) in function_names.into_iter().enumerate() {
+            let Some(rva) = read_u32(buf, functions + index * 4) else {
+                break;
+            };
+            if rva == 0 {
+                continue;
+            }
+            let forwarder = if rva >= directory.rva && (rva as u64) < directory_end {
+                self.read_string_at_rva(buf, rva)
+            } else {
+                None
+            };
+            exports.push(ExportFunction {
+                name,
+                ordinal: base.wrapping_add(index as u32),
+                rva,
+                forwarder,
+            });
+        }
+        exports
+    }
+
+    fn parse_tls_callbacks(&self, buf: &[u8]) -> Vec<u32> {
+        let mut callbacks = Vec::new();
+        let Some(directory) = self.data_directory(DIRECTORY_TLS) else {
+            return callbacks;
+        };
+        let Some(offset) = self.rva_to_offset(directory.rva) else {
+            return callbacks;
+        };
+        let address_of_callbacks = if self.is_64 {
+            read_u64(buf, offset + 24)
+        } else {
+            read_u32(buf, offset + 12).map(|va| va as u64)
+        };
+        let Some(mut callback_offset) = address_of_callbacks
+            .and_then(|va| self.va_to_rva(va))
+            .and_then(|rva| self.rva_to_offset(rva))
+        else {
+            return callbacks;
+        };
+        for _ in 0..MAX_TLS_CALLBACKS {
+            let va = if self.is_64 {
+                read_u64(buf, callback_offset)
+            } else {
+                read_u32(buf, callback_offset).map(|va| va as u64)
+            };
+            match va.and_then(|va| if va == 0 { None } else { self.va_to_rva(va) }) {
+                Some(rva) => callbacks.push(rva),
+                None => break,
+            }
+            callback_offset += if self.is_64 { 8 } else { 4 };
+        }
+        callbacks
+    }
+
+    //解析.pdata中的函数范围，链式展开信息的片段合并到其所属的主函数
+    pub fn runtime_functions(&self, buf: &[u8]) -> Vec<Vec<RuntimeFunction>> {
+        let Some(directory) = self.data_directory(DIRECTORY_EXCEPTION) else {
+            return Vec::new();
+        };
+        let Some(offset) = self.rva_to_offset(directory.rva) else {
+            return Vec::new();
+        };
+        let count =
+            (directory.size as usize / IMAGE_SIZEOF_RUNTIME_FUNCTION).min(MAX_RUNTIME_FUNCTIONS);
+        let read_entry = |offset: usize| {
+            Some((
+                RuntimeFunction {
+                    begin: read_u32(buf, offset)?,
+                    end: read_u32(buf, offset + 4)?,
+                },
+                read_u32(buf, offset + 8)?,
+            ))
+        };
+        let mut groups: Vec<Vec<RuntimeFunction>> = Vec::new();
+        let mut index_by_begin: HashMap<u32, usize> = HashMap::new();
+        let mut chained: Vec<(RuntimeFunction, u32)> = Vec::new();
+        for index in 0..count {
+            let Some((function, unwind_rva)) =
+                read_entry(offset + index * IMAGE_SIZEOF_RUNTIME_FUNCTION)
+            else {
+                break;
+            };
+            if function.begin == 0 || function.end <= function.begin {
+                continue;
+            }
+            match self.chained_parent(buf, unwind_rva) {
+                Some(parent) => chained.push((function, parent)),
+                None => {
+                    index_by_begin.insert(function.begin, groups.len());
+                    groups.push(vec![function]);
+                }
+            }
+        }
+        for (function, parent) in chained {
+            match index_by_begin.get(&parent) {
+                Some(index) => groups[*index].push(function),
+                None => {
+                    index_by_begin.insert(function.begin, groups.len());
+                    groups.push(vec![function]);
+                }
+            }
+        }
+        groups
+    }
+
+    //UNWIND_INFO带有UNW_FLAG_CHAININFO时，返回最终的主函数起始RVA
+    fn chained_parent(&self, buf: &[u8], unwind_rva: u32) -> Option<u32> {
+        let mut unwind_rva = unwind_rva & !1;
+        let mut parent = None;
+        // 链深度通常只有一两层，限制次数防止环
+        for _ in 0..32 {
+            let offset = self.rva_to_offset(unwind_rva)?;
+            let flags = buf.get(offset)? >> 3;
+            if flags & UNW_FLAG_CHAININFO == 0 {
+                return parent;
+            }
+            let code_count = *buf.get(offset + 2)? as usize;
+            let chain_offset = offset + 4 + ((code_count + 1) & !1) * 2;
+            parent = Some(read_u32(buf, chain_offset)?);
+            unwind_rva = read_u32(buf, chain_offset + 8)? & !1;
+        }
+        parent
+    }
+}
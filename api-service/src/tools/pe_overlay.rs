@@ -0,0 +1,762 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::{
+    self, PeImage, DIRECTORY_IMPORT, DIRECTORY_RESOURCE, DIRECTORY_SECURITY,
+};
+
+// 防止畸形文件产生过多的标注
+const MAX_ANNOTATIONS: usize = 200_000;
+const MAX_IMPORT_DESCRIPTORS: usize = 4096;
+const IMPORT_DESCRIPTOR_SIZE: usize = 20;
+const MAX_THUNKS: usize = 65536;
+const MAX_RESOURCE_ENTRIES: usize = 8192;
+// 资源目录固定为类型、名称、语言三层
+const MAX_RESOURCE_DEPTH: usize = 3;
+
+//字段名称、相对结构起始的偏移、字节数
+type Field = (&'static str, usize, usize);
+
+const DOS_HEADER: [Field; 19] = [
+    ("e_magic", 0x00, 2),
+    ("e_cblp", 0x02, 2),
+    ("e_cp", 0x04, 2),
+    ("e_crlc", 0x06, 2),
+    ("e_cparhdr", 0x08, 2),
+    ("e_minalloc", 0x0A, 2),
+    ("e_maxalloc", 0x0C, 2),
+    ("e_ss", 0x0E, 2),
+    ("e_sp", 0x10, 2),
+    ("e_csum", 0x12, 2),
+    ("e_ip", 0x14, 2),
+    ("e_cs", 0x16, 2),
+    ("e_lfarlc", 0x18, 2),
+    ("e_ovno", 0x1A, 2),
+    ("e_res", 0x1C, 8),
+    ("e_oemid", 0x24, 2),
+    ("e_oeminfo", 0x26, 2),
+    ("e_res2", 0x28, 20),
+    ("e_lfanew", 0x3C, 4),
+];
+const FILE_HEADER: [Field; 7] = [
+    ("Machine", 0, 2),
+    ("NumberOfSections", 2, 2),
+    ("TimeDateStamp", 4, 4),
+    ("PointerToSymbolTable", 8, 4),
+    ("NumberOfSymbols", 12, 4),
+    ("SizeOfOptionalHeader", 16, 2),
+    ("Characteristics", 18, 2),
+];
+// 32位与64位可选头相同的字段
+const OPTIONAL_HEADER: [Field; 22] = [
+    ("Magic", 0, 2),
+    ("MajorLinkerVersion", 2, 1),
+    ("MinorLinkerVersion", 3, 1),
+    ("SizeOfCode", 4, 4),
+    ("SizeOfInitializedData", 8, 4),
+    ("SizeOfUninitializedData", 12, 4),
+    ("AddressOfEntryPoint", 16, 4),
+    ("BaseOfCode", 20, 4),
+    ("SectionAlignment", 32, 4),
+    ("FileAlignment", 36, 4),
+    ("MajorOperatingSystemVersion", 40, 2),
+    ("MinorOperatingSystemVersion", 42, 2),
+    ("MajorImageVersion", 44, 2),
+    ("MinorImageVersion", 46, 2),
+    ("MajorSubsystemVersion", 48, 2),
+    ("MinorSubsystemVersion", 50, 2),
+    ("Win32VersionValue", 52, 4),
+    ("SizeOfImage", 56, 4),
+    ("SizeOfHeaders", 60, 4),
+    ("CheckSum", 64, 4),
+    ("Subsystem", 68, 2),
+    ("DllCharacteristics", 70, 2),
+];
+const OPTIONAL_HEADER32: [Field; 8] = [
+    ("BaseOfData", 24, 4),
+    ("ImageBase", 28, 4),
+    ("SizeOfStackReserve", 72, 4),
+    ("SizeOfStackCommit", 76, 4),
+    ("SizeOfHeapReserve", 80, 4),
+    ("SizeOfHeapCommit", 84, 4),
+    ("LoaderFlags", 88, 4),
+    ("NumberOfRvaAndSizes", 92, 4),
+];
+const OPTIONAL_HEADER64: [Field; 7] = [
+    ("ImageBase", 24, 8),
+    ("SizeOfStackReserve", 72, 8),
+    ("SizeOfStackCommit", 80, 8),
+    ("SizeOfHeapReserve", 88, 8),
+    ("SizeOfHeapCommit", 96, 8),
+    ("LoaderFlags", 104, 4),
+    ("NumberOfRvaAndSizes", 108, 4),
+];
+const DATA_DIRECTORY: [Field; 2] = [("VirtualAddress", 0, 4), ("Size", 4, 4)];
+const SECTION_HEADER: [Field; 10] = [
+    ("Name", 0, 8),
+    ("VirtualSize", 8, 4),
+    ("VirtualAddress", 12, 4),
+    ("SizeOfRawData", 16, 4),
+    ("PointerToRawData", 20, 4),
+    ("PointerToRelocations", 24, 4),
+    ("PointerToLinenumbers", 28, 4),
+    ("NumberOfRelocations", 32, 2),
+    ("NumberOfLinenumbers", 34, 2),
+    ("Characteristics", 36, 4),
+];
+const IMPORT_DESCRIPTOR: [Field; 5] = [
+    ("OriginalFirstThunk", 0, 4),
+    ("TimeDateStamp", 4, 4),
+    ("ForwarderChain", 8, 4),
+    ("Name", 12, 4),
+    ("FirstThunk", 16, 4),
+];
+const RESOURCE_DIRECTORY: [Field; 6] = [
+    ("Characteristics", 0, 4),
+    ("TimeDateStamp", 4, 4),
+    ("MajorVersion", 8, 2),
+    ("MinorVersion", 10, 2),
+    ("NumberOfNamedEntries", 12, 2),
+    ("NumberOfIdEntries", 14, 2),
+];
+const RESOURCE_DIRECTORY_ENTRY: [Field; 2] = [("Name", 0, 4), ("OffsetToData", 4, 4)];
+const RESOURCE_DATA_ENTRY: [Field; 4] = [
+    ("OffsetToData", 0, 4),
+    ("Size", 4, 4),
+    ("CodePage", 8, 4),
+    ("Reserved", 12, 4),
+];
+
+const FILE_CHARACTERISTICS: [(u64, &str); 9] = [
+    (0x0001, "RELOCS_STRIPPED"),
+    (0x0002, "EXECUTABLE_IMAGE"),
+    (0x0004, "LINE_NUMS_STRIPPED"),
+    (0x0008, "LOCAL_SYMS_STRIPPED"),
+    (0x0020, "LARGE_ADDRESS_AWARE"),
+    (0x0100, "32BIT_MACHINE"),
+    (0x0200, "DEBUG_STRIPPED"),
+    (0x1000, "SYSTEM"),
+    (0x2000, "DLL"),
+];
+const DLL_CHARACTERISTICS: [(u64, &str); 11] = [
+    (0x0020, "HIGH_ENTROPY_VA"),
+    (0x0040, "DYNAMIC_BASE"),
+    (0x0080, "FORCE_INTEGRITY"),
+    (0x0100, "NX_COMPAT"),
+    (0x0200, "NO_ISOLATION"),
+    (0x0400, "NO_SEH"),
+    (0x0800, "NO_BIND"),
+    (0x1000, "APPCONTAINER"),
+    (0x2000, "WDM_DRIVER"),
+    (0x4000, "GUARD_CF"),
+    (0x8000, "TERMINAL_SERVER_AWARE"),
+];
+const SECTION_CHARACTERISTICS: [(u64, &str); 7] = [
+    (0x0000_0020, "CODE"),
+    (0x0000_0040, "INITIALIZED_DATA"),
+    (0x0000_0080, "UNINITIALIZED_DATA"),
+    (0x0200_0000, "DISCARDABLE"),
+    (0x2000_0000, "EXECUTE"),
+    (0x4000_0000, "READ"),
+    (0x8000_0000, "WRITE"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    Header,
+    DataDirectory,
+    Section,
+    Import,
+    Resource,
+}
+
+//一段字节所属的PE结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub offset: u64,
+    pub length: u64,
+    pub kind: AnnotationKind,
+    // 结构名称，带有节名、模块名等区分同类结构
+    pub structure: String,
+    // 整段结构或数据区域时为空
+    pub field: Option<String>,
+    // 解析后的值
+    pub value: Option<String>,
+}
+
+fn flags_text(value: u64, flags: &[(u64, &str)]) -> String {
+    flags
+        .iter()
+        .filter(|(flag, _)| value & flag != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn timestamp_text(value: u64) -> Option<String> {
+    if value == 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp(value as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+}
+
+fn machine_name(value: u64) -> Option<&'static str> {
+    match value {
+        0x014C => Some("I386"),
+        0x0200 => Some("IA64"),
+        0x01C0 => Some("ARM"),
+        0x01C4 => Some("ARMNT"),
+        0x8664 => Some("AMD64"),
+        0xAA64 => Some("ARM64"),
+        _ => None,
+    }
+}
+
+fn subsystem_name(value: u64) -> Option<&'static str> {
+    match value {
+        1 => Some("NATIVE"),
+        2 => Some("WINDOWS_GUI"),
+        3 => Some("WINDOWS_CUI"),
+        7 => Some("POSIX_CUI"),
+        9 => Some("WINDOWS_CE_GUI"),
+        10 => Some("EFI_APPLICATION"),
+        11 => Some("EFI_BOOT_SERVICE_DRIVER"),
+        12 => Some("EFI_RUNTIME_DRIVER"),
+        14 => Some("XBOX"),
+        16 => Some("WINDOWS_BOOT_APPLICATION"),
+        _ => None,
+    }
+}
+
+fn resource_type_name(id: u32) -> Option<&'static str> {
+    match id {
+        1 => Some("CURSOR"),
+        2 => Some("BITMAP"),
+        3 => Some("ICON"),
+        4 => Some("MENU"),
+        5 => Some("DIALOG"),
+        6 => Some("STRING"),
+        7 => Some("FONTDIR"),
+        8 => Some("FONT"),
+        9 => Some("ACCELERATOR"),
+        10 => Some("RCDATA"),
+        11 => Some("MESSAGETABLE"),
+        12 => Some("GROUP_CURSOR"),
+        14 => Some("GROUP_ICON"),
+        16 => Some("VERSION"),
+        17 => Some("DLGINCLUDE"),
+        19 => Some("PLUGPLAY"),
+        20 => Some("VXD"),
+        21 => Some("ANICURSOR"),
+        22 => Some("ANIICON"),
+        23 => Some("HTML"),
+        24 => Some("MANIFEST"),
+        _ => None,
+    }
+}
+
+fn read_le(buf: &[u8], offset: usize, size: usize) -> Option<u64> {
+    let bytes = buf.get(offset..offset.checked_add(size)?)?;
+    Some(
+        bytes
+            .iter()
+            .rev()
+            .fold(0u64, |value, byte| (value << 8) | *byte as u64),
+    )
+}
+
+struct Overlay<'a> {
+    buf: &'a [u8],
+    image: &'a PeImage,
+    // 只保留与该范围相交的结构
+    range: Range<usize>,
+    annotations: Vec<Annotation>,
+}
+
+impl Overlay<'_> {
+    fn visible(&self, offset: usize, length: usize) -> bool {
+        offset < self.range.end && offset.saturating_add(length) > self.range.start
+    }
+
+    fn push(
+        &mut self,
+        kind: AnnotationKind,
+        structure: &str,
+        field: Option<&str>,
+        offset: usize,
+        length: usize,
+        value: Option<String>,
+    ) {
+        if offset >= self.buf.len()
+            || length == 0
+            || !self.visible(offset, length)
+            || self.annotations.len() >= MAX_ANNOTATIONS
+        {
+            return;
+        }
+        self.annotations.push(Annotation {
+            offset: offset as u64,
+            length: length.min(self.buf.len() - offset) as u64,
+            kind,
+            structure: structure.to_string(),
+            field: field.map(|field| field.to_string()),
+            value,
+        });
+    }
+
+    //RVA所在的节，用于说明地址字段
+    fn rva_text(&self, rva: u64) -> Option<String> {
+        let section = self.image.section_by_rva(u32::try_from(rva).ok()?)?;
+        Some(section.name.clone())
+    }
+
+    //按字段表标注结构，structure_type用于选择字段值的解析方式
+    fn fields(
+        &mut self,
+        kind: AnnotationKind,
+        structure_type: &str,
+        structure: &str,
+        base: usize,
+        fields: &[Field],
+    ) {
+        for (name, offset, size) in fields {
+            let offset = base + offset;
+            if !self.visible(offset, *size) {
+                continue;
+            }
+            let value = if *size <= 8 {
+                read_le(self.buf, offset, *size)
+                    .map(|value| self.field_value(structure_type, name, offset, *size, value))
+            } else {
+                None
+            };
+            self.push(kind, structure, Some(name), offset, *size, value);
+        }
+    }
+
+    fn field_value(
+        &self,
+        structure_type: &str,
+        name: &str,
+        offset: usize,
+        size: usize,
+        value: u64,
+    ) -> String {
+        let decoded = match (structure_type, name) {
+            ("IMAGE_DOS_HEADER", "e_magic") | ("IMAGE_NT_HEADERS", "Signature") => {
+                let bytes = &self.buf[offset..offset + size];
+                Some(
+                    bytes
+                        .iter()
+                        .take_while(|byte| **byte != 0)
+                        .map(|byte| crate::tools::hex_view::printable(*byte))
+                        .collect(),
+                )
+            }
+            ("IMAGE_SECTION_HEADER", "Name") => {
+                let bytes = &self.buf[offset..offset + size];
+                let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(size);
+                return String::from_utf8_lossy(&bytes[..end]).to_string();
+            }
+            ("IMAGE_FILE_HEADER", "Machine") => machine_name(value).map(|name| name.to_string()),
+            (_, "TimeDateStamp") => timestamp_text(value),
+            ("IMAGE_FILE_HEADER", "Characteristics") => {
+                Some(flags_text(value, &FILE_CHARACTERISTICS))
+            }
+            ("IMAGE_OPTIONAL_HEADER", "Magic") => match value {
+                0x10B => Some("PE32".to_string()),
+                0x20B => Some("PE32+".to_string()),
+                0x107 => Some("ROM".to_string()),
+                _ => None,
+            },
+            ("IMAGE_OPTIONAL_HEADER", "Subsystem") => {
+                subsystem_name(value).map(|name| name.to_string())
+            }
+            ("IMAGE_OPTIONAL_HEADER", "DllCharacteristics") => {
+                Some(flags_text(value, &DLL_CHARACTERISTICS))
+            }
+            ("IMAGE_OPTIONAL_HEADER", "AddressOfEntryPoint" | "BaseOfCode" | "BaseOfData")
+            | ("IMAGE_DATA_DIRECTORY", "VirtualAddress")
+            | ("IMAGE_IMPORT_DESCRIPTOR", "OriginalFirstThunk" | "Name" | "FirstThunk")
+            | ("IMAGE_RESOURCE_DATA_ENTRY", "OffsetToData") => self.rva_text(value),
+            ("IMAGE_SECTION_HEADER", "Characteristics") => {
+                Some(flags_text(value, &SECTION_CHARACTERISTICS))
+            }
+            _ => None,
+        };
+        let number = format!("0x{:0width$X}", value, width = size * 2);
+        match decoded.filter(|decoded| !decoded.is_empty()) {
+            Some(decoded) => format!("{} ({})", number, decoded),
+            None => number,
+        }
+    }
+
+    fn headers(&mut self) {
+        let image = self.image;
+        let kind = AnnotationKind::Header;
+        self.fields(kind, "IMAGE_DOS_HEADER", "IMAGE_DOS_HEADER", 0, &DOS_HEADER);
+        let nt = image.e_lfanew as usize;
+        if nt > 0x40 {
+            self.push(kind, "DOS Stub", None, 0x40, nt - 0x40, None);
+        }
+        self.fields(
+            kind,
+            "IMAGE_NT_HEADERS",
+            "IMAGE_NT_HEADERS",
+            nt,
+            &[("Signature", 0, 4)],
+        );
+        self.fields(
+            kind,
+            "IMAGE_FILE_HEADER",
+            "IMAGE_FILE_HEADER",
+            nt + 4,
+            &FILE_HEADER,
+        );
+        let opt = image.optional_header_offset;
+        self.fields(
+            kind,
+            "IMAGE_OPTIONAL_HEADER",
+            "IMAGE_OPTIONAL_HEADER",
+            opt,
+            &OPTIONAL_HEADER,
+        );
+        let specific: &[Field] = if image.is_64 {
+            &OPTIONAL_HEADER64
+        } else {
+            &OPTIONAL_HEADER32
+        };
+        self.fields(
+            kind,
+            "IMAGE_OPTIONAL_HEADER",
+            "IMAGE_OPTIONAL_HEADER",
+            opt,
+            specific,
+        );
+    }
+
+    //数据目录表项以及目录指向的数据
+    fn data_directories(&mut self) {
+        let image = self.image;
+        for directory in &image.data_directories {
+            let structure = format!("IMAGE_DATA_DIRECTORY[{}]", directory.name);
+            self.fields(
+                AnnotationKind::DataDirectory,
+                "IMAGE_DATA_DIRECTORY",
+                &structure,
+                image.data_directory_entry_offset(directory.index),
+                &DATA_DIRECTORY,
+            );
+            if directory.rva == 0 || directory.size == 0 {
+                continue;
+            }
+            // 证书目录中的地址是文件偏移
+            let offset = if directory.index == DIRECTORY_SECURITY {
+                Some(directory.rva as usize)
+            } else {
+                image.rva_to_offset(directory.rva)
+            };
+            if let Some(offset) = offset {
+                self.push(
+                    AnnotationKind::DataDirectory,
+                    &format!("{} Directory", directory.name),
+                    None,
+                    offset,
+                    directory.size as usize,
+                    Some(format!("RVA 0x{:08X}", directory.rva)),
+                );
+            }
+        }
+    }
+
+    fn sections(&mut self) {
+        let image = self.image;
+        for section in &image.sections {
+            self.fields(
+                AnnotationKind::Section,
+                "IMAGE_SECTION_HEADER",
+                &format!("IMAGE_SECTION_HEADER[{}]", section.name),
+                section.header_offset,
+                &SECTION_HEADER,
+            );
+            self.push(
+                AnnotationKind::Section,
+                &format!("Section[{}]", section.name),
+                None,
+                section.pointer_to_raw_data as usize,
+                section.size_of_raw_data as usize,
+                Some(format!(
+                    "RVA 0x{:08X}, {}",
+                    section.virtual_address,
+                    flags_text(section.characteristics as u64, &SECTION_CHARACTERISTICS)
+                )),
+            );
+        }
+    }
+
+    //导入描述符、INT/IAT中的每个thunk以及函数名称
+    fn imports(&mut self) {
+        let image = self.image;
+        let Some(mut descriptor) = image
+            .data_directory(DIRECTORY_IMPORT)
+            .and_then(|directory| image.rva_to_offset(directory.rva))
+        else {
+            return;
+        };
+        let thunk_size = if image.is_64 { 8 } else { 4 };
+        let ordinal_flag = 1u64 << (thunk_size * 8 - 1);
+        for _ in 0..MAX_IMPORT_DESCRIPTORS {
+            let (Some(original_first_thunk), Some(name_rva), Some(first_thunk)) = (
+                pe_image::read_u32(self.buf, descriptor),
+                pe_image::read_u32(self.buf, descriptor + 12),
+                pe_image::read_u32(self.buf, descriptor + 16),
+            ) else {
+                break;
+            };
+            if name_rva == 0 && first_thunk == 0 {
+                self.push(
+                    AnnotationKind::Import,
+                    "IMAGE_IMPORT_DESCRIPTOR[NULL]",
+                    None,
+                    descriptor,
+                    IMPORT_DESCRIPTOR_SIZE,
+                    None,
+                );
+                break;
+            }
+            let name_offset = image.rva_to_offset(name_rva);
+            let dll = name_offset
+                .and_then(|offset| pe_image::read_c_string(self.buf, offset))
+                .unwrap_or_default();
+            self.fields(
+                AnnotationKind::Import,
+                "IMAGE_IMPORT_DESCRIPTOR",
+                &format!("IMAGE_IMPORT_DESCRIPTOR[{}]", dll),
+                descriptor,
+                &IMPORT_DESCRIPTOR,
+            );
+            if let Some(offset) = name_offset {
+                self.push(
+                    AnnotationKind::Import,
+                    "DLL Name",
+                    None,
+                    offset,
+                    dll.len() + 1,
+                    Some(dll.clone()),
+                );
+            }
+            let lookup = image.rva_to_offset(if original_first_thunk != 0 {
+                original_first_thunk
+            } else {
+                first_thunk
+            });
+            let iat = image.rva_to_offset(first_thunk);
+            for index in 0..MAX_THUNKS {
+                let Some(thunk) = lookup
+                    .and_then(|offset| read_le(self.buf, offset + index * thunk_size, thunk_size))
+                else {
+                    break;
+                };
+                if thunk == 0 {
+                    break;
+                }
+                let iat_rva = first_thunk.wrapping_add((index * thunk_size) as u32);
+                let function = image
+                    .import_by_iat_rva(iat_rva)
+                    .map(|function| function.display_name());
+                if original_first_thunk != 0 {
+                    if let Some(offset) = lookup {
+                        self.push(
+                            AnnotationKind::Import,
+                            &format!("IMAGE_THUNK_DATA[{}] INT", dll),
+                            Some("AddressOfData"),
+                            offset + index * thunk_size,
+                            thunk_size,
+                            function.clone(),
+                        );
+                    }
+                }
+                if let Some(offset) = iat {
+                    self.push(
+                        AnnotationKind::Import,
+                        &format!("IMAGE_THUNK_DATA[{}] IAT", dll),
+                        Some("Function"),
+                        offset + index * thunk_size,
+                        thunk_size,
+                        function.clone(),
+                    );
+                }
+                if thunk & ordinal_flag != 0 {
+                    continue;
+                }
+                if let Some(offset) = image.rva_to_offset((thunk & 0x7FFF_FFFF) as u32) {
+                    let name = pe_image::read_c_string(self.buf, offset + 2).unwrap_or_default();
+                    let hint = pe_image::read_u16(self.buf, offset);
+                    self.push(
+                        AnnotationKind::Import,
+                        "IMAGE_IMPORT_BY_NAME",
+                        Some("Hint"),
+                        offset,
+                        2,
+                        hint.map(|hint| hint.to_string()),
+                    );
+                    self.push(
+                        AnnotationKind::Import,
+                        "IMAGE_IMPORT_BY_NAME",
+                        Some("Name"),
+                        offset + 2,
+                        name.len() + 1,
+                        Some(name),
+                    );
+                }
+            }
+            descriptor += IMPORT_DESCRIPTOR_SIZE;
+        }
+    }
+
+    //资源目录树，条目中的偏移相对资源目录起始
+    fn resources(&mut self) {
+        let image = self.image;
+        let Some(root) = image
+            .data_directory(DIRECTORY_RESOURCE)
+            .and_then(|directory| image.rva_to_offset(directory.rva))
+        else {
+            return;
+        };
+        let mut visited = HashSet::new();
+        let mut entries = 0;
+        // 待处理的目录: (相对偏移, 层级, 资源路径)
+        let mut pending = vec![(0usize, 0usize, String::new())];
+        while let Some((relative, depth, path)) = pending.pop() {
+            if depth >= MAX_RESOURCE_DEPTH || !visited.insert(relative) {
+                continue;
+            }
+            let directory = root + relative;
+            let (Some(named), Some(ids)) = (
+                pe_image::read_u16(self.buf, directory + 12),
+                pe_image::read_u16(self.buf, directory + 14),
+            ) else {
+                continue;
+            };
+            let structure = if path.is_empty() {
+                "IMAGE_RESOURCE_DIRECTORY".to_string()
+            } else {
+                format!("IMAGE_RESOURCE_DIRECTORY[{}]", path)
+            };
+            self.fields(
+                AnnotationKind::Resource,
+                "IMAGE_RESOURCE_DIRECTORY",
+                &structure,
+                directory,
+                &RESOURCE_DIRECTORY,
+            );
+            for index in 0..named as usize + ids as usize {
+                entries += 1;
+                if entries > MAX_RESOURCE_ENTRIES {
+                    return;
+                }
+                let entry = directory + 16 + index * 8;
+                let (Some(name), Some(offset_to_data)) = (
+                    pe_image::read_u32(self.buf, entry),
+                    pe_image::read_u32(self.buf, entry + 4),
+                ) else {
+                    break;
+                };
+                let entry_name = self.resource_name(root, name, depth);
+                let entry_path = if path.is_empty() {
+                    entry_name
+                } else {
+                    format!("{}/{}", path, entry_name)
+                };
+                self.fields(
+                    AnnotationKind::Resource,
+                    "IMAGE_RESOURCE_DIRECTORY_ENTRY",
+                    &format!("IMAGE_RESOURCE_DIRECTORY_ENTRY[{}]", entry_path),
+                    entry,
+                    &RESOURCE_DIRECTORY_ENTRY,
+                );
+                let target = (offset_to_data & 0x7FFF_FFFF) as usize;
+                if offset_to_data & 0x8000_0000 != 0 {
+                    pending.push((target, depth + 1, entry_path));
+                } else {
+                    self.resource_data(root + target, &entry_path);
+                }
+            }
+        }
+    }
+
+    //资源名称为字符串时高位置1，低31位是长度前缀的UTF-16字符串偏移
+    fn resource_name(&mut self, root: usize, name: u32, depth: usize) -> String {
+        if name & 0x8000_0000 == 0 {
+            return match resource_type_name(name).filter(|_| depth == 0) {
+                Some(type_name) => type_name.to_string(),
+                None => name.to_string(),
+            };
+        }
+        let offset = root + (name & 0x7FFF_FFFF) as usize;
+        let Some(length) = pe_image::read_u16(self.buf, offset) else {
+            return String::new();
+        };
+        let units = (0..length as usize)
+            .map_while(|index| pe_image::read_u16(self.buf, offset + 2 + index * 2))
+            .collect::<Vec<_>>();
+        let text = String::from_utf16_lossy(&units);
+        self.push(
+            AnnotationKind::Resource,
+            "IMAGE_RESOURCE_DIR_STRING_U",
+            None,
+            offset,
+            2 + units.len() * 2,
+            Some(text.clone()),
+        );
+        text
+    }
+
+    fn resource_data(&mut self, entry: usize, path: &str) {
+        self.fields(
+            AnnotationKind::Resource,
+            "IMAGE_RESOURCE_DATA_ENTRY",
+            &format!("IMAGE_RESOURCE_DATA_ENTRY[{}]", path),
+            entry,
+            &RESOURCE_DATA_ENTRY,
+        );
+        let (Some(rva), Some(size)) = (
+            pe_image::read_u32(self.buf, entry),
+            pe_image::read_u32(self.buf, entry + 4),
+        ) else {
+            return;
+        };
+        if let Some(offset) = self.image.rva_to_offset(rva) {
+            self.push(
+                AnnotationKind::Resource,
+                &format!("Resource[{}]", path),
+                None,
+                offset,
+                size as usize,
+                Some(format!("{} 字节", size)),
+            );
+        }
+    }
+}
+
+//生成与[range.start, range.end)相交的PE结构标注，按偏移排序，范围较大的结构排在前面
+pub fn annotations(buf: &[u8], image: &PeImage, range: Range<usize>) -> Vec<Annotation> {
+    let mut overlay = Overlay {
+        buf,
+        image,
+        range,
+        annotations: Vec::new(),
+    };
+    overlay.headers();
+    overlay.data_directories();
+    overlay.sections();
+    overlay.imports();
+    overlay.resources();
+    let mut annotations = overlay.annotations;
+    annotations.sort_by_key(|annotation| (annotation.offset, std::cmp::Reverse(annotation.length)));
+    annotations
+}
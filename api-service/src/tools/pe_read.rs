@@ -19,8 +19,72 @@ pub struct PeStudy {
     pub field_name: String,
     pub field_size: String,
 }
+//报告中附加的章节
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReportSection {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+const CHINESE_DIGITS: [&str; 10] = ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+const CHINESE_UNITS: [&str; 4] = ["千", "百", "十", ""];
+
+//一万以内的数字，leading为true时10到19省略开头的"一"
+fn chinese_below_10000(number: usize, leading: bool) -> String {
+    if leading && (10..20).contains(&number) {
+        return format!(
+            "十{}",
+            if number == 10 {
+                ""
+            } else {
+                CHINESE_DIGITS[number - 10]
+            }
+        );
+    }
+    let digits = [
+        number / 1000,
+        number / 100 % 10,
+        number / 10 % 10,
+        number % 10,
+    ];
+    let mut result = String::new();
+    let mut pending_zero = false;
+    for (digit, unit) in digits.into_iter().zip(CHINESE_UNITS) {
+        if digit == 0 {
+            pending_zero = !result.is_empty();
+            continue;
+        }
+        if pending_zero {
+            result.push_str(CHINESE_DIGITS[0]);
+            pending_zero = false;
+        }
+        result.push_str(CHINESE_DIGITS[digit]);
+        result.push_str(unit);
+    }
+    result
+}
+
+//报告章节的中文序号
+fn chinese_number(number: usize) -> String {
+    if number == 0 {
+        return CHINESE_DIGITS[0].to_string();
+    }
+    if number < 10000 {
+        return chinese_below_10000(number, true);
+    }
+    let rest = number % 10000;
+    let mut result = format!("{}万", chinese_number(number / 10000));
+    if rest > 0 {
+        if rest < 1000 {
+            result.push_str(CHINESE_DIGITS[0]);
+        }
+        result.push_str(&chinese_below_10000(rest, false));
+    }
+    result
+}
+
 impl PeStudy {
-    pub fn generate_report(&self, result: String) -> String {
+    pub fn generate_report(&self, result: String, sections: &[ReportSection]) -> String {
         let mut report = String::new();
         report.push_str("===== PE Study 报告 =====\n\n");
         report.push_str("一、文件信息\n");
@@ -54,6 +118,16 @@ impl PeStudy {
                 String::from_utf8_lossy(&hex::decode(item).unwrap())
             ));
         }
+        for (index, section) in sections.iter().enumerate() {
+            report.push_str(&format!(
+                "\n{}、{}:\n",
+                chinese_number(index + 4),
+                section.title
+            ));
+            for line in &section.lines {
+                report.push_str(&format!("{}\n", line));
+            }
+        }
         report.push_str("=============================\n");
 
         report
@@ -302,3 +376,30 @@ pub fn read_exe_file(
         field_size: file_size,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chinese_section_numbers() {
+        let cases = [
+            (4, "四"),
+            (10, "十"),
+            (11, "十一"),
+            (20, "二十"),
+            (21, "二十一"),
+            (100, "一百"),
+            (101, "一百零一"),
+            (110, "一百一十"),
+            (1001, "一千零一"),
+            (1010, "一千零一十"),
+            (10000, "一万"),
+            (10005, "一万零五"),
+            (12345, "一万二千三百四十五"),
+        ];
+        for (number, text) in cases {
+            assert_eq!(chinese_number(number), text, "{}", number);
+        }
+    }
+}
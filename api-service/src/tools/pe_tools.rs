@@ -75,16 +75,27 @@ pub fn hex_compare2(a: &str, b: &str) -> bool {
     let b_10 = u32::from_str_radix(b, 16).unwrap();
     a_10 <= b_10
 }
-pub fn vec_to_string(bytes: &[u8]) -> String {
-    let mut hex_string = String::new();
-    for byte in bytes {
-        hex_string.push_str(&format!("{:02x}", byte));
-    }
-    hex_string
-}
 //字符串模糊查询
-#[allow(dead_code)]
 pub fn fuzzy_search(query: &str, target: &str) -> bool {
     // 转换查询字符串为小写，以进行不区分大小写的搜索
     query.to_lowercase().contains(&target.to_lowercase())
 }
+//香农熵，取值范围0-8
+pub fn shannon_entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+    let len = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
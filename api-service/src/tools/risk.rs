@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::anomaly::PeAnomaly;
+use crate::tools::file_hash::ListType;
+use crate::tools::knowledge::{KnowledgeHit, Severity};
+use crate::tools::packer::PackerHit;
+use crate::tools::signature::{SignatureState, SignatureStatus};
+
+pub const WEIGHT_KNOWLEDGE_LOW: &str = "knowledge_low";
+pub const WEIGHT_KNOWLEDGE_MEDIUM: &str = "knowledge_medium";
+pub const WEIGHT_KNOWLEDGE_HIGH: &str = "knowledge_high";
+pub const WEIGHT_KNOWLEDGE_CRITICAL: &str = "knowledge_critical";
+pub const WEIGHT_KNOWLEDGE_MAX: &str = "knowledge_max";
+pub const WEIGHT_ANOMALY_HIT: &str = "anomaly_hit";
+pub const WEIGHT_ANOMALY_MAX: &str = "anomaly_max";
+pub const WEIGHT_PACKER: &str = "packer";
+pub const WEIGHT_SIGNATURE_UNSIGNED: &str = "signature_unsigned";
+pub const WEIGHT_SIGNATURE_SIGNED: &str = "signature_signed";
+pub const WEIGHT_SIGNATURE_TAMPERED: &str = "signature_tampered";
+pub const WEIGHT_SIGNATURE_MALFORMED: &str = "signature_malformed";
+pub const WEIGHT_RULE_HIT: &str = "rule_hit";
+pub const WEIGHT_RULE_MAX: &str = "rule_max";
+pub const WEIGHT_CAPABILITY_HIT: &str = "capability_hit";
+pub const WEIGHT_CAPABILITY_MAX: &str = "capability_max";
+pub const THRESHOLD_SUSPICIOUS: &str = "threshold_suspicious";
+pub const THRESHOLD_MALICIOUS: &str = "threshold_malicious";
+
+// (权重名称, 默认值, 说明)
+pub const DEFAULT_WEIGHTS: [(&str, i64, &str); 18] = [
+    (WEIGHT_KNOWLEDGE_LOW, 2, "每个低危敏感函数"),
+    (WEIGHT_KNOWLEDGE_MEDIUM, 6, "每个中危敏感函数"),
+    (WEIGHT_KNOWLEDGE_HIGH, 12, "每个高危敏感函数"),
+    (WEIGHT_KNOWLEDGE_CRITICAL, 20, "每个严重敏感函数"),
+    (WEIGHT_KNOWLEDGE_MAX, 30, "敏感函数得分上限"),
+    (WEIGHT_ANOMALY_HIT, 5, "每个PE结构异常"),
+    (WEIGHT_ANOMALY_MAX, 25, "PE结构异常得分上限"),
+    (WEIGHT_PACKER, 20, "检测到加壳"),
+    (WEIGHT_SIGNATURE_UNSIGNED, 10, "文件未签名"),
+    (WEIGHT_SIGNATURE_SIGNED, -15, "签名摘要一致"),
+    (WEIGHT_SIGNATURE_TAMPERED, 30, "签名后被修改"),
+    (WEIGHT_SIGNATURE_MALFORMED, 15, "签名结构错误"),
+    (WEIGHT_RULE_HIT, 15, "每条命中的特征规则"),
+    (WEIGHT_RULE_MAX, 45, "特征规则得分上限"),
+    (WEIGHT_CAPABILITY_HIT, 10, "每项检测到的能力"),
+    (WEIGHT_CAPABILITY_MAX, 40, "能力得分上限"),
+    (THRESHOLD_SUSPICIOUS, 30, "达到该分数判定为可疑"),
+    (THRESHOLD_MALICIOUS, 70, "达到该分数判定为恶意"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskWeight {
+    pub key: String,
+    pub value: i64,
+    pub default_value: i64,
+    pub description: String,
+}
+
+//合并默认权重与已保存的权重
+pub fn merge_weights(saved: &HashMap<String, i64>) -> Vec<RiskWeight> {
+    DEFAULT_WEIGHTS
+        .iter()
+        .map(|(key, default_value, description)| RiskWeight {
+            key: key.to_string(),
+            value: saved.get(*key).copied().unwrap_or(*default_value),
+            default_value: *default_value,
+            description: description.to_string(),
+        })
+        .collect()
+}
+
+//校验待保存的权重，返回错误描述
+pub fn validate_weights(weights: &HashMap<String, i64>) -> Result<(), String> {
+    for (key, value) in weights {
+        if !DEFAULT_WEIGHTS.iter().any(|(name, _, _)| name == key) {
+            return Err(format!("未知的权重: {}", key));
+        }
+        if key.starts_with("threshold_") {
+            if !(1..=100).contains(value) {
+                return Err(format!("阈值{}必须在1到100之间", key));
+            }
+        } else if !(-100..=100).contains(value) {
+            return Err(format!("权重{}必须在-100到100之间", key));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Clean,
+    Suspicious,
+    Malicious,
+}
+
+impl Verdict {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Verdict::Clean => "安全",
+            Verdict::Suspicious => "可疑",
+            Verdict::Malicious => "恶意",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskContribution {
+    pub category: String,
+    pub item: String,
+    pub points: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskScore {
+    pub score: u32,
+    pub verdict: Verdict,
+    // 命中知识库条目的最高严重级别
+    pub severity: Option<Severity>,
+    pub breakdown: Vec<RiskContribution>,
+}
+
+pub struct RiskInput<'a> {
+    pub knowledge: &'a [KnowledgeHit],
+    pub anomalies: &'a [PeAnomaly],
+    pub packers: &'a [PackerHit],
+    // 非PE文件没有签名状态
+    pub signature: Option<&'a SignatureStatus>,
+    pub rule_hits: &'a [String],
+    pub capabilities: &'a [String],
+}
+
+struct Scorer<'a> {
+    weights: &'a HashMap<String, i64>,
+    breakdown: Vec<RiskContribution>,
+}
+
+impl Scorer<'_> {
+    fn weight(&self, key: &str) -> i64 {
+        self.weights.get(key).copied().unwrap_or_else(|| {
+            DEFAULT_WEIGHTS
+                .iter()
+                .find(|(name, _, _)| *name == key)
+                .map_or(0, |(_, value, _)| *value)
+        })
+    }
+
+    //同类结果逐个计分，超过上限后的结果记为0分
+    fn add_weighted(&mut self, category: &str, items: &[(String, i64)], max_key: &str) {
+        let max = self.weight(max_key);
+        let mut total = 0;
+        for (item, weight) in items {
+            let points = if *weight >= 0 {
+                (*weight).min((max - total).max(0))
+            } else {
+                *weight
+            };
+            total += points;
+            self.breakdown.push(RiskContribution {
+                category: category.to_string(),
+                item: item.clone(),
+                points,
+            });
+        }
+    }
+
+    fn add_items(&mut self, category: &str, items: &[String], hit_key: &str, max_key: &str) {
+        let weight = self.weight(hit_key);
+        let items = items
+            .iter()
+            .map(|item| (item.clone(), weight))
+            .collect::<Vec<_>>();
+        self.add_weighted(category, &items, max_key);
+    }
+
+    fn add(&mut self, category: &str, item: String, key: &str) {
+        self.breakdown.push(RiskContribution {
+            category: category.to_string(),
+            item,
+            points: self.weight(key),
+        });
+    }
+}
+
+//按权重计算0-100的风险分数，并给出每项结果的得分明细
+pub fn score(input: &RiskInput, weights: &HashMap<String, i64>) -> RiskScore {
+    let mut scorer = Scorer {
+        weights,
+        breakdown: Vec::new(),
+    };
+    // 提示信息级别的条目不计分
+    let knowledge = input
+        .knowledge
+        .iter()
+        .filter_map(|hit| {
+            let key = match hit.severity {
+                Severity::Info => return None,
+                Severity::Low => WEIGHT_KNOWLEDGE_LOW,
+                Severity::Medium => WEIGHT_KNOWLEDGE_MEDIUM,
+                Severity::High => WEIGHT_KNOWLEDGE_HIGH,
+                Severity::Critical => WEIGHT_KNOWLEDGE_CRITICAL,
+            };
+            Some((
+                format!("{}（{}）", hit.func_name, hit.severity.display_name()),
+                scorer.weight(key),
+            ))
+        })
+        .collect::<Vec<_>>();
+    scorer.add_weighted("敏感函数", &knowledge, WEIGHT_KNOWLEDGE_MAX);
+    let anomalies = input
+        .anomalies
+        .iter()
+        .map(|item| item.description.clone())
+        .collect::<Vec<_>>();
+    scorer.add_items(
+        "结构异常",
+        &anomalies,
+        WEIGHT_ANOMALY_HIT,
+        WEIGHT_ANOMALY_MAX,
+    );
+    if !input.packers.is_empty() {
+        let names = input
+            .packers
+            .iter()
+            .map(|hit| hit.name.clone())
+            .collect::<Vec<_>>();
+        scorer.add("加壳", names.join(", "), WEIGHT_PACKER);
+    }
+    if let Some(signature) = input.signature {
+        let key = match signature.state {
+            SignatureState::Unsigned => WEIGHT_SIGNATURE_UNSIGNED,
+            SignatureState::Signed => WEIGHT_SIGNATURE_SIGNED,
+            SignatureState::Tampered => WEIGHT_SIGNATURE_TAMPERED,
+            SignatureState::Malformed => WEIGHT_SIGNATURE_MALFORMED,
+        };
+        scorer.add("签名", signature.message.clone(), key);
+    }
+    scorer.add_items(
+        "特征规则",
+        input.rule_hits,
+        WEIGHT_RULE_HIT,
+        WEIGHT_RULE_MAX,
+    );
+    scorer.add_items(
+        "能力",
+        input.capabilities,
+        WEIGHT_CAPABILITY_HIT,
+        WEIGHT_CAPABILITY_MAX,
+    );
+    let total: i64 = scorer.breakdown.iter().map(|item| item.points).sum();
+    let score = total.clamp(0, 100);
+    let verdict = if score >= scorer.weight(THRESHOLD_MALICIOUS) {
+        Verdict::Malicious
+    } else if score >= scorer.weight(THRESHOLD_SUSPICIOUS) {
+        Verdict::Suspicious
+    } else {
+        Verdict::Clean
+    };
+    // 命中严重条目至少判定为恶意，命中高危条目至少判定为可疑
+    let severity = input.knowledge.iter().map(|hit| hit.severity).max();
+    let verdict = match severity {
+        Some(Severity::Critical) => Verdict::Malicious,
+        Some(Severity::High) => verdict.max(Verdict::Suspicious),
+        _ => verdict,
+    };
+    RiskScore {
+        score: score as u32,
+        verdict,
+        severity,
+        breakdown: scorer.breakdown,
+    }
+}
+
+//命中哈希名单时直接给出判定，黑名单为恶意，白名单为安全
+pub fn listed_score(list_type: ListType, reason: String) -> RiskScore {
+    let (score, verdict) = match list_type {
+        ListType::Block => (100, Verdict::Malicious),
+        ListType::Allow => (0, Verdict::Clean),
+    };
+    RiskScore {
+        score,
+        verdict,
+        severity: None,
+        breakdown: vec![RiskContribution {
+            category: "哈希名单".to_string(),
+            item: reason,
+            points: score as i64,
+        }],
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::tools::pe_image::{read_u16, read_u32, PeImage, DIRECTORY_SECURITY};
+
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+// 1.3.6.1.4.1.311.2.1.4 SPC_INDIRECT_DATA_OBJID
+const OID_SPC_INDIRECT_DATA: &[u8] = &[
+    0x06, 0x0A, 0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x04,
+];
+const OID_SHA1: &[u8] = &[0x06, 0x05, 0x2B, 0x0E, 0x03, 0x02, 0x1A];
+const OID_SHA256: &[u8] = &[
+    0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureState {
+    Unsigned,
+    // 文件摘要与签名一致，证书链未校验
+    Signed,
+    // 文件摘要与签名不一致，签名后被修改
+    Tampered,
+    Malformed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureStatus {
+    pub state: SignatureState,
+    pub digest_algorithm: Option<String>,
+    pub message: String,
+}
+
+fn status(state: SignatureState, digest_algorithm: Option<&str>, message: &str) -> SignatureStatus {
+    SignatureStatus {
+        state,
+        digest_algorithm: digest_algorithm.map(|name| name.to_string()),
+        message: message.to_string(),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| index + from)
+}
+
+//从PKCS#7 SignedData中取出SpcIndirectDataContent里的摘要算法与摘要值
+fn signed_digest(pkcs7: &[u8]) -> Option<(&'static str, &[u8])> {
+    let content = find(pkcs7, OID_SPC_INDIRECT_DATA, 0)? + OID_SPC_INDIRECT_DATA.len();
+    let (name, oid, position) = [("sha1", OID_SHA1), ("sha256", OID_SHA256)]
+        .into_iter()
+        .filter_map(|(name, oid)| Some((name, oid, find(pkcs7, oid, content)?)))
+        .min_by_key(|(_, _, position)| *position)?;
+    let mut offset = position + oid.len();
+    // AlgorithmIdentifier 中可选的 NULL 参数
+    if pkcs7.get(offset..offset + 2) == Some(&[0x05, 0x00]) {
+        offset += 2;
+    }
+    if *pkcs7.get(offset)? != 0x04 {
+        return None;
+    }
+    let len = *pkcs7.get(offset + 1)? as usize;
+    Some((name, pkcs7.get(offset + 2..offset + 2 + len)?))
+}
+
+//Authenticode摘要：跳过CheckSum、证书表目录项与证书表本身
+fn authenticode_hash<D: Digest>(
+    image: &PeImage,
+    buf: &[u8],
+    cert_offset: usize,
+    cert_end: usize,
+) -> Vec<u8> {
+    let checksum_offset = image.checksum_offset();
+    let entry_offset = image.data_directory_entry_offset(DIRECTORY_SECURITY);
+    let mut digest = D::new();
+    digest.update(&buf[..checksum_offset]);
+    digest.update(&buf[checksum_offset + 4..entry_offset]);
+    digest.update(&buf[entry_offset + 8..cert_offset]);
+    digest.update(&buf[cert_end..]);
+    digest.finalize().to_vec()
+}
+
+//检查Authenticode签名状态，只校验文件摘要，不校验证书链
+pub fn check_signature(image: &PeImage, buf: &[u8]) -> SignatureStatus {
+    let Some(directory) = image.data_directory(DIRECTORY_SECURITY) else {
+        return status(SignatureState::Unsigned, None, "文件未签名");
+    };
+    // 证书表目录中的地址是文件偏移而不是RVA
+    let cert_offset = directory.rva as usize;
+    let cert_end = cert_offset + directory.size as usize;
+    let entry_offset = image.data_directory_entry_offset(DIRECTORY_SECURITY);
+    if cert_end > buf.len() || cert_offset < entry_offset + 8 {
+        return status(SignatureState::Malformed, None, "证书表超出文件范围");
+    }
+    let (Some(length), Some(cert_type)) =
+        (read_u32(buf, cert_offset), read_u16(buf, cert_offset + 6))
+    else {
+        return status(SignatureState::Malformed, None, "证书表头部不完整");
+    };
+    let length = length as usize;
+    if length < 8 || cert_offset + length > cert_end {
+        return status(SignatureState::Malformed, None, "证书长度错误");
+    }
+    if cert_type != WIN_CERT_TYPE_PKCS_SIGNED_DATA {
+        return status(SignatureState::Malformed, None, "不支持的证书类型");
+    }
+    let Some((algorithm, expected)) = signed_digest(&buf[cert_offset + 8..cert_offset + length])
+    else {
+        return status(SignatureState::Malformed, None, "无法解析签名中的文件摘要");
+    };
+    let actual = match algorithm {
+        "sha1" => authenticode_hash::<Sha1>(image, buf, cert_offset, cert_end),
+        _ => authenticode_hash::<Sha256>(image, buf, cert_offset, cert_end),
+    };
+    if actual == expected {
+        status(
+            SignatureState::Signed,
+            Some(algorithm),
+            "文件摘要与签名一致（未校验证书链）",
+        )
+    } else {
+        status(
+            SignatureState::Tampered,
+            Some(algorithm),
+            "文件摘要与签名不一致，文件在签名后被修改",
+        )
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tools::pe_image::PeImage;
+
+pub const DEFAULT_MIN_LEN: usize = 4;
+pub const MAX_STRING_LEN: usize = 4096;
+pub const MAX_STRING_COUNT: usize = 200_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StringEncoding {
+    Ascii,
+    Utf16le,
+}
+
+impl StringEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StringEncoding::Ascii => "ascii",
+            StringEncoding::Utf16le => "utf16le",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedString {
+    pub offset: usize,
+    pub rva: Option<u32>,
+    pub section: Option<String>,
+    pub encoding: StringEncoding,
+    pub value: String,
+}
+
+fn is_printable(byte: u8) -> bool {
+    (0x20..=0x7E).contains(&byte) || byte == b'\t'
+}
+
+//ASCII可打印字符串
+pub fn extract_ascii(buf: &[u8], min_len: usize) -> Vec<(usize, String)> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut len = 0;
+    for (index, byte) in buf.iter().enumerate() {
+        if is_printable(*byte) {
+            if len == 0 {
+                start = index;
+            }
+            len += 1;
+            continue;
+        }
+        if len >= min_len {
+            let end = start + len.min(MAX_STRING_LEN);
+            result.push((start, String::from_utf8_lossy(&buf[start..end]).to_string()));
+        }
+        len = 0;
+    }
+    if len >= min_len {
+        let end = start + len.min(MAX_STRING_LEN);
+        result.push((start, String::from_utf8_lossy(&buf[start..end]).to_string()));
+    }
+    result
+}
+
+//UTF-16LE字符串，只识别ASCII范围内的字符
+pub fn extract_utf16le(buf: &[u8], min_len: usize) -> Vec<(usize, String)> {
+    let mut result = Vec::new();
+    let mut index = 0;
+    while index + 1 < buf.len() {
+        let mut value = String::new();
+        let mut cursor = index;
+        while cursor + 1 < buf.len() && buf[cursor + 1] == 0 && is_printable(buf[cursor]) {
+            if value.len() < MAX_STRING_LEN {
+                value.push(buf[cursor] as char);
+            }
+            cursor += 2;
+        }
+        if cursor == index {
+            index += 1;
+            continue;
+        }
+        if value.len() >= min_len {
+            result.push((index, value));
+        }
+        index = cursor;
+    }
+    result
+}
+
+//按文件偏移排序，并补充RVA与所属节信息
+pub fn extract_strings(
+    buf: &[u8],
+    image: Option<&PeImage>,
+    min_len: usize,
+) -> Vec<ExtractedString> {
+    let min_len = min_len.max(1);
+    let mut strings = extract_ascii(buf, min_len)
+        .into_iter()
+        .map(|(offset, value)| (offset, StringEncoding::Ascii, value))
+        .chain(
+            extract_utf16le(buf, min_len)
+                .into_iter()
+                .map(|(offset, value)| (offset, StringEncoding::Utf16le, value)),
+        )
+        .map(|(offset, encoding, value)| ExtractedString {
+            offset,
+            rva: image.and_then(|image| image.offset_to_rva(offset)),
+            section: image
+                .and_then(|image| image.section_by_offset(offset))
+                .map(|section| section.name.clone()),
+            encoding,
+            value,
+        })
+        .collect::<Vec<_>>();
+    strings.sort_by_key(|item| item.offset);
+    strings.truncate(MAX_STRING_COUNT);
+    strings
+}
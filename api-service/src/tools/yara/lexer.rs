@@ -0,0 +1,422 @@
+use crate::tools::yara::RuleError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    // $a / #a / @a / !a，名称不含前缀，匿名字符串名称为空
+    StringId(String),
+    StringCount(String),
+    StringOffset(String),
+    StringLength(String),
+    // 以*结尾的字符串集合，如 $a*
+    StringWildcard(String),
+    Int(i64),
+    Text(Vec<u8>),
+    Regex { pattern: String, flags: String },
+    Hex(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Colon,
+    Comma,
+    Assign,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Plus,
+    Minus,
+    Star,
+    Backslash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    Shl,
+    Shr,
+    DotDot,
+}
+
+#[derive(Debug, Clone)]
+pub struct Spanned {
+    pub token: Token,
+    pub line: usize,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.get(self.position).copied()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> RuleError {
+        RuleError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), RuleError> {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let line = self.line;
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match (self.peek(), self.peek_at(1)) {
+                            (Some('*'), Some('/')) => {
+                                self.bump();
+                                self.bump();
+                                break;
+                            }
+                            (Some(_), _) => {
+                                self.bump();
+                            }
+                            (None, _) => {
+                                return Err(RuleError {
+                                    line,
+                                    message: "注释未闭合".to_string(),
+                                })
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut value = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            value.push(c);
+            self.bump();
+        }
+        value
+    }
+
+    fn number(&mut self) -> Result<i64, RuleError> {
+        let mut text = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_alphanumeric()) {
+            text.push(c);
+            self.bump();
+        }
+        let (digits, multiplier) = if let Some(digits) = text.strip_suffix("KB") {
+            (digits, 1024)
+        } else if let Some(digits) = text.strip_suffix("MB") {
+            (digits, 1024 * 1024)
+        } else {
+            (text.as_str(), 1)
+        };
+        let value = if let Some(hex) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            i64::from_str_radix(hex, 16)
+        } else if let Some(octal) = digits.strip_prefix("0o") {
+            i64::from_str_radix(octal, 8)
+        } else {
+            digits.parse::<i64>()
+        };
+        value
+            .map(|value| value * multiplier)
+            .map_err(|_| self.error(format!("无效的数字: {}", text)))
+    }
+
+    fn text(&mut self) -> Result<Vec<u8>, RuleError> {
+        let line = self.line;
+        self.bump();
+        let mut value = Vec::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(value),
+                Some('\\') => match self.bump() {
+                    Some('n') => value.push(b'\n'),
+                    Some('r') => value.push(b'\r'),
+                    Some('t') => value.push(b'\t'),
+                    Some('"') => value.push(b'"'),
+                    Some('\\') => value.push(b'\\'),
+                    Some('x') => {
+                        let hex = [self.bump(), self.bump()]
+                            .into_iter()
+                            .flatten()
+                            .collect::<String>();
+                        let byte = u8::from_str_radix(&hex, 16)
+                            .map_err(|_| self.error(format!("无效的转义字符: \\x{}", hex)))?;
+                        value.push(byte);
+                    }
+                    Some(c) => return Err(self.error(format!("无效的转义字符: \\{}", c))),
+                    None => break,
+                },
+                Some('\n') | None => break,
+                Some(c) => {
+                    let mut buf = [0; 4];
+                    value.extend(c.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+        Err(RuleError {
+            line,
+            message: "字符串未闭合".to_string(),
+        })
+    }
+
+    fn regex(&mut self) -> Result<Token, RuleError> {
+        let line = self.line;
+        self.bump();
+        let mut pattern = String::new();
+        loop {
+            match self.bump() {
+                Some('/') => break,
+                Some('\\') => {
+                    // 转义的斜杠不结束正则，其他转义原样保留
+                    match self.bump() {
+                        Some('/') => pattern.push('/'),
+                        Some(c) => {
+                            pattern.push('\\');
+                            pattern.push(c);
+                        }
+                        None => {
+                            return Err(RuleError {
+                                line,
+                                message: "正则表达式未闭合".to_string(),
+                            })
+                        }
+                    }
+                }
+                Some('\n') | None => {
+                    return Err(RuleError {
+                        line,
+                        message: "正则表达式未闭合".to_string(),
+                    })
+                }
+                Some(c) => pattern.push(c),
+            }
+        }
+        let mut flags = String::new();
+        while let Some(c) = self.peek().filter(|c| *c == 'i' || *c == 's') {
+            flags.push(c);
+            self.bump();
+        }
+        Ok(Token::Regex { pattern, flags })
+    }
+
+    fn hex(&mut self) -> Result<String, RuleError> {
+        let line = self.line;
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('}') => return Ok(value),
+                Some(c) => value.push(c),
+                None => {
+                    return Err(RuleError {
+                        line,
+                        message: "十六进制串未闭合".to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    fn string_identifier(&mut self) -> (String, bool) {
+        self.bump();
+        let name = self.identifier();
+        if self.peek() == Some('*') {
+            self.bump();
+            return (name, true);
+        }
+        (name, false)
+    }
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Spanned>, RuleError> {
+    let mut lexer = Lexer {
+        chars: source.chars().collect(),
+        position: 0,
+        line: 1,
+    };
+    let mut tokens: Vec<Spanned> = Vec::new();
+    loop {
+        lexer.skip_whitespace_and_comments()?;
+        let line = lexer.line;
+        let Some(c) = lexer.peek() else {
+            break;
+        };
+        let after_assign = tokens
+            .last()
+            .is_some_and(|last| last.token == Token::Assign);
+        let token = match c {
+            '"' => Token::Text(lexer.text()?),
+            '/' => lexer.regex()?,
+            // 字符串定义中等号后面的花括号是十六进制串
+            '{' if after_assign => Token::Hex(lexer.hex()?),
+            '$' => match lexer.string_identifier() {
+                (name, true) => Token::StringWildcard(name),
+                (name, false) => Token::StringId(name),
+            },
+            '#' => Token::StringCount(lexer.string_identifier().0),
+            '@' => Token::StringOffset(lexer.string_identifier().0),
+            '!' if lexer.peek_at(1) == Some('=') => {
+                lexer.bump();
+                lexer.bump();
+                Token::Ne
+            }
+            '!' => Token::StringLength(lexer.string_identifier().0),
+            c if c.is_ascii_digit() => Token::Int(lexer.number()?),
+            c if c.is_ascii_alphabetic() || c == '_' => Token::Ident(lexer.identifier()),
+            _ => {
+                lexer.bump();
+                let (token, double) = match (c, lexer.peek()) {
+                    ('=', Some('=')) => (Token::Eq, true),
+                    ('<', Some('=')) => (Token::Le, true),
+                    ('>', Some('=')) => (Token::Ge, true),
+                    ('<', Some('<')) => (Token::Shl, true),
+                    ('>', Some('>')) => (Token::Shr, true),
+                    ('.', Some('.')) => (Token::DotDot, true),
+                    ('=', _) => (Token::Assign, false),
+                    ('<', _) => (Token::Lt, false),
+                    ('>', _) => (Token::Gt, false),
+                    ('(', _) => (Token::LParen, false),
+                    (')', _) => (Token::RParen, false),
+                    ('[', _) => (Token::LBracket, false),
+                    (']', _) => (Token::RBracket, false),
+                    ('{', _) => (Token::LBrace, false),
+                    ('}', _) => (Token::RBrace, false),
+                    (':', _) => (Token::Colon, false),
+                    (',', _) => (Token::Comma, false),
+                    ('+', _) => (Token::Plus, false),
+                    ('-', _) => (Token::Minus, false),
+                    ('*', _) => (Token::Star, false),
+                    ('\\', _) => (Token::Backslash, false),
+                    ('%', _) => (Token::Percent, false),
+                    ('&', _) => (Token::Ampersand, false),
+                    ('|', _) => (Token::Pipe, false),
+                    ('^', _) => (Token::Caret, false),
+                    ('~', _) => (Token::Tilde, false),
+                    _ => return Err(lexer.error(format!("无法识别的字符: {}", c))),
+                };
+                if double {
+                    lexer.bump();
+                }
+                token
+            }
+        };
+        tokens.push(Spanned { token, line });
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
+    #[test]
+    fn hex_string_after_assign() {
+        assert_eq!(
+            tokens("$a = { 4D 5A ?? [2-4] }"),
+            vec![
+                Token::StringId("a".to_string()),
+                Token::Assign,
+                Token::Hex(" 4D 5A ?? [2-4] ".to_string()),
+            ]
+        );
+        // 其他位置的花括号是规则体
+        assert_eq!(tokens("{ }"), vec![Token::LBrace, Token::RBrace]);
+    }
+
+    #[test]
+    fn numbers_and_operators() {
+        assert_eq!(
+            tokens("0x10 2KB 1MB 0o17 <= << != !a #b @c $d*"),
+            vec![
+                Token::Int(16),
+                Token::Int(2048),
+                Token::Int(1024 * 1024),
+                Token::Int(15),
+                Token::Le,
+                Token::Shl,
+                Token::Ne,
+                Token::StringLength("a".to_string()),
+                Token::StringCount("b".to_string()),
+                Token::StringOffset("c".to_string()),
+                Token::StringWildcard("d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_escapes_and_regex() {
+        assert_eq!(
+            tokens(r#""a\x41\n\"" /ab\/c\d/is"#),
+            vec![
+                Token::Text(b"aA\n\"".to_vec()),
+                Token::Regex {
+                    pattern: r"ab/c\d".to_string(),
+                    flags: "is".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_advance_line_numbers() {
+        let spanned = tokenize("// comment\n/* a\nb */ rule").unwrap();
+        assert_eq!(spanned.len(), 1);
+        assert_eq!(spanned[0].line, 3);
+    }
+
+    #[test]
+    fn malformed_input_reports_line() {
+        let err = tokenize("rule\n$a = \"abc\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(tokenize("$a = { 4D 5A").is_err());
+        assert!(tokenize("/* never closed").is_err());
+        assert!(tokenize("0xZZ").is_err());
+        assert!(tokenize(r#""\q""#).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::yara::parser::{BinOp, CmpOp, Expr, Quantifier, Rule, StringDef};
+
+pub mod lexer;
+pub mod parser;
+
+// 单个字符串最多记录的命中数量
+const MAX_STRING_MATCHES: usize = 1000;
+// 结果中每个字符串最多返回的命中数量
+const MAX_REPORTED_MATCHES: usize = 20;
+const MAX_MATCH_DATA: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct RuleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "第{}行: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StringMatch {
+    pub identifier: String,
+    pub offset: usize,
+    pub length: usize,
+    // 命中内容的16进制，过长时截断
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub tags: Vec<String>,
+    pub meta: serde_json::Map<String, serde_json::Value>,
+    pub strings: Vec<StringMatch>,
+}
+
+//编译规则源码，语法错误时返回出错行号
+pub fn compile(source: &str) -> Result<Vec<Rule>, RuleError> {
+    let tokens = lexer::tokenize(source)?;
+    parser::parse(&tokens)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Int(i64),
+    Bool(bool),
+    Undefined,
+}
+
+impl Value {
+    fn as_bool(self) -> bool {
+        match self {
+            Value::Int(value) => value != 0,
+            Value::Bool(value) => value,
+            Value::Undefined => false,
+        }
+    }
+
+    fn as_int(self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(value),
+            Value::Bool(value) => Some(value as i64),
+            Value::Undefined => None,
+        }
+    }
+}
+
+struct ScanContext<'a> {
+    buf: &'a [u8],
+    entrypoint: Option<usize>,
+    // 当前规则每个字符串的命中 (偏移, 长度)
+    matches: Vec<Vec<(usize, usize)>>,
+    rule_results: &'a [bool],
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+//查找全部命中（包括相互重叠的命中）
+fn find_matches(definition: &StringDef, buf: &[u8]) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    while start < buf.len() && result.len() < MAX_STRING_MATCHES {
+        let Some(found) = definition.regex.find_at(buf, start) else {
+            break;
+        };
+        start = found.start() + 1;
+        if found.is_empty() {
+            continue;
+        }
+        if definition.fullword {
+            let bytes = found.as_bytes();
+            let wide = definition.wide && bytes.len() >= 2 && bytes[1] == 0;
+            let step = if wide { 2 } else { 1 };
+            let before = found
+                .start()
+                .checked_sub(step)
+                .map(|index| buf[index])
+                .filter(|_| !wide || buf[found.start() - 1] == 0);
+            let after = buf
+                .get(found.end())
+                .copied()
+                .filter(|_| !wide || buf.get(found.end() + 1) == Some(&0));
+            if before.is_some_and(is_word_byte) || after.is_some_and(is_word_byte) {
+                continue;
+            }
+        }
+        result.push((found.start(), found.len()));
+    }
+    result
+}
+
+fn read_int(buf: &[u8], offset: i64, size: usize, signed: bool, big_endian: bool) -> Value {
+    let Ok(offset) = usize::try_from(offset) else {
+        return Value::Undefined;
+    };
+    let Some(bytes) = buf.get(offset..offset.saturating_add(size)) else {
+        return Value::Undefined;
+    };
+    let mut value: u64 = 0;
+    for index in 0..size {
+        let byte = if big_endian {
+            bytes[index]
+        } else {
+            bytes[size - 1 - index]
+        };
+        value = value << 8 | byte as u64;
+    }
+    if signed {
+        let shift = 64 - size * 8;
+        return Value::Int(((value << shift) as i64) >> shift);
+    }
+    Value::Int(value as i64)
+}
+
+impl ScanContext<'_> {
+    fn nth_match(&self, index: usize, nth: &Expr) -> Option<(usize, usize)> {
+        let nth = self.eval(nth).as_int()?;
+        let nth = usize::try_from(nth).ok()?.checked_sub(1)?;
+        self.matches[index].get(nth).copied()
+    }
+
+    fn eval(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Bool(value) => Value::Bool(*value),
+            Expr::Int(value) => Value::Int(*value),
+            Expr::Filesize => Value::Int(self.buf.len() as i64),
+            Expr::Entrypoint => self
+                .entrypoint
+                .map_or(Value::Undefined, |value| Value::Int(value as i64)),
+            Expr::Not(inner) => match self.eval(inner) {
+                Value::Undefined => Value::Undefined,
+                value => Value::Bool(!value.as_bool()),
+            },
+            Expr::And(left, right) => {
+                Value::Bool(self.eval(left).as_bool() && self.eval(right).as_bool())
+            }
+            Expr::Or(left, right) => {
+                Value::Bool(self.eval(left).as_bool() || self.eval(right).as_bool())
+            }
+            Expr::Cmp(op, left, right) => {
+                let (Some(left), Some(right)) =
+                    (self.eval(left).as_int(), self.eval(right).as_int())
+                else {
+                    return Value::Undefined;
+                };
+                Value::Bool(match op {
+                    CmpOp::Eq => left == right,
+                    CmpOp::Ne => left != right,
+                    CmpOp::Lt => left < right,
+                    CmpOp::Le => left <= right,
+                    CmpOp::Gt => left > right,
+                    CmpOp::Ge => left >= right,
+                })
+            }
+            Expr::Binary(op, left, right) => {
+                let (Some(left), Some(right)) =
+                    (self.eval(left).as_int(), self.eval(right).as_int())
+                else {
+                    return Value::Undefined;
+                };
+                let value = match op {
+                    BinOp::Add => Some(left.wrapping_add(right)),
+                    BinOp::Sub => Some(left.wrapping_sub(right)),
+                    BinOp::Mul => Some(left.wrapping_mul(right)),
+                    BinOp::Div => left.checked_div(right),
+                    BinOp::Mod => left.checked_rem(right),
+                    BinOp::BitAnd => Some(left & right),
+                    BinOp::BitOr => Some(left | right),
+                    BinOp::BitXor => Some(left ^ right),
+                    BinOp::Shl => Some(
+                        u32::try_from(right)
+                            .map_or(0, |shift| left.checked_shl(shift).unwrap_or(0)),
+                    ),
+                    BinOp::Shr => Some(
+                        u32::try_from(right)
+                            .map_or(0, |shift| left.checked_shr(shift).unwrap_or(0)),
+                    ),
+                };
+                value.map_or(Value::Undefined, Value::Int)
+            }
+            Expr::Neg(inner) => self
+                .eval(inner)
+                .as_int()
+                .map_or(Value::Undefined, |value| Value::Int(value.wrapping_neg())),
+            Expr::BitNot(inner) => self
+                .eval(inner)
+                .as_int()
+                .map_or(Value::Undefined, |value| Value::Int(!value)),
+            Expr::StringMatch(index) => Value::Bool(!self.matches[*index].is_empty()),
+            Expr::StringAt(index, offset) => match self.eval(offset).as_int() {
+                Some(offset) => Value::Bool(
+                    self.matches[*index]
+                        .iter()
+                        .any(|(start, _)| *start as i64 == offset),
+                ),
+                None => Value::Undefined,
+            },
+            Expr::StringIn(index, low, high) => {
+                match (self.eval(low).as_int(), self.eval(high).as_int()) {
+                    (Some(low), Some(high)) => Value::Bool(
+                        self.matches[*index]
+                            .iter()
+                            .any(|(start, _)| (low..=high).contains(&(*start as i64))),
+                    ),
+                    _ => Value::Undefined,
+                }
+            }
+            Expr::StringCount(index) => Value::Int(self.matches[*index].len() as i64),
+            Expr::StringOffset(index, nth) => self
+                .nth_match(*index, nth)
+                .map_or(Value::Undefined, |(start, _)| Value::Int(start as i64)),
+            Expr::StringLength(index, nth) => self
+                .nth_match(*index, nth)
+                .map_or(Value::Undefined, |(_, length)| Value::Int(length as i64)),
+            Expr::ReadInt {
+                size,
+                signed,
+                big_endian,
+                offset,
+            } => match self.eval(offset).as_int() {
+                Some(offset) => read_int(self.buf, offset, *size, *signed, *big_endian),
+                None => Value::Undefined,
+            },
+            Expr::Of(quantifier, set) => {
+                let matched = set
+                    .iter()
+                    .filter(|index| !self.matches[**index].is_empty())
+                    .count();
+                Value::Bool(match quantifier {
+                    Quantifier::All => matched == set.len(),
+                    Quantifier::Any => matched > 0,
+                    Quantifier::None => matched == 0,
+                    Quantifier::Count(count) => match self.eval(count).as_int() {
+                        Some(count) => matched as i64 >= count,
+                        None => return Value::Undefined,
+                    },
+                })
+            }
+            Expr::RuleRef(index) => Value::Bool(self.rule_results[*index]),
+        }
+    }
+}
+
+//依次执行规则，entrypoint为入口点的文件偏移
+pub fn scan(rules: &[Rule], buf: &[u8], entrypoint: Option<usize>) -> Vec<RuleMatch> {
+    let mut rule_results: Vec<bool> = Vec::with_capacity(rules.len());
+    let mut result = Vec::new();
+    for rule in rules {
+        let matches = rule
+            .strings
+            .iter()
+            .map(|definition| find_matches(definition, buf))
+            .collect::<Vec<_>>();
+        let context = ScanContext {
+            buf,
+            entrypoint,
+            matches,
+            rule_results: &rule_results,
+        };
+        let matched = context.eval(&rule.condition).as_bool();
+        let matches = context.matches;
+        rule_results.push(matched);
+        if !matched || rule.private {
+            continue;
+        }
+        let strings = rule
+            .strings
+            .iter()
+            .zip(matches)
+            .flat_map(|(definition, matches)| {
+                matches
+                    .into_iter()
+                    .take(MAX_REPORTED_MATCHES)
+                    .map(|(offset, length)| StringMatch {
+                        identifier: format!("${}", definition.identifier),
+                        offset,
+                        length,
+                        data: hex::encode_upper(&buf[offset..offset + length.min(MAX_MATCH_DATA)]),
+                    })
+            })
+            .collect();
+        result.push(RuleMatch {
+            rule: rule.name.clone(),
+            tags: rule.tags.clone(),
+            meta: rule.meta.iter().cloned().collect(),
+            strings,
+        });
+    }
+    result
+}
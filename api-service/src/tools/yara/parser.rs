@@ -0,0 +1,860 @@
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::tools::yara::lexer::{Spanned, Token};
+use crate::tools::yara::RuleError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+}
+
+#[derive(Debug, Clone)]
+pub enum Quantifier {
+    All,
+    Any,
+    None,
+    Count(Box<Expr>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Bool(bool),
+    Int(i64),
+    Filesize,
+    Entrypoint,
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(CmpOp, Box<Expr>, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    BitNot(Box<Expr>),
+    // 以下usize为字符串在规则中的下标
+    StringMatch(usize),
+    StringAt(usize, Box<Expr>),
+    StringIn(usize, Box<Expr>, Box<Expr>),
+    StringCount(usize),
+    StringOffset(usize, Box<Expr>),
+    StringLength(usize, Box<Expr>),
+    ReadInt {
+        size: usize,
+        signed: bool,
+        big_endian: bool,
+        offset: Box<Expr>,
+    },
+    Of(Quantifier, Vec<usize>),
+    // 引用前面已定义的规则
+    RuleRef(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct StringDef {
+    pub identifier: String,
+    pub regex: Regex,
+    pub fullword: bool,
+    pub wide: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub tags: Vec<String>,
+    pub private: bool,
+    pub meta: Vec<(String, serde_json::Value)>,
+    pub strings: Vec<StringDef>,
+    pub condition: Expr,
+}
+
+struct Parser<'a> {
+    tokens: &'a [Spanned],
+    position: usize,
+    // 已解析的规则名称，用于规则间引用
+    rule_names: Vec<String>,
+    strings: Vec<StringDef>,
+}
+
+fn escape_byte(byte: u8, nocase: bool) -> String {
+    if nocase && byte.is_ascii_alphabetic() {
+        return format!(
+            "[\\x{:02X}\\x{:02X}]",
+            byte.to_ascii_lowercase(),
+            byte.to_ascii_uppercase()
+        );
+    }
+    format!("\\x{:02X}", byte)
+}
+
+fn build_regex(pattern: &str, line: usize) -> Result<Regex, RuleError> {
+    RegexBuilder::new(pattern)
+        .unicode(false)
+        .build()
+        .map_err(|err| RuleError {
+            line,
+            message: format!("正则表达式错误: {}", err),
+        })
+}
+
+//十六进制串转换为字节正则：?? 通配、半字节通配、[n-m] 跳转、(A|B) 候选
+fn hex_to_regex(hex: &str, line: usize) -> Result<String, RuleError> {
+    let error = |message: String| RuleError { line, message };
+    let chars = hex
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let mut pattern = String::from("(?s)");
+    let mut index = 0;
+    let mut depth = 0;
+    let mut has_byte = false;
+    while index < chars.len() {
+        match chars[index] {
+            '[' => {
+                let end = chars[index..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|end| index + end)
+                    .ok_or_else(|| error("十六进制串中的跳转未闭合".to_string()))?;
+                let range = chars[index + 1..end].iter().collect::<String>();
+                let parse = |value: &str| {
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| error(format!("无效的跳转: [{}]", range)))
+                };
+                let jump = match range.split_once('-') {
+                    None => format!(".{{{}}}", parse(&range)?),
+                    Some(("", "")) => ".*?".to_string(),
+                    Some((low, "")) => format!(".{{{},}}?", parse(low)?),
+                    Some((low, high)) => {
+                        let (low, high) = (parse(low)?, parse(high)?);
+                        if low > high {
+                            return Err(error(format!("无效的跳转: [{}]", range)));
+                        }
+                        format!(".{{{},{}}}?", low, high)
+                    }
+                };
+                if !has_byte {
+                    return Err(error("十六进制串不能以跳转开头".to_string()));
+                }
+                pattern.push_str(&jump);
+                index = end + 1;
+            }
+            '(' => {
+                pattern.push_str("(?:");
+                depth += 1;
+                index += 1;
+            }
+            '|' if depth > 0 => {
+                pattern.push('|');
+                index += 1;
+            }
+            ')' if depth > 0 => {
+                pattern.push(')');
+                depth -= 1;
+                index += 1;
+            }
+            high => {
+                let low = *chars
+                    .get(index + 1)
+                    .ok_or_else(|| error("十六进制串长度必须为偶数".to_string()))?;
+                let digit = |c: char| {
+                    c.to_digit(16)
+                        .map(|value| value as u8)
+                        .ok_or_else(|| error(format!("无效的十六进制字符: {}", c)))
+                };
+                let part = match (high, low) {
+                    ('?', '?') => ".".to_string(),
+                    ('?', low) => {
+                        let low = digit(low)?;
+                        format!(
+                            "[{}]",
+                            (0..16)
+                                .map(|high| format!("\\x{:02X}", high << 4 | low))
+                                .collect::<String>()
+                        )
+                    }
+                    (high, '?') => {
+                        let high = digit(high)?;
+                        format!("[\\x{:02X}-\\x{:02X}]", high << 4, high << 4 | 0x0F)
+                    }
+                    (high, low) => escape_byte(digit(high)? << 4 | digit(low)?, false),
+                };
+                pattern.push_str(&part);
+                has_byte = true;
+                index += 2;
+            }
+        }
+    }
+    if depth != 0 {
+        return Err(error("十六进制串中的括号不匹配".to_string()));
+    }
+    if !has_byte {
+        return Err(error("十六进制串不能为空".to_string()));
+    }
+    Ok(pattern)
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|spanned| &spanned.token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |spanned| spanned.line)
+    }
+
+    fn error(&self, message: impl Into<String>) -> RuleError {
+        RuleError {
+            line: self.line(),
+            message: message.into(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, RuleError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("规则意外结束"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), RuleError> {
+        let token = self.next()?;
+        if token != expected {
+            self.position -= 1;
+            return Err(self.error(format!("期望 {:?}, 实际为 {:?}", expected, token)));
+        }
+        Ok(())
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn identifier(&mut self) -> Result<String, RuleError> {
+        match self.next()? {
+            Token::Ident(ident) => Ok(ident),
+            token => {
+                self.position -= 1;
+                Err(self.error(format!("期望标识符, 实际为 {:?}", token)))
+            }
+        }
+    }
+
+    fn rule(&mut self) -> Result<Rule, RuleError> {
+        let mut private = false;
+        loop {
+            if self.eat_keyword("private") {
+                private = true;
+            } else if !self.eat_keyword("global") {
+                break;
+            }
+        }
+        if !self.eat_keyword("rule") {
+            return Err(self.error("期望关键字 rule"));
+        }
+        let name = self.identifier()?;
+        if self.rule_names.contains(&name) {
+            return Err(self.error(format!("规则名称重复: {}", name)));
+        }
+        let mut tags = Vec::new();
+        if self.peek() == Some(&Token::Colon) {
+            self.position += 1;
+            while let Some(Token::Ident(tag)) = self.peek() {
+                tags.push(tag.clone());
+                self.position += 1;
+            }
+        }
+        self.expect(Token::LBrace)?;
+        self.strings = Vec::new();
+        let mut meta = Vec::new();
+        if self.eat_keyword("meta") {
+            self.expect(Token::Colon)?;
+            while !self.is_keyword("strings") && !self.is_keyword("condition") {
+                let key = self.identifier()?;
+                self.expect(Token::Assign)?;
+                let value = match self.next()? {
+                    Token::Text(text) => {
+                        serde_json::Value::String(String::from_utf8_lossy(&text).to_string())
+                    }
+                    Token::Int(value) => serde_json::Value::from(value),
+                    Token::Minus => match self.next()? {
+                        Token::Int(value) => serde_json::Value::from(-value),
+                        _ => return Err(self.error("无效的meta值")),
+                    },
+                    Token::Ident(value) if value == "true" || value == "false" => {
+                        serde_json::Value::Bool(value == "true")
+                    }
+                    _ => return Err(self.error("无效的meta值")),
+                };
+                meta.push((key, value));
+            }
+        }
+        if self.eat_keyword("strings") {
+            self.expect(Token::Colon)?;
+            while !self.is_keyword("condition") {
+                let definition = self.string_definition()?;
+                if self
+                    .strings
+                    .iter()
+                    .any(|item| item.identifier == definition.identifier)
+                {
+                    return Err(self.error(format!("字符串名称重复: ${}", definition.identifier)));
+                }
+                self.strings.push(definition);
+            }
+        }
+        if !self.eat_keyword("condition") {
+            return Err(self.error("缺少condition部分"));
+        }
+        self.expect(Token::Colon)?;
+        let condition = self.expression()?;
+        self.expect(Token::RBrace)?;
+        self.rule_names.push(name.clone());
+        Ok(Rule {
+            name,
+            tags,
+            private,
+            meta,
+            strings: std::mem::take(&mut self.strings),
+            condition,
+        })
+    }
+
+    fn string_definition(&mut self) -> Result<StringDef, RuleError> {
+        let line = self.line();
+        let identifier = match self.next()? {
+            Token::StringId(identifier) if !identifier.is_empty() => identifier,
+            _ => {
+                self.position -= 1;
+                return Err(self.error("期望字符串定义, 如 $a = \"text\""));
+            }
+        };
+        self.expect(Token::Assign)?;
+        let value = self.next()?;
+        let mut modifiers: Vec<String> = Vec::new();
+        while let Some(Token::Ident(modifier)) = self.peek() {
+            if !["ascii", "wide", "nocase", "fullword", "private"].contains(&modifier.as_str()) {
+                break;
+            }
+            modifiers.push(modifier.clone());
+            self.position += 1;
+        }
+        let has = |name: &str| modifiers.iter().any(|modifier| modifier == name);
+        let wide = has("wide");
+        let ascii = has("ascii") || !wide;
+        let nocase = has("nocase");
+        let pattern = match value {
+            Token::Text(text) => {
+                if text.is_empty() {
+                    return Err(RuleError {
+                        line,
+                        message: "字符串不能为空".to_string(),
+                    });
+                }
+                let mut alternatives = Vec::new();
+                if ascii {
+                    alternatives.push(
+                        text.iter()
+                            .map(|byte| escape_byte(*byte, nocase))
+                            .collect::<String>(),
+                    );
+                }
+                if wide {
+                    alternatives.push(
+                        text.iter()
+                            .map(|byte| format!("{}\\x00", escape_byte(*byte, nocase)))
+                            .collect::<String>(),
+                    );
+                }
+                format!("(?:{})", alternatives.join("|"))
+            }
+            Token::Regex { pattern, flags } => {
+                if wide {
+                    return Err(RuleError {
+                        line,
+                        message: "正则表达式不支持wide修饰符".to_string(),
+                    });
+                }
+                let mut prefix = String::new();
+                if nocase || flags.contains('i') {
+                    prefix.push('i');
+                }
+                if flags.contains('s') {
+                    prefix.push('s');
+                }
+                if prefix.is_empty() {
+                    pattern
+                } else {
+                    format!("(?{}){}", prefix, pattern)
+                }
+            }
+            Token::Hex(hex) => {
+                if !modifiers.iter().all(|modifier| modifier == "private") {
+                    return Err(RuleError {
+                        line,
+                        message: "十六进制串不支持修饰符".to_string(),
+                    });
+                }
+                hex_to_regex(&hex, line)?
+            }
+            token => {
+                return Err(RuleError {
+                    line,
+                    message: format!("无效的字符串值: {:?}", token),
+                })
+            }
+        };
+        Ok(StringDef {
+            identifier,
+            regex: build_regex(&pattern, line)?,
+            fullword: has("fullword"),
+            wide,
+        })
+    }
+
+    //标识符已被读取，错误行号使用该标识符所在的行
+    fn string_index(&self, identifier: &str) -> Result<usize, RuleError> {
+        self.strings
+            .iter()
+            .position(|item| item.identifier == identifier)
+            .ok_or_else(|| RuleError {
+                line: self.tokens[self.position - 1].line,
+                message: format!("未定义的字符串: ${}", identifier),
+            })
+    }
+
+    fn expression(&mut self) -> Result<Expr, RuleError> {
+        let mut left = self.and_expression()?;
+        while self.eat_keyword("or") {
+            let right = self.and_expression()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_expression(&mut self) -> Result<Expr, RuleError> {
+        let mut left = self.not_expression()?;
+        while self.eat_keyword("and") {
+            let right = self.not_expression()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn not_expression(&mut self) -> Result<Expr, RuleError> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not_expression()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, RuleError> {
+        let left = self.bit_or()?;
+        let op = match self.peek() {
+            Some(Token::Eq) => CmpOp::Eq,
+            Some(Token::Ne) => CmpOp::Ne,
+            Some(Token::Lt) => CmpOp::Lt,
+            Some(Token::Le) => CmpOp::Le,
+            Some(Token::Gt) => CmpOp::Gt,
+            Some(Token::Ge) => CmpOp::Ge,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        let right = self.bit_or()?;
+        Ok(Expr::Cmp(op, Box::new(left), Box::new(right)))
+    }
+
+    //按优先级从低到高: | ^ & 移位 加减 乘除
+    fn binary_level(&mut self, level: usize) -> Result<Expr, RuleError> {
+        const LEVELS: [&[(Token, BinOp)]; 6] = [
+            &[(Token::Pipe, BinOp::BitOr)],
+            &[(Token::Caret, BinOp::BitXor)],
+            &[(Token::Ampersand, BinOp::BitAnd)],
+            &[(Token::Shl, BinOp::Shl), (Token::Shr, BinOp::Shr)],
+            &[(Token::Plus, BinOp::Add), (Token::Minus, BinOp::Sub)],
+            &[
+                (Token::Star, BinOp::Mul),
+                (Token::Backslash, BinOp::Div),
+                (Token::Percent, BinOp::Mod),
+            ],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary_level(level + 1)?;
+        while let Some(op) = self.peek().and_then(|token| {
+            LEVELS[level]
+                .iter()
+                .find(|(candidate, _)| candidate == token)
+                .map(|(_, op)| *op)
+        }) {
+            self.position += 1;
+            let right = self.binary_level(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn bit_or(&mut self) -> Result<Expr, RuleError> {
+        self.binary_level(0)
+    }
+
+    fn unary(&mut self) -> Result<Expr, RuleError> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.position += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some(Token::Tilde) => {
+                self.position += 1;
+                Ok(Expr::BitNot(Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn range(&mut self) -> Result<(Expr, Expr), RuleError> {
+        self.expect(Token::LParen)?;
+        let low = self.bit_or()?;
+        self.expect(Token::DotDot)?;
+        let high = self.bit_or()?;
+        self.expect(Token::RParen)?;
+        Ok((low, high))
+    }
+
+    fn index(&mut self) -> Result<Expr, RuleError> {
+        if self.peek() != Some(&Token::LBracket) {
+            return Ok(Expr::Int(1));
+        }
+        self.position += 1;
+        let index = self.bit_or()?;
+        self.expect(Token::RBracket)?;
+        Ok(index)
+    }
+
+    //of 后面的字符串集合: them 或 ($a, $b*, ...)
+    fn string_set(&mut self) -> Result<Vec<usize>, RuleError> {
+        if self.eat_keyword("them") {
+            if self.strings.is_empty() {
+                return Err(self.error("规则中没有定义字符串"));
+            }
+            return Ok((0..self.strings.len()).collect());
+        }
+        self.expect(Token::LParen)?;
+        let mut set = Vec::new();
+        loop {
+            match self.next()? {
+                Token::StringId(identifier) => set.push(self.string_index(&identifier)?),
+                Token::StringWildcard(prefix) => {
+                    let matched = self
+                        .strings
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, item)| item.identifier.starts_with(&prefix))
+                        .map(|(index, _)| index)
+                        .collect::<Vec<_>>();
+                    if matched.is_empty() {
+                        return Err(self.error(format!("没有匹配的字符串: ${}*", prefix)));
+                    }
+                    set.extend(matched);
+                }
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("期望字符串标识符"));
+                }
+            }
+            match self.next()? {
+                Token::Comma => continue,
+                Token::RParen => break,
+                _ => {
+                    self.position -= 1;
+                    return Err(self.error("期望 , 或 )"));
+                }
+            }
+        }
+        set.sort_unstable();
+        set.dedup();
+        Ok(set)
+    }
+
+    fn primary(&mut self) -> Result<Expr, RuleError> {
+        let token = self.next()?;
+        let expr = match token {
+            Token::Int(value) => {
+                if self.eat_keyword("of") {
+                    return Ok(Expr::Of(
+                        Quantifier::Count(Box::new(Expr::Int(value))),
+                        self.string_set()?,
+                    ));
+                }
+                Expr::Int(value)
+            }
+            Token::LParen => {
+                let expr = self.expression()?;
+                self.expect(Token::RParen)?;
+                expr
+            }
+            Token::StringId(identifier) => {
+                let index = self.string_index(&identifier)?;
+                if self.eat_keyword("at") {
+                    Expr::StringAt(index, Box::new(self.bit_or()?))
+                } else if self.eat_keyword("in") {
+                    let (low, high) = self.range()?;
+                    Expr::StringIn(index, Box::new(low), Box::new(high))
+                } else {
+                    Expr::StringMatch(index)
+                }
+            }
+            Token::StringCount(identifier) => Expr::StringCount(self.string_index(&identifier)?),
+            Token::StringOffset(identifier) => {
+                let index = self.string_index(&identifier)?;
+                Expr::StringOffset(index, Box::new(self.index()?))
+            }
+            Token::StringLength(identifier) => {
+                let index = self.string_index(&identifier)?;
+                Expr::StringLength(index, Box::new(self.index()?))
+            }
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Expr::Bool(true),
+                "false" => Expr::Bool(false),
+                "filesize" => Expr::Filesize,
+                "entrypoint" => Expr::Entrypoint,
+                "all" | "any" | "none" => {
+                    if !self.eat_keyword("of") {
+                        return Err(self.error(format!("期望 {} of", ident)));
+                    }
+                    let quantifier = match ident.as_str() {
+                        "all" => Quantifier::All,
+                        "any" => Quantifier::Any,
+                        _ => Quantifier::None,
+                    };
+                    Expr::Of(quantifier, self.string_set()?)
+                }
+                _ => {
+                    if let Some(read) = read_function(&ident) {
+                        self.expect(Token::LParen)?;
+                        let offset = self.bit_or()?;
+                        self.expect(Token::RParen)?;
+                        let (size, signed, big_endian) = read;
+                        Expr::ReadInt {
+                            size,
+                            signed,
+                            big_endian,
+                            offset: Box::new(offset),
+                        }
+                    } else if let Some(index) =
+                        self.rule_names.iter().position(|name| *name == ident)
+                    {
+                        Expr::RuleRef(index)
+                    } else {
+                        self.position -= 1;
+                        return Err(self.error(format!("未知的标识符: {}", ident)));
+                    }
+                }
+            },
+            token => {
+                self.position -= 1;
+                return Err(self.error(format!("无效的表达式: {:?}", token)));
+            }
+        };
+        Ok(expr)
+    }
+}
+
+//uint16(0)、int32be(x) 之类的读取函数: (字节数, 有符号, 大端)
+fn read_function(name: &str) -> Option<(usize, bool, bool)> {
+    let (name, big_endian) = match name.strip_suffix("be") {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let (bits, signed) = match name.strip_prefix("uint") {
+        Some(bits) => (bits, false),
+        None => (name.strip_prefix("int")?, true),
+    };
+    let size = match bits {
+        "8" => 1,
+        "16" => 2,
+        "32" => 4,
+        _ => return None,
+    };
+    Some((size, signed, big_endian))
+}
+
+pub fn parse(tokens: &[Spanned]) -> Result<Vec<Rule>, RuleError> {
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        rule_names: Vec::new(),
+        strings: Vec::new(),
+    };
+    let mut rules = Vec::new();
+    while parser.peek().is_some() {
+        rules.push(parser.rule()?);
+    }
+    if rules.is_empty() {
+        return Err(RuleError {
+            line: 1,
+            message: "没有定义任何规则".to_string(),
+        });
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::yara::{compile, scan};
+
+    fn condition(source: &str) -> Expr {
+        compile(&format!("rule test {{ condition: {} }}", source))
+            .unwrap()
+            .remove(0)
+            .condition
+    }
+
+    fn hex_regex(hex: &str) -> Regex {
+        build_regex(&hex_to_regex(hex, 1).unwrap(), 1).unwrap()
+    }
+
+    #[test]
+    fn hex_wildcards() {
+        let regex = hex_regex("4D ?? ?0 A?");
+        assert!(regex.is_match(&[0x4D, 0xFF, 0x30, 0xA7]));
+        assert!(!regex.is_match(&[0x4D, 0xFF, 0x31, 0xA7]));
+        assert!(!regex.is_match(&[0x4D, 0xFF, 0x30, 0xB7]));
+        // ??也匹配换行
+        assert!(regex.is_match(&[0x4D, 0x0A, 0x00, 0xA0]));
+    }
+
+    #[test]
+    fn hex_jumps_and_alternatives() {
+        let regex = hex_regex("4D [2-3] 5A (90 | CC ??)");
+        assert!(regex.is_match(&[0x4D, 1, 2, 0x5A, 0x90]));
+        assert!(regex.is_match(&[0x4D, 1, 2, 3, 0x5A, 0xCC, 0x00]));
+        assert!(!regex.is_match(&[0x4D, 1, 0x5A, 0x90]));
+        assert!(!regex.is_match(&[0x4D, 1, 2, 3, 4, 0x5A, 0x90]));
+        let regex = hex_regex("01 [2] 02 [-] 03");
+        assert!(regex.is_match(&[1, 9, 9, 2, 7, 7, 7, 3]));
+        assert!(!regex.is_match(&[1, 9, 2, 3]));
+    }
+
+    #[test]
+    fn malformed_hex() {
+        for hex in [
+            "4D 5",
+            "[2] 4D",
+            "4D [3-1] 5A",
+            "4D [x] 5A",
+            "(4D",
+            "4D [2",
+            "XY",
+            "",
+        ] {
+            assert!(hex_to_regex(hex, 1).is_err(), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn boolean_precedence() {
+        // and 优先于 or
+        assert!(matches!(
+            condition("true or false and false"),
+            Expr::Or(left, right)
+                if matches!(*left, Expr::Bool(true)) && matches!(*right, Expr::And(_, _))
+        ));
+        // not 只作用于紧随的表达式
+        assert!(matches!(
+            condition("not true and false"),
+            Expr::And(left, _) if matches!(*left, Expr::Not(_))
+        ));
+    }
+
+    #[test]
+    fn arithmetic_precedence() {
+        let Expr::Cmp(CmpOp::Eq, left, _) = condition("1 + 2 * 3 == 7") else {
+            panic!("expected comparison");
+        };
+        assert!(matches!(
+            *left,
+            Expr::Binary(BinOp::Add, _, right) if matches!(*right, Expr::Binary(BinOp::Mul, _, _))
+        ));
+        let Expr::Binary(BinOp::BitOr, _, right) = condition("1 | 2 & 3") else {
+            panic!("expected bit or");
+        };
+        assert!(matches!(*right, Expr::Binary(BinOp::BitAnd, _, _)));
+    }
+
+    #[test]
+    fn evaluates_conditions() {
+        let rules = compile(
+            r#"
+            private rule mz { condition: uint16(0) == 0x5A4D }
+            rule test : tag {
+                meta:
+                    author = "x"
+                strings:
+                    $a = "abc" nocase
+                    $b = { 61 ?? 63 }
+                condition:
+                    mz and #a == 2 and @a[2] == 7 and $b at 2 and all of them
+            }
+            "#,
+        )
+        .unwrap();
+        let matches = scan(&rules, b"MZabcxxABC", None);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rule, "test");
+        assert_eq!(matches[0].tags, vec!["tag".to_string()]);
+        assert!(scan(&rules, b"ZMabcxxABC", None).is_empty());
+    }
+
+    #[test]
+    fn malformed_rules_report_line() {
+        let err = compile("rule a {\n  strings:\n    $a = \"x\"\n}").unwrap_err();
+        assert_eq!(err.line, 4);
+        let err = compile("rule a {\n condition:\n  $b\n}").unwrap_err();
+        assert_eq!(err.line, 3);
+        for source in [
+            "",
+            "rule { condition: true }",
+            "rule a { condition: true",
+            "rule a { condition: true } rule a { condition: true }",
+            "rule a { strings: $a = \"\" condition: $a }",
+            "rule a { strings: $a = { 4D } wide condition: $a }",
+            "rule a { condition: 1 + }",
+            "rule a { condition: unknown }",
+        ] {
+            assert!(compile(source).is_err(), "{}", source);
+        }
+    }
+}
@@ -2,6 +2,14 @@
 
 pub mod prelude;
 
+pub mod t_attack_technique;
+pub mod t_capability;
 pub mod t_file;
+pub mod t_file_analysis;
+pub mod t_hash_list;
+pub mod t_ioc;
 pub mod t_knowledge;
+pub mod t_knowledge_history;
+pub mod t_risk_weight;
+pub mod t_rule;
 pub mod t_test;
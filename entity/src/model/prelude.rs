@@ -1,5 +1,13 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.6
 
+pub use super::t_attack_technique::Entity as TAttackTechnique;
+pub use super::t_capability::Entity as TCapability;
 pub use super::t_file::Entity as TFile;
+pub use super::t_file_analysis::Entity as TFileAnalysis;
+pub use super::t_hash_list::Entity as THashList;
+pub use super::t_ioc::Entity as TIoc;
 pub use super::t_knowledge::Entity as TKnowledge;
+pub use super::t_knowledge_history::Entity as TKnowledgeHistory;
+pub use super::t_risk_weight::Entity as TRiskWeight;
+pub use super::t_rule::Entity as TRule;
 pub use super::t_test::Entity as TTest;
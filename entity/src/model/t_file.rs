@@ -16,6 +16,10 @@ pub struct Model {
     pub file_report: Option<Vec<u8>>,
     pub create_time: DateTime,
     pub modify_time: DateTime,
+    pub parent_id: Option<String>,
+    pub version: i32,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub file_patch: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -11,6 +11,13 @@ pub struct Model {
     pub func_name: String,
     pub func_desc: Option<String>,
     pub is_sensitive: bool,
+    pub technique_ids: Option<String>,
+    pub tactics: Option<String>,
+    pub severity: String,
+    pub categories: Option<String>,
+    pub enabled: bool,
+    pub match_mode: String,
+    pub dll_name: Option<String>,
     pub create_time: DateTime,
     pub modify_time: DateTime,
 }
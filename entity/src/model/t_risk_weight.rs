@@ -1,15 +1,15 @@
-//! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.10
+//! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.6
 
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
-#[sea_orm(table_name = "knowledge_base")]
+#[sea_orm(table_name = "t_risk_weight")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
-    pub id: String,
-    pub name_hex: Option<String>,
-    pub description: Option<String>,
+    pub weight_key: String,
+    pub weight_value: i64,
+    pub modify_time: DateTime,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,50 @@
+use crate::create_t_file::TFile;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 已上传的文件都是第1版，没有父文件
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TFile::Table)
+                    .add_column_if_not_exists(ColumnDef::new(TFile::ParentId).string())
+                    .add_column_if_not_exists(
+                        ColumnDef::new(TFile::Version)
+                            .integer()
+                            .default(1)
+                            .not_null(),
+                    )
+                    .add_column_if_not_exists(ColumnDef::new(TFile::FilePatch).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_t_file_parent_id")
+                    .table(TFile::Table)
+                    .col(TFile::ParentId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TFile::Table)
+                    .drop_column(TFile::ParentId)
+                    .drop_column(TFile::Version)
+                    .drop_column(TFile::FilePatch)
+                    .to_owned(),
+            )
+            .await
+    }
+}
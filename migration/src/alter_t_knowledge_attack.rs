@@ -0,0 +1,63 @@
+use crate::create_t_knowledge::TKnowledge;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 启动时会重新执行全部迁移，只在首次添加字段时补充标签，避免覆盖用户清空的标签
+        let first_run = !manager
+            .has_column(
+                TKnowledge::Table.to_string(),
+                TKnowledge::TechniqueIds.to_string(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TKnowledge::Table)
+                    .add_column_if_not_exists(ColumnDef::new(TKnowledge::TechniqueIds).string())
+                    .add_column_if_not_exists(ColumnDef::new(TKnowledge::Tactics).string())
+                    .to_owned(),
+            )
+            .await?;
+        if !first_run {
+            return Ok(());
+        }
+        // 为内置知识补充ATT&CK标签，只更新尚未设置过的数据
+        let init_system_tags = vec![
+            ("1", "T1057"),
+            ("2", "T1071"),
+            ("3", "T1106"),
+            ("5", "T1055"),
+            ("6", "T1134"),
+            ("8", "T1543.003"),
+            ("9", "T1562.001"),
+            ("11", "T1552.004"),
+        ];
+        for (id, technique_ids) in init_system_tags {
+            let update = Query::update()
+                .table(TKnowledge::Table)
+                .value(TKnowledge::TechniqueIds, technique_ids)
+                .and_where(Expr::col(TKnowledge::Id).eq(id))
+                .and_where(Expr::col(TKnowledge::TechniqueIds).is_null())
+                .to_owned();
+            manager.exec_stmt(update).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TKnowledge::Table)
+                    .drop_column(TKnowledge::TechniqueIds)
+                    .drop_column(TKnowledge::Tactics)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,38 @@
+use crate::create_t_knowledge::TKnowledge;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 已有数据默认按精确匹配处理，并自动匹配A/W/Ex后缀
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TKnowledge::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(TKnowledge::MatchMode)
+                            .string()
+                            .default("exact_variants")
+                            .not_null(),
+                    )
+                    .add_column_if_not_exists(ColumnDef::new(TKnowledge::DllName).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TKnowledge::Table)
+                    .drop_column(TKnowledge::MatchMode)
+                    .drop_column(TKnowledge::DllName)
+                    .to_owned(),
+            )
+            .await
+    }
+}
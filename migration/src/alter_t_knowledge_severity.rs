@@ -0,0 +1,83 @@
+use crate::create_t_knowledge::TKnowledge;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TKnowledge::Table)
+                    .add_column_if_not_exists(ColumnDef::new(TKnowledge::Severity).string())
+                    .add_column_if_not_exists(ColumnDef::new(TKnowledge::Categories).string())
+                    .add_column_if_not_exists(
+                        ColumnDef::new(TKnowledge::Enabled)
+                            .boolean()
+                            .default(true)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // 为内置知识设置严重级别与分类，只更新尚未设置过的数据
+        let init_system_levels = vec![
+            ("1", "low", "进程"),
+            ("2", "medium", "网络"),
+            ("3", "medium", "进程"),
+            ("4", "low", "同步"),
+            ("5", "critical", "进程注入"),
+            ("6", "high", "权限"),
+            ("7", "medium", "加密"),
+            ("8", "high", "服务,持久化"),
+            ("9", "high", "防御规避"),
+            ("10", "medium", "驱动"),
+            ("11", "medium", "凭据"),
+        ];
+        for (id, severity, categories) in init_system_levels {
+            let update = Query::update()
+                .table(TKnowledge::Table)
+                .value(TKnowledge::Severity, severity)
+                .value(TKnowledge::Categories, categories)
+                .and_where(Expr::col(TKnowledge::Id).eq(id))
+                .and_where(Expr::col(TKnowledge::Severity).is_null())
+                .to_owned();
+            manager.exec_stmt(update).await?;
+        }
+        // 已有的自定义知识默认为中危
+        let update = Query::update()
+            .table(TKnowledge::Table)
+            .value(TKnowledge::Severity, "medium")
+            .and_where(Expr::col(TKnowledge::Severity).is_null())
+            .to_owned();
+        manager.exec_stmt(update).await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TKnowledge::Table)
+                    .modify_column(
+                        ColumnDef::new(TKnowledge::Severity)
+                            .string()
+                            .default("medium")
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TKnowledge::Table)
+                    .drop_column(TKnowledge::Severity)
+                    .drop_column(TKnowledge::Categories)
+                    .drop_column(TKnowledge::Enabled)
+                    .to_owned(),
+            )
+            .await
+    }
+}
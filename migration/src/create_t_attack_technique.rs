@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TAttackTechnique::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TAttackTechnique::TechniqueId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TAttackTechnique::TechniqueName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TAttackTechnique::ParentId).string())
+                    .col(
+                        ColumnDef::new(TAttackTechnique::Tactics)
+                            .string()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TAttackTechnique::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TAttackTechnique {
+    Table,
+    TechniqueId,
+    TechniqueName,
+    ParentId,
+    Tactics,
+}
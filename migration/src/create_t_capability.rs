@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TCapability::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TCapability::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TCapability::CapName).string().not_null())
+                    .col(ColumnDef::new(TCapability::TechniqueId).string().not_null())
+                    .col(ColumnDef::new(TCapability::CapDesc).string())
+                    .col(ColumnDef::new(TCapability::Scope).string().not_null())
+                    .col(
+                        ColumnDef::new(TCapability::RuleContent)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TCapability::CreateTime)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TCapability::ModifyTime)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TCapability::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TCapability {
+    Table,
+    Id,
+    CapName,
+    TechniqueId,
+    CapDesc,
+    Scope,
+    RuleContent,
+    CreateTime,
+    ModifyTime,
+}
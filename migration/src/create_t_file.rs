@@ -31,13 +31,16 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum TFile {
+pub enum TFile {
     Table,
     Id,
     FileName,
     FileMd5,
     FileBuf,
     FileReport,
+    ParentId,
+    Version,
+    FilePatch,
     CreateTime,
     ModifyTime,
 }
@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TFileAnalysis::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TFileAnalysis::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TFileAnalysis::FileId).string().not_null())
+                    .col(ColumnDef::new(TFileAnalysis::Analyzer).string().not_null())
+                    .col(
+                        ColumnDef::new(TFileAnalysis::Result)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TFileAnalysis::CreateTime)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TFileAnalysis::ModifyTime)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_t_file_analysis_file_id_analyzer")
+                    .table(TFileAnalysis::Table)
+                    .col(TFileAnalysis::FileId)
+                    .col(TFileAnalysis::Analyzer)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TFileAnalysis::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TFileAnalysis {
+    Table,
+    Id,
+    FileId,
+    Analyzer,
+    Result,
+    CreateTime,
+    ModifyTime,
+}
@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(THashList::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(THashList::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(THashList::HashType).string().not_null())
+                    .col(ColumnDef::new(THashList::HashValue).string().not_null())
+                    .col(ColumnDef::new(THashList::ListType).string().not_null())
+                    .col(ColumnDef::new(THashList::Reason).text())
+                    .col(ColumnDef::new(THashList::CreateTime).timestamp().not_null())
+                    .col(ColumnDef::new(THashList::ModifyTime).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        // 同一个哈希只能属于一个名单
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .unique()
+                    .name("idx_t_hash_list_type_value")
+                    .table(THashList::Table)
+                    .col(THashList::HashType)
+                    .col(THashList::HashValue)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(THashList::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum THashList {
+    Table,
+    Id,
+    HashType,
+    HashValue,
+    ListType,
+    Reason,
+    CreateTime,
+    ModifyTime,
+}
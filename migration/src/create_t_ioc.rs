@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TIoc::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TIoc::Id).string().not_null().primary_key())
+                    .col(ColumnDef::new(TIoc::FileId).string().not_null())
+                    .col(ColumnDef::new(TIoc::IocType).string().not_null())
+                    .col(ColumnDef::new(TIoc::Value).text().not_null())
+                    .col(ColumnDef::new(TIoc::Offset).big_integer().not_null())
+                    .col(ColumnDef::new(TIoc::Encoding).string().not_null())
+                    .col(ColumnDef::new(TIoc::CreateTime).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_t_ioc_file_id")
+                    .table(TIoc::Table)
+                    .col(TIoc::FileId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_t_ioc_type_value")
+                    .table(TIoc::Table)
+                    .col(TIoc::IocType)
+                    .col(TIoc::Value)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TIoc::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TIoc {
+    Table,
+    Id,
+    FileId,
+    IocType,
+    Value,
+    Offset,
+    Encoding,
+    CreateTime,
+}
@@ -54,6 +54,13 @@ pub enum TKnowledge {
     FuncName,
     FuncDesc,
     IsSensitive,
+    TechniqueIds,
+    Tactics,
+    Severity,
+    Categories,
+    Enabled,
+    MatchMode,
+    DllName,
     CreateTime,
     ModifyTime,
 }
@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TKnowledgeHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TKnowledgeHistory::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TKnowledgeHistory::KnowledgeId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TKnowledgeHistory::Operation)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TKnowledgeHistory::Operator)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TKnowledgeHistory::OldValue).json_binary())
+                    .col(ColumnDef::new(TKnowledgeHistory::NewValue).json_binary())
+                    .col(
+                        ColumnDef::new(TKnowledgeHistory::CreateTime)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_t_knowledge_history_knowledge_id")
+                    .table(TKnowledgeHistory::Table)
+                    .col(TKnowledgeHistory::KnowledgeId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TKnowledgeHistory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TKnowledgeHistory {
+    Table,
+    Id,
+    KnowledgeId,
+    Operation,
+    Operator,
+    OldValue,
+    NewValue,
+    CreateTime,
+}
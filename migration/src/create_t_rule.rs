@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TRule::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TRule::Id).string().not_null().primary_key())
+                    .col(ColumnDef::new(TRule::RuleName).string().not_null())
+                    .col(ColumnDef::new(TRule::RuleSource).text().not_null())
+                    .col(ColumnDef::new(TRule::RuleDesc).string())
+                    .col(ColumnDef::new(TRule::CreateTime).timestamp().not_null())
+                    .col(ColumnDef::new(TRule::ModifyTime).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TRule::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TRule {
+    Table,
+    Id,
+    RuleName,
+    RuleSource,
+    RuleDesc,
+    CreateTime,
+    ModifyTime,
+}
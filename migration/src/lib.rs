@@ -1,8 +1,24 @@
 pub use sea_orm_migration::prelude::*;
 
+mod alter_t_file_version;
+mod alter_t_knowledge_attack;
+mod alter_t_knowledge_match;
+mod alter_t_knowledge_severity;
+mod create_t_attack_technique;
+mod create_t_capability;
 mod create_t_file;
+mod create_t_file_analysis;
+mod create_t_hash_list;
+mod create_t_ioc;
 mod create_t_knowledge;
+mod create_t_knowledge_history;
+mod create_t_risk_weight;
+mod create_t_rule;
 mod create_t_test;
+mod migrate_knowledge_base;
+mod migrate_risk_weight_knowledge;
+mod seed_t_attack_technique;
+mod seed_t_capability;
 mod seed_t_knowledge;
 
 pub struct Migrator;
@@ -15,6 +31,22 @@ impl MigratorTrait for Migrator {
             Box::new(create_t_knowledge::Migration),
             Box::new(create_t_file::Migration),
             Box::new(seed_t_knowledge::Migration),
+            Box::new(create_t_file_analysis::Migration),
+            Box::new(create_t_ioc::Migration),
+            Box::new(create_t_rule::Migration),
+            Box::new(create_t_capability::Migration),
+            Box::new(seed_t_capability::Migration),
+            Box::new(create_t_attack_technique::Migration),
+            Box::new(seed_t_attack_technique::Migration),
+            Box::new(alter_t_knowledge_attack::Migration),
+            Box::new(create_t_risk_weight::Migration),
+            Box::new(alter_t_knowledge_severity::Migration),
+            Box::new(migrate_risk_weight_knowledge::Migration),
+            Box::new(alter_t_knowledge_match::Migration),
+            Box::new(create_t_knowledge_history::Migration),
+            Box::new(create_t_hash_list::Migration),
+            Box::new(migrate_knowledge_base::Migration),
+            Box::new(alter_t_file_version::Migration),
         ]
     }
 }
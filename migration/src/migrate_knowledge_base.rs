@@ -0,0 +1,158 @@
+use crate::create_t_knowledge::TKnowledge;
+use crate::create_t_knowledge_history::TKnowledgeHistory;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+//旧版知识库的函数名称以十六进制保存，解码失败时返回空
+fn decode_name_hex(name_hex: &str) -> Option<String> {
+    let name_hex = name_hex.trim();
+    if name_hex.is_empty() {
+        return None;
+    }
+    let bytes = (0..name_hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(name_hex.get(index..index + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    let name = String::from_utf8(bytes).ok()?;
+    let name = name.trim_end_matches('\0').trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    //把旧版knowledge_base表中的数据合并到t_knowledge，全部合并后删除旧表
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_table(KnowledgeBase::Table.to_string()).await? {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let select = Query::select()
+            .columns([
+                KnowledgeBase::Id,
+                KnowledgeBase::NameHex,
+                KnowledgeBase::Description,
+            ])
+            .from(KnowledgeBase::Table)
+            .to_owned();
+        let rows = db.query_all(builder.build(&select)).await?;
+        // 名称无法解码的数据保留在旧表中，避免丢失
+        let mut undecoded = 0;
+        for row in rows {
+            let id: String = row.try_get("", &KnowledgeBase::Id.to_string())?;
+            let name_hex: Option<String> = row.try_get("", &KnowledgeBase::NameHex.to_string())?;
+            let description: Option<String> =
+                row.try_get("", &KnowledgeBase::Description.to_string())?;
+            let Some(name) = name_hex.as_deref().and_then(decode_name_hex) else {
+                undecoded += 1;
+                continue;
+            };
+            // 已存在同名知识时保留现有数据，只删除旧表中的记录
+            let exists = Query::select()
+                .column(TKnowledge::Id)
+                .from(TKnowledge::Table)
+                .and_where(Expr::col(TKnowledge::FuncName).eq(name.as_str()))
+                .to_owned();
+            if db.query_one(builder.build(&exists)).await?.is_none() {
+                insert_knowledge(manager, &id, name, description).await?;
+            }
+            let delete = Query::delete()
+                .from_table(KnowledgeBase::Table)
+                .and_where(Expr::col(KnowledgeBase::Id).eq(id.as_str()))
+                .to_owned();
+            manager.exec_stmt(delete).await?;
+        }
+        if undecoded > 0 {
+            return Ok(());
+        }
+        manager
+            .drop_table(Table::drop().table(KnowledgeBase::Table).to_owned())
+            .await
+    }
+
+    //旧表已删除，无法回滚
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+//导入一条旧版知识，同时写入创建记录
+async fn insert_knowledge(
+    manager: &SchemaManager<'_>,
+    id: &str,
+    name: String,
+    description: Option<String>,
+) -> Result<(), DbErr> {
+    let knowledge_id = format!("legacy_{}", id);
+    let now = chrono::Local::now().naive_local();
+    let new_value = Expr::cust_with_values(
+        "jsonb_build_object('id', $1, 'func_name', $2, 'func_desc', $3, 'is_sensitive', $4)",
+        [
+            Value::from(knowledge_id.as_str()),
+            Value::from(name.as_str()),
+            Value::from(description.clone()),
+            Value::from(true),
+        ],
+    );
+    let insert = Query::insert()
+        .into_table(TKnowledge::Table)
+        .columns([
+            TKnowledge::Id,
+            TKnowledge::FuncName,
+            TKnowledge::FuncDesc,
+            TKnowledge::IsSensitive,
+            TKnowledge::ModifyTime,
+            TKnowledge::CreateTime,
+        ])
+        .values_panic([
+            knowledge_id.as_str().into(),
+            name.into(),
+            description.into(),
+            true.into(),
+            now.into(),
+            now.into(),
+        ])
+        .on_conflict(OnConflict::column(TKnowledge::Id).do_nothing().to_owned())
+        .to_owned();
+    manager.exec_stmt(insert).await?;
+    let history = Query::insert()
+        .into_table(TKnowledgeHistory::Table)
+        .columns([
+            TKnowledgeHistory::Id,
+            TKnowledgeHistory::KnowledgeId,
+            TKnowledgeHistory::Operation,
+            TKnowledgeHistory::Operator,
+            TKnowledgeHistory::NewValue,
+            TKnowledgeHistory::CreateTime,
+        ])
+        .values_panic([
+            knowledge_id.as_str().into(),
+            knowledge_id.as_str().into(),
+            "create".into(),
+            "migration".into(),
+            new_value,
+            now.into(),
+        ])
+        .on_conflict(
+            OnConflict::column(TKnowledgeHistory::Id)
+                .do_nothing()
+                .to_owned(),
+        )
+        .to_owned();
+    manager.exec_stmt(history).await
+}
+
+#[derive(DeriveIden)]
+enum KnowledgeBase {
+    Table,
+    Id,
+    NameHex,
+    Description,
+}
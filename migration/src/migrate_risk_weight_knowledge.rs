@@ -0,0 +1,47 @@
+use crate::create_t_risk_weight::TRiskWeight;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// 敏感函数权重按严重级别拆分前使用的名称
+const LEGACY_KNOWLEDGE_HIT: &str = "knowledge_hit";
+const KNOWLEDGE_MEDIUM: &str = "knowledge_medium";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    //旧的敏感函数权重转为中危权重，已单独设置过中危权重时保留现有值
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let select = Query::select()
+            .expr(Expr::val(KNOWLEDGE_MEDIUM))
+            .columns([TRiskWeight::WeightValue, TRiskWeight::ModifyTime])
+            .from(TRiskWeight::Table)
+            .and_where(Expr::col(TRiskWeight::WeightKey).eq(LEGACY_KNOWLEDGE_HIT))
+            .to_owned();
+        let insert = Query::insert()
+            .into_table(TRiskWeight::Table)
+            .columns([
+                TRiskWeight::WeightKey,
+                TRiskWeight::WeightValue,
+                TRiskWeight::ModifyTime,
+            ])
+            .select_from(select)
+            .map_err(|err| DbErr::Migration(err.to_string()))?
+            .on_conflict(
+                OnConflict::column(TRiskWeight::WeightKey)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .to_owned();
+        manager.exec_stmt(insert).await?;
+        let delete = Query::delete()
+            .from_table(TRiskWeight::Table)
+            .and_where(Expr::col(TRiskWeight::WeightKey).eq(LEGACY_KNOWLEDGE_HIT))
+            .to_owned();
+        manager.exec_stmt(delete).await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use crate::create_t_attack_technique::TAttackTechnique;
+use sea_orm_migration::prelude::*;
+
+// 离线打包的ATT&CK数据，每次启动时同步名称与战术
+const ATTACK_DATASET: &str = include_str!("data/attack_enterprise.tsv");
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for line in ATTACK_DATASET.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let value: Vec<&str> = line.split('\t').collect();
+            if value.len() != 3 {
+                continue;
+            }
+            let parent_id = value[0].split_once('.').map(|(parent, _)| parent);
+            let on_conflict = OnConflict::column(TAttackTechnique::TechniqueId)
+                .update_columns([
+                    TAttackTechnique::TechniqueName,
+                    TAttackTechnique::ParentId,
+                    TAttackTechnique::Tactics,
+                ])
+                .to_owned();
+            let insert = Query::insert()
+                .into_table(TAttackTechnique::Table)
+                .columns([
+                    TAttackTechnique::TechniqueId,
+                    TAttackTechnique::TechniqueName,
+                    TAttackTechnique::ParentId,
+                    TAttackTechnique::Tactics,
+                ])
+                .values_panic([
+                    value[0].into(),
+                    value[1].into(),
+                    parent_id.into(),
+                    value[2].into(),
+                ])
+                .on_conflict(on_conflict)
+                .to_owned();
+            manager.exec_stmt(insert).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let delete = Query::delete()
+            .from_table(TAttackTechnique::Table)
+            .to_owned();
+        manager.exec_stmt(delete).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use crate::create_t_capability::TCapability;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // (id, 名称, ATT&CK技术编号, 范围, 条件, 描述)
+        let init_system_rule = vec![
+            (
+                "1",
+                "进程注入",
+                "T1055",
+                "file",
+                r#"{"and":[{"api":"OpenProcess"},{"api":"VirtualAllocEx"},{"api":"WriteProcessMemory"},{"or":[{"api":"CreateRemoteThread"},{"api":"NtCreateThreadEx"},{"api":"RtlCreateUserThread"},{"api":"QueueUserAPC"}]}]}"#,
+                "在其他进程中申请内存、写入代码并创建远程线程执行",
+            ),
+            (
+                "2",
+                "进程镂空",
+                "T1055.012",
+                "file",
+                r#"{"and":[{"api":"CreateProcess"},{"or":[{"api":"NtUnmapViewOfSection"},{"api":"ZwUnmapViewOfSection"}]},{"api":"WriteProcessMemory"},{"api":"SetThreadContext"},{"api":"ResumeThread"}]}"#,
+                "以挂起方式创建进程，替换其内存映像后恢复执行",
+            ),
+            (
+                "3",
+                "键盘记录",
+                "T1056.001",
+                "function",
+                r#"{"or":[{"and":[{"api":"SetWindowsHookEx"},{"constant":13}]},{"n_of":{"count":2,"items":[{"api":"GetAsyncKeyState"},{"api":"GetKeyState"},{"api":"GetKeyboardState"},{"api":"MapVirtualKey"}]}}]}"#,
+                "安装低级键盘钩子或轮询按键状态记录用户输入",
+            ),
+            (
+                "4",
+                "屏幕截图",
+                "T1113",
+                "function",
+                r#"{"and":[{"or":[{"api":"GetDC"},{"api":"GetWindowDC"},{"api":"CreateDC"}]},{"api":"BitBlt"},{"or":[{"api":"CreateCompatibleBitmap"},{"api":"GetDIBits"}]}]}"#,
+                "复制屏幕设备上下文生成位图",
+            ),
+            (
+                "5",
+                "注册表Run键持久化",
+                "T1547.001",
+                "file",
+                r#"{"and":[{"or":[{"api":"RegSetValue"},{"api":"RegCreateKey"}]},{"string":"CurrentVersion\\Run"}]}"#,
+                "写入Run/RunOnce键实现开机自启动",
+            ),
+            (
+                "6",
+                "创建系统服务",
+                "T1543.003",
+                "function",
+                r#"{"and":[{"api":"OpenSCManager"},{"api":"CreateService"}]}"#,
+                "创建Windows服务实现持久化或加载驱动",
+            ),
+            (
+                "7",
+                "调试器检测",
+                "T1622",
+                "file",
+                r#"{"n_of":{"count":2,"items":[{"api":"IsDebuggerPresent"},{"api":"CheckRemoteDebuggerPresent"},{"api":"NtQueryInformationProcess"},{"api":"OutputDebugString"},{"string":"ollydbg"},{"string":"x64dbg"}]}}"#,
+                "检测调试器是否存在以对抗分析",
+            ),
+            (
+                "8",
+                "进程枚举",
+                "T1057",
+                "function",
+                r#"{"or":[{"and":[{"api":"CreateToolhelp32Snapshot"},{"api":"Process32First"},{"api":"Process32Next"}]},{"api":"EnumProcesses"}]}"#,
+                "遍历系统中正在运行的进程",
+            ),
+            (
+                "9",
+                "下载远程文件",
+                "T1105",
+                "file",
+                r#"{"or":[{"api":"URLDownloadToFile"},{"and":[{"api":"InternetOpen"},{"or":[{"api":"InternetOpenUrl"},{"api":"HttpSendRequest"}]},{"api":"InternetReadFile"}]}]}"#,
+                "通过HTTP等协议下载额外的载荷",
+            ),
+            (
+                "10",
+                "令牌权限调整",
+                "T1134",
+                "function",
+                r#"{"and":[{"api":"OpenProcessToken"},{"api":"LookupPrivilegeValue"},{"api":"AdjustTokenPrivileges"}]}"#,
+                "为当前进程启用调试等特权",
+            ),
+            (
+                "11",
+                "命令行执行",
+                "T1059.003",
+                "function",
+                r#"{"and":[{"or":[{"api":"CreateProcess"},{"api":"WinExec"},{"api":"ShellExecute"}]},{"string":"cmd.exe"}]}"#,
+                "调用cmd.exe执行命令",
+            ),
+            (
+                "12",
+                "删除卷影副本",
+                "T1490",
+                "file",
+                r#"{"or":[{"string":"vssadmin delete shadows"},{"string":"shadowcopy delete"}]}"#,
+                "删除系统卷影副本阻止数据恢复，勒索软件常见行为",
+            ),
+        ];
+        for (id, name, technique_id, scope, rule, desc) in init_system_rule {
+            let on_conflict = OnConflict::column(TCapability::Id).do_nothing().to_owned();
+            let insert = Query::insert()
+                .into_table(TCapability::Table)
+                .columns([
+                    TCapability::Id,
+                    TCapability::CapName,
+                    TCapability::TechniqueId,
+                    TCapability::CapDesc,
+                    TCapability::Scope,
+                    TCapability::RuleContent,
+                    TCapability::ModifyTime,
+                    TCapability::CreateTime,
+                ])
+                .values_panic([
+                    id.into(),
+                    name.into(),
+                    technique_id.into(),
+                    desc.into(),
+                    scope.into(),
+                    Expr::val(rule).cast_as(Alias::new("jsonb")),
+                    chrono::Local::now().naive_local().into(),
+                    chrono::Local::now().naive_local().into(),
+                ])
+                .on_conflict(on_conflict)
+                .to_owned();
+            manager.exec_stmt(insert).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let delete = Query::delete().from_table(TCapability::Table).to_owned();
+        manager.exec_stmt(delete).await?;
+        Ok(())
+    }
+}